@@ -0,0 +1,29 @@
+#!/usr/bin/env python3
+"""Example external bot: heads straight for the food, never turning back.
+
+Run it with `snaek_rust --headless --ticks 10000 --external-bot "python3 bots/food_chaser.py"`.
+"""
+import json
+import sys
+
+OPPOSITE = {"Up": "Down", "Down": "Up", "Left": "Right", "Right": "Left"}
+
+for line in sys.stdin:
+    state = json.loads(line)
+    if state["over"]:
+        break
+    me = state["snakes"][state["you"]]
+    food = state["food"]
+    if food is None:
+        print(json.dumps(None), flush=True)
+        continue
+    dx = food["x"] + food["w"] / 2 - me["head"]["x"]
+    dy = food["y"] + food["h"] / 2 - me["head"]["y"]
+    wanted = [
+        "Right" if dx > 0 else "Left",
+        "Down" if dy > 0 else "Up",
+    ]
+    if abs(dy) > abs(dx):
+        wanted.reverse()
+    dir = next((d for d in wanted if d != OPPOSITE[me["dir"]]), None)
+    print(json.dumps(dir), flush=True)
@@ -0,0 +1,486 @@
+//! Module with the computer controlled snakes
+//!
+//! Bots observe the same simulation the players do and steer their snakes
+//! only by choosing directions, so they move by the very same rules.
+//! `Greedy` heads straight for the food, turning away only from what is
+//! right in front of it, while `Pathfinder` plans its way with A* over a grid
+//! laid on the arena and, if asked to, checks with a flood fill that it does
+//! not lock itself in a space too small for its body.
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+    fmt,
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig, controller::Controller, coords::Coords, direction::Direction, rect::Rect,
+    segment::Collidable, simulation::Simulation, snake::Snake,
+};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Down,
+    Direction::Left,
+    Direction::Right,
+];
+
+/// How well the bots play
+///
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    /// Greedy bot with slow reactions
+    Easy,
+    /// Path planning bot
+    #[default]
+    Normal,
+    /// Path planning bot that avoids getting trapped, reacting every tick
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    /// Return the name used for this difficulty in config files and on the command line
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        }
+    }
+
+    /// Return the number of ticks between two decisions of a bot,
+    /// the more of them the slower it reacts.
+    ///
+    pub fn reaction_ticks(&self) -> u64 {
+        match self {
+            Difficulty::Easy => 15,
+            Difficulty::Normal => 4,
+            Difficulty::Hard => 1,
+        }
+    }
+
+    /// Create controller of a bot playing at this difficulty.
+    ///
+    pub fn controller(&self) -> Box<dyn Controller> {
+        match self {
+            Difficulty::Easy => Box::new(Greedy::new(self.reaction_ticks())),
+            Difficulty::Normal => Box::new(Pathfinder::new(self.reaction_ticks(), false)),
+            Difficulty::Hard => Box::new(Pathfinder::new(self.reaction_ticks(), true)),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|difficulty| difficulty.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(Difficulty::name).collect();
+                format!(
+                    "Unknown difficulty '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Create controllers of all bots of the given config, in the order of their snakes,
+/// which follow the players' ones.
+///
+pub fn bots(config: &GameConfig) -> Vec<Box<dyn Controller>> {
+    (0..config.bots)
+        .map(|_| config.bot_difficulty.controller())
+        .collect()
+}
+
+/// Return directions the snake going in `current` one can take,
+/// which are all but the opposite one, starting with `current`.
+///
+fn possible_dirs(current: Direction) -> impl Iterator<Item = Direction> {
+    Some(current).into_iter().chain(
+        DIRECTIONS
+            .iter()
+            .copied()
+            .filter(move |dir| !dir.is_colinear(current)),
+    )
+}
+
+/// Check if the snake of `player` would turn in the upcoming tick.
+/// Directions given any earlier would be queued up and applied too late.
+///
+fn can_turn(sim: &Simulation, player: usize) -> bool {
+    let player = &sim.players[player];
+    player.inputs.is_empty()
+        && player.input_timer + sim.config.tick_duration() >= sim.config.secs_per_input_update()
+}
+
+/// Return the middle of the snake head.
+///
+fn head_center(snake: &Snake) -> Coords {
+    let head = snake.body.front().unwrap();
+    head.end() - head.direction().as_coords() * (snake.width / 2.)
+}
+
+/// Return distance between two points along the axes,
+/// going across the edges in the wrap-around arena.
+///
+fn axis_distance(a: Coords, b: Coords, config: &GameConfig) -> f32 {
+    let (dx, dy) = ((a.x - b.x).abs(), (a.y - b.y).abs());
+    if !config.mode.wraps() {
+        return dx + dy;
+    }
+    let dx = dx.rem_euclid(config.screen_width);
+    let dy = dy.rem_euclid(config.screen_height);
+    dx.min(config.screen_width - dx) + dy.min(config.screen_height - dy)
+}
+
+/// Check if the snake of `player` survives moving `dist` in `dir`,
+/// assuming everything else stays where it is.
+///
+fn is_safe(sim: &Simulation, player: usize, dir: Direction, dist: f32) -> bool {
+    let mut snake = sim.players[player].snake.clone();
+    snake.dir = dir;
+    let step = sim.config.snake_width / 2.;
+    let mut moved = 0.;
+    while moved < dist {
+        snake.do_move(step);
+        moved += step;
+        let hits_other =
+            sim.players.iter().enumerate().any(|(idx, other)| {
+                idx != player && other.alive && snake.snake_collide(&other.snake)
+            });
+        if hits_other
+            || snake.self_collide()
+            || snake.wall_collide(&sim.config)
+            || snake.obstacle_collide(&sim.config.obstacles)
+        {
+            return false;
+        }
+    }
+    true
+}
+
+/// Bot heading straight for the food, turning away only from what is
+/// right in front of it.
+///
+pub struct Greedy {
+    reaction_ticks: u64,
+    /// How far ahead it looks for danger, in snake widths
+    lookahead: f32,
+}
+
+impl Greedy {
+    /// Create new `Greedy` bot deciding every `reaction_ticks` ticks.
+    ///
+    pub fn new(reaction_ticks: u64) -> Self {
+        Self {
+            reaction_ticks: reaction_ticks.max(1),
+            lookahead: 3.,
+        }
+    }
+}
+
+impl Controller for Greedy {
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction> {
+        if !sim.tick.is_multiple_of(self.reaction_ticks) || !can_turn(sim, player) {
+            return None;
+        }
+
+        let snake = &sim.players[player].snake;
+        let dist = self.lookahead * sim.config.snake_width;
+        let head = head_center(snake);
+        // Without any food all ways are as good, so it keeps going while it is safe
+        let food = sim.food.map_or(head, |food| food.bbox.center());
+        let distance_after =
+            |dir: Direction| axis_distance(head + dir.as_coords() * dist, food, &sim.config);
+
+        let mut best: Option<(f32, Direction)> = None;
+        for dir in possible_dirs(snake.dir).filter(|&dir| is_safe(sim, player, dir, dist)) {
+            let distance = distance_after(dir);
+            if best.is_none_or(|(best_distance, _)| distance < best_distance) {
+                best = Some((distance, dir));
+            }
+        }
+        best.map(|(_, dir)| dir).filter(|&dir| dir != snake.dir)
+    }
+}
+
+/// Bot planning its way to the food with A* over a grid laid on the arena,
+/// each cell being as big as the snake is wide.
+///
+/// With `safety` it also checks with a flood fill that its way leaves
+/// enough room for the whole body, heading for the biggest free space
+/// when it does not.
+///
+pub struct Pathfinder {
+    reaction_ticks: u64,
+    safety: bool,
+}
+
+impl Pathfinder {
+    /// Create new `Pathfinder` bot deciding every `reaction_ticks` ticks.
+    ///
+    pub fn new(reaction_ticks: u64, safety: bool) -> Self {
+        Self {
+            reaction_ticks: reaction_ticks.max(1),
+            safety,
+        }
+    }
+}
+
+impl Controller for Pathfinder {
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction> {
+        if !sim.tick.is_multiple_of(self.reaction_ticks) || !can_turn(sim, player) {
+            return None;
+        }
+
+        let snake = &sim.players[player].snake;
+        let grid = Grid::new(sim, player);
+        // Head enters the next cell whatever it does, a turn included
+        let start = grid.neighbour(grid.cell_at(head_center(snake)), snake.dir);
+        let mut dir = sim
+            .food
+            .and_then(|food| grid.first_step(start, grid.cell_at(food.bbox.center()), snake.dir))
+            .unwrap_or(snake.dir);
+
+        if self.safety {
+            let needed = (snake.length() / sim.config.snake_width) as usize + 1;
+            if grid.room(start, dir, needed) < needed {
+                let mut best = (grid.room(start, snake.dir, needed), snake.dir);
+                for other in possible_dirs(snake.dir).skip(1) {
+                    let room = grid.room(start, other, needed);
+                    if room > best.0 {
+                        best = (room, other);
+                    }
+                }
+                dir = best.1;
+            }
+        }
+
+        let lookahead = sim.config.snake_width * 2.;
+        if !is_safe(sim, player, dir, lookahead) {
+            dir = possible_dirs(snake.dir)
+                .find(|&other| is_safe(sim, player, other, lookahead))
+                .unwrap_or(dir);
+        }
+        Some(dir).filter(|&dir| dir != snake.dir)
+    }
+}
+
+/// Arena divided into square cells, marking those taken by anything deadly
+///
+/// Grid is aligned with the head of the snake it is made for, which keeps
+/// the cells the snake moves through, turning or not, in line with it.
+///
+struct Grid {
+    origin: Coords,
+    cols: i64,
+    rows: i64,
+    cell: f32,
+    wraps: bool,
+    blocked: Vec<bool>,
+}
+
+impl Grid {
+    /// Create grid with obstacles and snakes seen by the snake of `player`.
+    ///
+    fn new(sim: &Simulation, player: usize) -> Self {
+        let config = &sim.config;
+        let cell = config.snake_width;
+        let wraps = config.mode.wraps();
+        // Cells sticking out of the screen less than the wall margin can be still entered
+        let margin = if wraps { 0. } else { config.wall_margin };
+        let head = head_center(&sim.players[player].snake);
+        let align = |pos: f32| (pos - cell / 2. + margin).rem_euclid(cell) - margin;
+        let origin = Coords::new(align(head.x), align(head.y));
+        let count = |size: f32, origin: f32| {
+            if wraps {
+                (size / cell).round() as i64
+            } else {
+                ((size + margin - origin) / cell + 0.001).floor() as i64
+            }
+        };
+        let cols = count(config.screen_width, origin.x);
+        let rows = count(config.screen_height, origin.y);
+        let mut grid = Self {
+            origin,
+            cols,
+            rows,
+            cell,
+            wraps,
+            blocked: vec![false; (cols * rows) as usize],
+        };
+
+        for obstacle in &config.obstacles {
+            grid.block(obstacle.bounding_box());
+        }
+        for other in sim.players.iter().filter(|other| other.alive) {
+            for segment in &other.snake.body {
+                grid.block(segment.bounding_box());
+            }
+        }
+        grid
+    }
+
+    /// Mark all cells the `rect` reaches into as blocked.
+    ///
+    fn block(&mut self, rect: Rect) {
+        const EPS: f32 = 0.01;
+        let first = self.cell_at(Coords::new(rect.left() + EPS, rect.top() + EPS));
+        let last = self.cell_at(Coords::new(rect.right() - EPS, rect.bottom() - EPS));
+        for y in first.1..=last.1 {
+            for x in first.0..=last.0 {
+                if let Some(idx) = self.index((x, y)) {
+                    self.blocked[idx] = true;
+                }
+            }
+        }
+    }
+
+    /// Return column and row of the cell with the given position.
+    ///
+    fn cell_at(&self, pos: Coords) -> (i64, i64) {
+        (
+            ((pos.x - self.origin.x) / self.cell).floor() as i64,
+            ((pos.y - self.origin.y) / self.cell).floor() as i64,
+        )
+    }
+
+    /// Return index of the cell, wrapping it around the arena if it wraps.
+    /// Cells outside of the walled arena have none.
+    ///
+    fn index(&self, (x, y): (i64, i64)) -> Option<usize> {
+        let (x, y) = if self.wraps {
+            (x.rem_euclid(self.cols), y.rem_euclid(self.rows))
+        } else {
+            (x, y)
+        };
+        let inside = (0..self.cols).contains(&x) && (0..self.rows).contains(&y);
+        inside.then_some((y * self.cols + x) as usize)
+    }
+
+    /// Return the cell next to `cell` in the given direction.
+    ///
+    fn neighbour(&self, (x, y): (i64, i64), dir: Direction) -> (i64, i64) {
+        let step = dir.as_coords();
+        (x + step.x as i64, y + step.y as i64)
+    }
+
+    /// Return cell of the given index.
+    ///
+    fn cell_of(&self, idx: usize) -> (i64, i64) {
+        (idx as i64 % self.cols, idx as i64 / self.cols)
+    }
+
+    /// Return the number of steps between two cells, ignoring anything on the way.
+    ///
+    fn steps(&self, a: (i64, i64), b: (i64, i64)) -> u32 {
+        let (dx, dy) = ((a.0 - b.0).abs(), (a.1 - b.1).abs());
+        let (dx, dy) = if self.wraps {
+            (dx.min(self.cols - dx), dy.min(self.rows - dy))
+        } else {
+            (dx, dy)
+        };
+        (dx + dy) as u32
+    }
+
+    /// Find the shortest free path from `start` to `goal` with A*
+    /// and return the direction of its first step. The snake cannot
+    /// reverse, so the first step is never opposite to `current` one.
+    ///
+    fn first_step(
+        &self,
+        start: (i64, i64),
+        goal: (i64, i64),
+        current: Direction,
+    ) -> Option<Direction> {
+        let start_idx = self.index(start)?;
+        let goal_idx = self.index(goal)?;
+        if start_idx == goal_idx {
+            return None;
+        }
+
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut first_dir: Vec<Option<Direction>> = vec![None; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[start_idx] = 0;
+        open.push(Reverse((self.steps(start, goal), start_idx)));
+
+        while let Some(Reverse((_, idx))) = open.pop() {
+            if idx == goal_idx {
+                return first_dir[idx];
+            }
+            let cell = self.cell_of(idx);
+            let dirs: Vec<_> = if idx == start_idx {
+                possible_dirs(current).collect()
+            } else {
+                DIRECTIONS.to_vec()
+            };
+            for dir in dirs {
+                let next = self.neighbour(cell, dir);
+                let next_idx = match self.index(next) {
+                    Some(next_idx) if !self.blocked[next_idx] || next_idx == goal_idx => next_idx,
+                    _ => continue,
+                };
+                let next_cost = cost[idx] + 1;
+                if next_cost < cost[next_idx] {
+                    cost[next_idx] = next_cost;
+                    first_dir[next_idx] = first_dir[idx].or(Some(dir));
+                    let next = self.cell_of(next_idx);
+                    open.push(Reverse((next_cost + self.steps(next, goal), next_idx)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Return the number of free cells reachable after stepping from `from`
+    /// in `dir`, counting up to `limit` of them.
+    ///
+    fn room(&self, from: (i64, i64), dir: Direction, limit: usize) -> usize {
+        let start = match self.index(self.neighbour(from, dir)) {
+            Some(start) if !self.blocked[start] => start,
+            _ => return 0,
+        };
+        let mut seen = vec![false; self.blocked.len()];
+        if let Some(from) = self.index(from) {
+            seen[from] = true;
+        }
+        seen[start] = true;
+
+        let mut queue = VecDeque::from(vec![start]);
+        let mut count = 0;
+        while let Some(idx) = queue.pop_front() {
+            count += 1;
+            if count >= limit {
+                break;
+            }
+            for dir in DIRECTIONS {
+                if let Some(next) = self.index(self.neighbour(self.cell_of(idx), dir)) {
+                    if !self.blocked[next] && !seen[next] {
+                        seen[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        count
+    }
+}
@@ -0,0 +1,615 @@
+//! Module with bots called over HTTP, using a Battlesnake-like JSON API
+//!
+//! Bot is a web server answering `POST /start` once the game starts,
+//! `POST /move` whenever its snake needs to decide where to go next and
+//! `POST /end` once the game is over. All of them receive the same
+//! `GameRequest`, only the answer to `/move` matters:
+//!
+//! ```text
+//! {"move": "up", "shout": "optional message"}
+//! ```
+//!
+//! Battlesnake bots expect a board of tiles, so the continuous arena is
+//! projected onto a grid with cells as big as the snakes are wide, see
+//! `GridProjection`. As in Battlesnake, `(0, 0)` is the bottom left cell
+//! and `up` increases `y`. Snake is asked to move each time its head
+//! enters another cell and keeps going straight in between.
+//!
+//! `MockServer` is a tiny bot server running on a thread, for testing
+//! bots and tournaments without a real one. It is built for the tests
+//! of this crate and with the `test-util` feature only.
+use std::{fmt, io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig,
+    controller::Controller,
+    coords::Coords,
+    direction::Direction,
+    rect::Rect,
+    segment::{Collidable, SegmentKind},
+    simulation::Simulation,
+    snake::Snake,
+};
+
+/// Time an HTTP bot gets to answer a single request, if not configured otherwise
+pub const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Health reported for every snake, as the engine has no starvation
+const FULL_HEALTH: u32 = 100;
+
+/// Error that gets an HTTP bot disqualified
+///
+#[derive(Debug)]
+pub enum HttpBotError {
+    Request(Box<ureq::Error>),
+    Malformed(io::Error),
+}
+
+impl fmt::Display for HttpBotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HttpBotError::Request(err) => write!(f, "Request to bot failed: {}", err),
+            HttpBotError::Malformed(err) => write!(f, "Bot sent malformed answer: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HttpBotError {}
+
+impl From<ureq::Error> for HttpBotError {
+    fn from(err: ureq::Error) -> Self {
+        HttpBotError::Request(Box::new(err))
+    }
+}
+
+/// Move of a snake on the grid
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GridMove {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl From<GridMove> for Direction {
+    fn from(grid_move: GridMove) -> Self {
+        match grid_move {
+            GridMove::Up => Direction::Up,
+            GridMove::Down => Direction::Down,
+            GridMove::Left => Direction::Left,
+            GridMove::Right => Direction::Right,
+        }
+    }
+}
+
+impl From<Direction> for GridMove {
+    fn from(dir: Direction) -> Self {
+        match dir {
+            Direction::Up => GridMove::Up,
+            Direction::Down => GridMove::Down,
+            Direction::Left => GridMove::Left,
+            Direction::Right => GridMove::Right,
+        }
+    }
+}
+
+/// Cell of the grid, `(0, 0)` being the bottom left one
+///
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct GridPos {
+    pub x: i32,
+    pub y: i32,
+}
+
+/// Grid laid over the arena, with cells as big as the snakes are wide
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct GridProjection {
+    pub cell: f32,
+    pub width: i32,
+    pub height: i32,
+    pub wraps: bool,
+}
+
+impl GridProjection {
+    /// Create projection of the arena described by the `config`.
+    ///
+    pub fn new(config: &GameConfig) -> Self {
+        let cell = config.snake_width;
+        Self {
+            cell,
+            width: ((config.screen_width / cell) as i32).max(1),
+            height: ((config.screen_height / cell) as i32).max(1),
+            wraps: config.mode.wraps(),
+        }
+    }
+
+    /// Return the cell the point `pos` of the arena lies in. Points outside
+    /// of the arena are wrapped around in the wrap-around arena and moved to
+    /// the closest cell otherwise.
+    ///
+    pub fn cell_of(&self, pos: Coords) -> GridPos {
+        let col = (pos.x / self.cell).floor() as i32;
+        let row = (pos.y / self.cell).floor() as i32;
+        let (col, row) = if self.wraps {
+            (col.rem_euclid(self.width), row.rem_euclid(self.height))
+        } else {
+            (col.clamp(0, self.width - 1), row.clamp(0, self.height - 1))
+        };
+        GridPos {
+            x: col,
+            y: self.height - 1 - row,
+        }
+    }
+
+    /// Return cells covered by the `rect`, i.e. the ones with their centres inside of it.
+    ///
+    pub fn cells_of(&self, rect: &Rect) -> Vec<GridPos> {
+        let first_col = (rect.left() / self.cell).round() as i32;
+        let last_col = (rect.right() / self.cell).round() as i32;
+        let first_row = (rect.top() / self.cell).round() as i32;
+        let last_row = (rect.bottom() / self.cell).round() as i32;
+        let mut cells = Vec::new();
+        for row in first_row.max(0)..last_row.min(self.height) {
+            for col in first_col.max(0)..last_col.min(self.width) {
+                cells.push(GridPos {
+                    x: col,
+                    y: self.height - 1 - row,
+                });
+            }
+        }
+        cells
+    }
+
+    /// Return cells taken by the `snake`, from the head to the tail.
+    ///
+    /// Straight segments are sampled along their middle once per cell, turns
+    /// take the cell of their centre. Cells repeated one after another are skipped.
+    ///
+    pub fn snake(&self, snake: &Snake) -> Vec<GridPos> {
+        let half = snake.width / 2.;
+        let mut cells: Vec<GridPos> = Vec::new();
+        for segment in &snake.body {
+            let points = match segment.kind() {
+                SegmentKind::Line(line) => {
+                    let back = line.dir.as_coords() * -1.;
+                    let size = line.size();
+                    let mut points = vec![line.end + back * half.min(size / 2.)];
+                    let mut offset = half + self.cell;
+                    while offset <= size {
+                        points.push(line.end + back * offset);
+                        offset += self.cell;
+                    }
+                    points
+                }
+                SegmentKind::Turn(turn) => vec![turn.bounding_box().center()],
+            };
+            for cell in points.into_iter().map(|point| self.cell_of(point)) {
+                if cells.last() != Some(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// Rules the game is played by
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub name: String,
+    pub version: String,
+}
+
+/// Game the request belongs to
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct GameInfo {
+    pub id: String,
+    pub ruleset: Ruleset,
+    /// Milliseconds the bot has to answer
+    pub timeout: u64,
+    pub source: String,
+}
+
+/// Snake projected onto the grid
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct GridSnake {
+    pub id: String,
+    pub name: String,
+    pub health: u32,
+    pub body: Vec<GridPos>,
+    pub head: GridPos,
+    pub length: u32,
+    pub latency: String,
+    pub shout: String,
+}
+
+/// Arena projected onto the grid, obstacles being the hazards
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Board {
+    pub height: i32,
+    pub width: i32,
+    pub food: Vec<GridPos>,
+    pub hazards: Vec<GridPos>,
+    pub snakes: Vec<GridSnake>,
+}
+
+/// Body of every request sent to an HTTP bot
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct GameRequest {
+    pub game: GameInfo,
+    pub turn: u64,
+    pub board: Board,
+    pub you: GridSnake,
+}
+
+impl GameRequest {
+    /// Create request describing the `sim` as seen by the snake of the given player,
+    /// with `turn` being the number of moves it was asked for so far.
+    ///
+    pub fn new(
+        sim: &Simulation,
+        player: usize,
+        grid: &GridProjection,
+        turn: u64,
+        timeout: Duration,
+    ) -> Self {
+        let grid_snake = |idx: usize| {
+            let body = grid.snake(&sim.players[idx].snake);
+            GridSnake {
+                id: format!("snake-{}", idx),
+                name: format!("Snake {}", idx + 1),
+                health: FULL_HEALTH,
+                head: body[0],
+                length: body.len() as u32,
+                body,
+                latency: "0".to_string(),
+                shout: String::new(),
+            }
+        };
+        let snakes = (0..sim.players.len())
+            .filter(|&idx| sim.players[idx].alive)
+            .map(grid_snake)
+            .collect();
+        let hazards = sim
+            .config
+            .obstacles
+            .iter()
+            .flat_map(|obstacle| grid.cells_of(&obstacle.bounding_box()))
+            .collect();
+
+        Self {
+            game: GameInfo {
+                id: format!("snaek-{}", sim.seed),
+                ruleset: Ruleset {
+                    name: sim.config.mode.name().to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                },
+                timeout: timeout.as_millis() as u64,
+                source: "snaek_rust".to_string(),
+            },
+            turn,
+            board: Board {
+                height: grid.height,
+                width: grid.width,
+                food: sim
+                    .food
+                    .iter()
+                    .map(|food| grid.cell_of(food.bbox.center()))
+                    .collect(),
+                hazards,
+                snakes,
+            },
+            you: grid_snake(player),
+        }
+    }
+}
+
+/// Answer to the `/move` request
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct MoveResponse {
+    #[serde(rename = "move")]
+    pub grid_move: GridMove,
+    #[serde(default)]
+    pub shout: String,
+}
+
+/// Controller calling a bot over HTTP, see the module docs for the API
+///
+pub struct HttpBot {
+    url: String,
+    agent: ureq::Agent,
+    timeout: Duration,
+    grid: Option<GridProjection>,
+    last_head: Option<GridPos>,
+    turn: u64,
+    error: Option<HttpBotError>,
+}
+
+impl HttpBot {
+    /// Create bot calling the server under the base `url`,
+    /// waiting at most `timeout` for each answer.
+    ///
+    pub fn new(url: &str, timeout: Duration) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            agent: ureq::AgentBuilder::new().timeout(timeout).build(),
+            timeout,
+            grid: None,
+            last_head: None,
+            turn: 0,
+            error: None,
+        }
+    }
+
+    /// Return the base url of the bot server.
+    ///
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Return the error the bot was disqualified for, if it was.
+    ///
+    pub fn error(&self) -> Option<&HttpBotError> {
+        self.error.as_ref()
+    }
+
+    fn post(&self, path: &str, request: &GameRequest) -> Result<ureq::Response, HttpBotError> {
+        Ok(self
+            .agent
+            .post(&format!("{}{}", self.url, path))
+            .send_json(request)?)
+    }
+
+    fn ask(&mut self, sim: &Simulation, player: usize) -> Result<Option<Direction>, HttpBotError> {
+        let grid = *self
+            .grid
+            .get_or_insert_with(|| GridProjection::new(&sim.config));
+        let request = GameRequest::new(sim, player, &grid, self.turn, self.timeout);
+        if self.last_head.is_none() {
+            self.post("/start", &request)?;
+        } else if self.last_head == Some(request.you.head) {
+            return Ok(None);
+        }
+        self.last_head = Some(request.you.head);
+
+        let response: MoveResponse = self
+            .post("/move", &request)?
+            .into_json()
+            .map_err(HttpBotError::Malformed)?;
+        self.turn += 1;
+        Ok(Some(response.grid_move.into()))
+    }
+}
+
+impl Controller for HttpBot {
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.ask(sim, player) {
+            Ok(dir) => dir,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn disqualified(&self) -> Option<String> {
+        self.error.as_ref().map(HttpBotError::to_string)
+    }
+
+    fn game_over(&mut self, sim: &Simulation, player: usize) {
+        if let (Some(grid), None) = (self.grid, &self.error) {
+            let request = GameRequest::new(sim, player, &grid, self.turn, self.timeout);
+            // Nothing is expected back, bot that went away is not a problem anymore
+            let _ = self.post("/end", &request);
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+pub use self::mock::{MockAnswer, MockFailure, MockServer};
+
+/// Mock bot server, built only for the tests and with the `test-util` feature
+///
+#[cfg(any(test, feature = "test-util"))]
+mod mock {
+    use std::{
+        io::{self, BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc, Mutex,
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    use super::{GameRequest, GridMove, MoveResponse};
+
+    /// Answer the mock server gives to a `/move` request, or a reason to fail it
+    ///
+    pub type MockAnswer = Result<GridMove, MockFailure>;
+
+    /// Way the mock server fails to answer a `/move` request
+    ///
+    #[derive(PartialEq, Clone, Copy, Debug)]
+    pub enum MockFailure {
+        /// Answer only after the given time
+        Delay(Duration, GridMove),
+        /// Answer with something that is not a move
+        Garbage,
+        /// Answer with the given HTTP error status
+        Status(u16),
+    }
+
+    /// Bot server for tests, answering on a loopback port on its own thread
+    /// and remembering every request it got, in order.
+    ///
+    pub struct MockServer {
+        addr: SocketAddr,
+        requests: Arc<Mutex<Vec<(String, GameRequest)>>>,
+        stopped: Arc<AtomicBool>,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl MockServer {
+        /// Start server answering each `/move` request with whatever `answer` returns for it.
+        ///
+        pub fn start<F>(mut answer: F) -> io::Result<Self>
+        where
+            F: FnMut(&GameRequest) -> MockAnswer + Send + 'static,
+        {
+            let listener = TcpListener::bind("127.0.0.1:0")?;
+            let addr = listener.local_addr()?;
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let stopped = Arc::new(AtomicBool::new(false));
+
+            let thread = {
+                let (requests, stopped) = (requests.clone(), stopped.clone());
+                thread::spawn(move || {
+                    for stream in listener.incoming() {
+                        if stopped.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        if let Ok(stream) = stream {
+                            // Failing connection only fails the request the bot made
+                            let _ = Self::serve(stream, &requests, &mut answer);
+                        }
+                    }
+                })
+            };
+
+            Ok(Self {
+                addr,
+                requests,
+                stopped,
+                thread: Some(thread),
+            })
+        }
+
+        /// Start server always answering `/move` with the same move.
+        ///
+        pub fn always(grid_move: GridMove) -> io::Result<Self> {
+            Self::start(move |_| Ok(grid_move))
+        }
+
+        /// Return the base url of the server.
+        ///
+        pub fn url(&self) -> String {
+            format!("http://{}", self.addr)
+        }
+
+        /// Return paths and bodies of all requests received so far.
+        ///
+        pub fn requests(&self) -> Vec<(String, GameRequest)> {
+            self.requests
+                .lock()
+                .unwrap_or_else(|err| err.into_inner())
+                .clone()
+        }
+
+        fn serve<F>(
+            stream: TcpStream,
+            requests: &Mutex<Vec<(String, GameRequest)>>,
+            answer: &mut F,
+        ) -> io::Result<()>
+        where
+            F: FnMut(&GameRequest) -> MockAnswer,
+        {
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line)?;
+            let path = request_line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or("/")
+                .to_string();
+
+            let mut content_len = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header)?;
+                let header = header.trim();
+                if header.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_len = value.trim().parse().unwrap_or(0);
+                    }
+                }
+            }
+            let mut body = vec![0; content_len];
+            reader.read_exact(&mut body)?;
+
+            let (status, body) = match serde_json::from_slice::<GameRequest>(&body) {
+                Ok(request) => {
+                    let response = match path.as_str() {
+                        "/move" => Self::move_response(answer(&request)),
+                        _ => (200, "{}".to_string()),
+                    };
+                    requests
+                        .lock()
+                        .unwrap_or_else(|err| err.into_inner())
+                        .push((path, request));
+                    response
+                }
+                Err(_) if path == "/" => (200, r#"{"apiversion":"1"}"#.to_string()),
+                Err(_) => (400, "{}".to_string()),
+            };
+
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 {} MOCK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )?;
+            stream.flush()
+        }
+
+        fn move_response(answer: MockAnswer) -> (u16, String) {
+            let response = |grid_move| {
+                serde_json::to_string(&MoveResponse {
+                    grid_move,
+                    shout: String::new(),
+                })
+                .expect("move response is serializable")
+            };
+            match answer {
+                Ok(grid_move) => (200, response(grid_move)),
+                Err(MockFailure::Delay(delay, grid_move)) => {
+                    thread::sleep(delay);
+                    (200, response(grid_move))
+                }
+                Err(MockFailure::Garbage) => (200, "not a move".to_string()),
+                Err(MockFailure::Status(status)) => (status, "{}".to_string()),
+            }
+        }
+    }
+
+    impl Drop for MockServer {
+        fn drop(&mut self) {
+            self.stopped.store(true, Ordering::SeqCst);
+            // Wake the server up, so it notices it was stopped
+            let _ = TcpStream::connect(self.addr);
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
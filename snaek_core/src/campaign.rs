@@ -0,0 +1,183 @@
+//! Module with the campaign: ordered levels and the progress through them
+//!
+//! Campaign file lists level files in the order they are played,
+//! one per line, relative to the campaign file. Empty lines and lines
+//! starting with `#` are skipped:
+//!
+//! ```text
+//! # levels are unlocked one by one
+//! 01_walled_garden.lvl
+//! 02_pillars.lvl
+//! ```
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    json_file,
+    level::{Level, LevelError},
+};
+
+/// Error that can occur while loading a campaign or the progress through it
+///
+#[derive(Debug)]
+pub enum CampaignError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    Level(PathBuf, LevelError),
+    Empty,
+}
+
+impl fmt::Display for CampaignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CampaignError::Io(err) => write!(f, "Could not read campaign: {}", err),
+            CampaignError::Parse(err) => write!(f, "Could not parse campaign progress: {}", err),
+            CampaignError::Level(path, err) => write!(f, "{}: {}", path.display(), err),
+            CampaignError::Empty => write!(f, "Campaign has no levels"),
+        }
+    }
+}
+
+impl std::error::Error for CampaignError {}
+
+impl json_file::LoadError for CampaignError {
+    fn is_not_found(&self) -> bool {
+        matches!(self, CampaignError::Io(err) if err.kind() == io::ErrorKind::NotFound)
+    }
+
+    fn is_corrupt(&self) -> bool {
+        matches!(self, CampaignError::Parse(_))
+    }
+}
+
+impl From<io::Error> for CampaignError {
+    fn from(err: io::Error) -> Self {
+        CampaignError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for CampaignError {
+    fn from(err: serde_json::Error) -> Self {
+        CampaignError::Parse(err)
+    }
+}
+
+/// Level of the campaign, identified by its file name
+///
+#[derive(PartialEq, Clone, Debug)]
+pub struct CampaignLevel {
+    pub id: String,
+    pub level: Level,
+}
+
+/// Ordered list of levels, each one unlocked by completing the previous one
+///
+#[derive(PartialEq, Clone, Debug, Default)]
+pub struct Campaign {
+    pub levels: Vec<CampaignLevel>,
+}
+
+impl Campaign {
+    /// Load campaign file under `path` and all of the levels it lists.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CampaignError> {
+        let path = path.as_ref();
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        let mut levels = Vec::new();
+
+        for line in fs::read_to_string(path)?.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let level_path = dir.join(line);
+            let level =
+                Level::load(&level_path).map_err(|err| CampaignError::Level(level_path, err))?;
+            levels.push(CampaignLevel {
+                id: line.to_string(),
+                level,
+            });
+        }
+
+        if levels.is_empty() {
+            return Err(CampaignError::Empty);
+        }
+        Ok(Self { levels })
+    }
+}
+
+/// State of a campaign level
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum LevelStatus {
+    Locked,
+    Unlocked,
+    Completed,
+}
+
+/// Best result achieved on a completed level
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct LevelRecord {
+    pub best_score: u32,
+    pub best_secs: f32,
+}
+
+/// Progress through the campaign, completed levels keyed by their ids
+///
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    #[serde(default)]
+    pub completed: BTreeMap<String, LevelRecord>,
+}
+
+impl CampaignProgress {
+    /// Load progress from the JSON file under `path`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CampaignError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// Load progress from the file under `path`, starting from scratch when
+    /// it is missing or cannot be loaded, along with the error then.
+    /// Corrupt file is moved aside, so it is not overwritten.
+    ///
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> (Self, Option<CampaignError>) {
+        json_file::load_or_default(path.as_ref(), |path| Self::load(path))
+    }
+
+    /// Save progress into the JSON file under `path`, creating missing directories.
+    ///
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        json_file::save(self, path.as_ref())
+    }
+
+    /// Return status of the campaign level under `idx`.
+    /// First level is always unlocked, every next one once the previous is completed.
+    ///
+    pub fn status(&self, campaign: &Campaign, idx: usize) -> LevelStatus {
+        let is_completed = |idx: usize| self.completed.contains_key(&campaign.levels[idx].id);
+        if is_completed(idx) {
+            LevelStatus::Completed
+        } else if idx == 0 || is_completed(idx - 1) {
+            LevelStatus::Unlocked
+        } else {
+            LevelStatus::Locked
+        }
+    }
+
+    /// Mark level `id` as completed with the given result, keeping the best one.
+    ///
+    pub fn complete(&mut self, id: &str, score: u32, secs: f32) {
+        let record = self.completed.entry(id.to_string()).or_insert(LevelRecord {
+            best_score: score,
+            best_secs: secs,
+        });
+        record.best_score = record.best_score.max(score);
+        record.best_secs = record.best_secs.min(secs);
+    }
+}
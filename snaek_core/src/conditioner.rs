@@ -0,0 +1,209 @@
+//! Module with a UDP proxy simulating a bad network, for testing the online game
+//!
+//! `LinkConditioner` sits between the clients and the server on loopback and
+//! forwards every datagram after the configured latency plus a random jitter,
+//! unless it loses it. With enough jitter, datagrams arrive out of order too.
+//! Each client gets its own socket towards the server, so the server still
+//! tells the clients apart.
+use std::{
+    io,
+    net::{SocketAddr, UdpSocket},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+
+use crate::rng::{self, GameRng};
+
+/// Largest datagram the proxy forwards
+const MAX_DATAGRAM: usize = 65507;
+/// How long the proxy sleeps when there is nothing to do
+const IDLE: Duration = Duration::from_millis(1);
+
+/// Conditions of the simulated network, the same in both directions
+///
+#[derive(PartialEq, Clone, Copy, Debug, Default)]
+pub struct NetConditions {
+    /// Time every datagram takes to get through
+    pub latency: Duration,
+    /// Most time added at random to the latency of every datagram
+    pub jitter: Duration,
+    /// Probability of losing a datagram, from 0 to 1
+    pub loss: f64,
+}
+
+impl NetConditions {
+    /// Return the moment the datagram sent now arrives, `None` if it gets lost.
+    ///
+    fn arrival(&self, rng: &mut GameRng) -> Option<Instant> {
+        if rng.gen_bool(self.loss.clamp(0., 1.)) {
+            return None;
+        }
+        let jitter = self.jitter.mul_f64(rng.gen());
+        Some(Instant::now() + self.latency + jitter)
+    }
+}
+
+/// Where a datagram is going
+///
+enum Route {
+    /// To the server, from the client with the given index
+    Server(usize),
+    /// Back to the client with the given address
+    Client(SocketAddr),
+}
+
+/// Datagram on its way through the proxy
+///
+struct InFlight {
+    arrival: Instant,
+    route: Route,
+    bytes: Vec<u8>,
+}
+
+/// Proxy forwarding the datagrams of the clients to the server and back
+/// under the given `NetConditions`, running on its own thread until dropped
+///
+pub struct LinkConditioner {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl LinkConditioner {
+    /// Starts the proxy of the `server` on loopback. Lost datagrams and
+    /// the jitter are drawn from an RNG with the given seed.
+    ///
+    pub fn spawn(server: SocketAddr, conditions: NetConditions, seed: u64) -> io::Result<Self> {
+        let socket = UdpSocket::bind("127.0.0.1:0")?;
+        socket.set_nonblocking(true)?;
+        let addr = socket.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let mut proxy = Proxy {
+            server,
+            conditions,
+            rng: rng::from_seed(seed),
+            socket,
+            clients: Vec::new(),
+            in_flight: Vec::new(),
+        };
+        let stopped = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            while !stopped.load(Ordering::Relaxed) {
+                if proxy.forward().is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            addr,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Return the address the clients connect to instead of the server's.
+    ///
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for LinkConditioner {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+struct Proxy {
+    server: SocketAddr,
+    conditions: NetConditions,
+    rng: GameRng,
+    /// Socket the clients talk to
+    socket: UdpSocket,
+    /// Address of each client with its own socket talking to the server
+    clients: Vec<(SocketAddr, UdpSocket)>,
+    in_flight: Vec<InFlight>,
+}
+
+impl Proxy {
+    /// Takes in every datagram that arrived and passes on the ones whose time has come.
+    ///
+    fn forward(&mut self) -> io::Result<()> {
+        let mut buf = vec![0; MAX_DATAGRAM];
+        let mut idle = true;
+
+        while let Some((len, addr)) = receive(&self.socket, &mut buf)? {
+            let client = match self.clients.iter().position(|(client, _)| *client == addr) {
+                Some(client) => client,
+                None => {
+                    let socket = UdpSocket::bind("127.0.0.1:0")?;
+                    socket.set_nonblocking(true)?;
+                    self.clients.push((addr, socket));
+                    self.clients.len() - 1
+                }
+            };
+            self.schedule(Route::Server(client), &buf[..len]);
+            idle = false;
+        }
+        for idx in 0..self.clients.len() {
+            while let Some((len, _)) = receive(&self.clients[idx].1, &mut buf)? {
+                let addr = self.clients[idx].0;
+                self.schedule(Route::Client(addr), &buf[..len]);
+                idle = false;
+            }
+        }
+
+        let now = Instant::now();
+        let (arrived, in_flight) = std::mem::take(&mut self.in_flight)
+            .into_iter()
+            .partition(|datagram| datagram.arrival <= now);
+        self.in_flight = in_flight;
+        for datagram in arrived {
+            // Peers that are gone are no concern of the network
+            let _ = match datagram.route {
+                Route::Server(client) => {
+                    self.clients[client].1.send_to(&datagram.bytes, self.server)
+                }
+                Route::Client(addr) => self.socket.send_to(&datagram.bytes, addr),
+            };
+        }
+
+        if idle {
+            thread::sleep(IDLE);
+        }
+        Ok(())
+    }
+
+    fn schedule(&mut self, route: Route, bytes: &[u8]) {
+        if let Some(arrival) = self.conditions.arrival(&mut self.rng) {
+            self.in_flight.push(InFlight {
+                arrival,
+                route,
+                bytes: bytes.to_vec(),
+            });
+        }
+    }
+}
+
+/// Receives a datagram from the non-blocking `socket`, if there is any.
+///
+fn receive(socket: &UdpSocket, buf: &mut [u8]) -> io::Result<Option<(usize, SocketAddr)>> {
+    match socket.recv_from(buf) {
+        Ok(received) => Ok(Some(received)),
+        Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+        // Answer to a datagram sent to a peer that is gone
+        Err(err) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(None),
+        Err(err) => Err(err),
+    }
+}
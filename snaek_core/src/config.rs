@@ -0,0 +1,352 @@
+//! Module with GameConfig struct
+use std::{fmt, fs, io, path::Path};
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::Difficulty, consts, coords::Coords, direction::Direction, line::Line, mode::GameMode,
+    obstacle::Obstacle, rect::Rect, segment::Collidable,
+};
+
+/// Error that can occur while loading or validating a `GameConfig`
+///
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "Could not read config: {}", err),
+            ConfigError::Parse(err) => write!(f, "Could not parse config: {}", err),
+            ConfigError::Invalid(reason) => write!(f, "Invalid config: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
+/// All tunable gameplay parameters.
+///
+/// Every field missing from the config file falls back
+/// to its default value from the `consts` module.
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameConfig {
+    pub mode: GameMode,
+    pub screen_width: f32,
+    pub screen_height: f32,
+    pub snake_width: f32,
+    pub snake_start_len: u8,
+    pub speed: f32,
+    pub wall_margin: f32,
+    pub food_size: f32,
+    pub turn_margin: f32,
+    pub ticks_per_sec: u32,
+    /// Number of snakes in the arena controlled by local players
+    pub players: u8,
+    /// Number of snakes controlled by the computer, added after the players' ones
+    pub bots: u8,
+    pub bot_difficulty: Difficulty,
+    // Tables are kept last, as TOML requires them to follow plain values
+    /// Snake start, in the middle of the screen pointing down if not given.
+    /// Used only when there is a single player.
+    pub spawn: Option<Spawn>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<Obstacle>,
+    /// Points where food is placed, in order, before it starts to appear randomly
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub food_spawns: Vec<Coords>,
+}
+
+/// Position of the snake head and its direction when the game starts
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+    pub x: f32,
+    pub y: f32,
+    pub dir: Direction,
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            mode: GameMode::default(),
+            screen_width: consts::SCREEN_WIDTH,
+            screen_height: consts::SCREEN_HEIGHT,
+            snake_width: consts::SNAKE_WIDTH,
+            snake_start_len: consts::SNAKE_START_LEN,
+            speed: consts::SPEED,
+            wall_margin: consts::WALL_MARGIN,
+            food_size: consts::FOOD_SIZE,
+            turn_margin: consts::TURN_MARGIN,
+            ticks_per_sec: consts::TICKS_PER_SEC,
+            players: consts::PLAYERS,
+            bots: 0,
+            bot_difficulty: Difficulty::default(),
+            spawn: None,
+            obstacles: Vec::new(),
+            food_spawns: Vec::new(),
+        }
+    }
+}
+
+impl GameConfig {
+    /// Load and validate config from the TOML file under `path`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Parse and validate config from the TOML string.
+    ///
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(toml)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Serialize config into the TOML string.
+    ///
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("GameConfig is always representable in TOML")
+    }
+
+    /// Check that all values are sane and consistent with each other.
+    ///
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason: String| Err(ConfigError::Invalid(reason));
+        let positive = [
+            ("screen_width", self.screen_width),
+            ("screen_height", self.screen_height),
+            ("snake_width", self.snake_width),
+            ("speed", self.speed),
+            ("food_size", self.food_size),
+        ];
+
+        if let Some((name, value)) = positive
+            .iter()
+            .find(|(_, value)| !value.is_finite() || *value <= 0.)
+        {
+            return invalid(format!("{} must be positive, got {}", name, value));
+        }
+        if self.ticks_per_sec == 0 {
+            return invalid("ticks_per_sec must be positive".to_string());
+        }
+        if self.snake_count() == 0 {
+            return invalid(
+                "there must be at least one snake, got no players and no bots".to_string(),
+            );
+        }
+        if self.snake_count() > consts::MAX_PLAYERS as usize {
+            return invalid(format!(
+                "at most {} snakes fit in the arena, got {} players and {} bots",
+                consts::MAX_PLAYERS,
+                self.players,
+                self.bots
+            ));
+        }
+        if self.snake_start_len == 0 {
+            return invalid("snake_start_len must be at least 1".to_string());
+        }
+        if !(0. ..self.snake_width).contains(&self.turn_margin) {
+            return invalid(format!(
+                "turn_margin must be between 0 and snake_width ({}), got {}",
+                self.snake_width, self.turn_margin
+            ));
+        }
+        if !self.wall_margin.is_finite() || self.wall_margin < 0. {
+            return invalid(format!(
+                "wall_margin must not be negative, got {}",
+                self.wall_margin
+            ));
+        }
+        if self.tick_distance() >= self.snake_width {
+            return invalid(format!(
+                "snake moves {} per tick, which is not less than snake_width ({}); \
+                 raise ticks_per_sec or lower speed",
+                self.tick_distance(),
+                self.snake_width
+            ));
+        }
+        let screen = Rect::new(0., 0., self.screen_width, self.screen_height);
+        let starts = self.snake_start_rects();
+        if !starts.iter().all(|start| screen.contains(start)) {
+            return invalid(format!(
+                "snake of {} segments does not fit on the screen of size {}x{}",
+                self.snake_start_len, self.screen_width, self.screen_height
+            ));
+        }
+        if starts
+            .iter()
+            .tuple_combinations()
+            .any(|(a, b)| a.overlaps(b))
+        {
+            return invalid(format!(
+                "{} snakes do not fit side by side on the screen of width {}",
+                self.snake_count(),
+                self.screen_width
+            ));
+        }
+        if self.food_size * 2. >= f32::min(self.screen_width, self.screen_height) {
+            return invalid(format!(
+                "food_size {} is too big for the screen",
+                self.food_size
+            ));
+        }
+        for (idx, obstacle) in self.obstacles.iter().enumerate() {
+            let bbox = obstacle.bounding_box();
+            if ![bbox.x, bbox.y, bbox.w, bbox.h]
+                .iter()
+                .all(|v| v.is_finite())
+                || bbox.w <= 0.
+                || bbox.h <= 0.
+            {
+                return invalid(format!("obstacle {} must have a positive size", idx));
+            }
+            if !bbox.overlaps(&screen) {
+                return invalid(format!("obstacle {} lies outside of the screen", idx));
+            }
+            if starts.iter().any(|start| bbox.overlaps(start)) {
+                return invalid(format!("obstacle {} blocks the snake start", idx));
+            }
+        }
+        for (idx, pos) in self.food_spawns.iter().enumerate() {
+            let food = Rect::new(
+                pos.x - self.food_size / 2.,
+                pos.y - self.food_size / 2.,
+                self.food_size,
+                self.food_size,
+            );
+            if !food.overlaps(&screen) {
+                return invalid(format!("food spawn {} lies outside of the screen", idx));
+            }
+            if self
+                .obstacles
+                .iter()
+                .any(|obstacle| obstacle.collision(&food))
+            {
+                return invalid(format!("food spawn {} lies inside an obstacle", idx));
+            }
+        }
+        Ok(())
+    }
+
+    /// Return where the snake starts, falling back to the middle of the screen.
+    ///
+    pub fn snake_spawn(&self) -> Spawn {
+        self.spawn.unwrap_or(Spawn {
+            x: self.screen_width / 2.,
+            y: (self.screen_height + self.snake_start_height()) / 2.,
+            dir: Direction::Down,
+        })
+    }
+
+    /// Return the number of snakes in the arena, the players' and the bots' ones.
+    ///
+    pub fn snake_count(&self) -> usize {
+        self.players as usize + self.bots as usize
+    }
+
+    /// Check if the snake under `idx` is controlled by a bot.
+    ///
+    pub fn is_bot(&self, idx: usize) -> bool {
+        idx >= self.players as usize
+    }
+
+    /// Return where each of the snakes starts. Single snake starts on `snake_spawn`,
+    /// more of them are spread evenly across the screen, all pointing down.
+    ///
+    pub fn snake_spawns(&self) -> Vec<Spawn> {
+        let count = self.snake_count();
+        if count <= 1 {
+            return vec![self.snake_spawn()];
+        }
+        let gap = self.screen_width / (count + 1) as f32;
+        (1..=count)
+            .map(|idx| Spawn {
+                x: gap * idx as f32,
+                y: (self.screen_height + self.snake_start_height()) / 2.,
+                dir: Direction::Down,
+            })
+            .collect()
+    }
+
+    /// Return the straight segment a snake consists of when it starts on `spawn`.
+    ///
+    pub fn snake_start_line(&self, spawn: Spawn) -> Line {
+        let head = Coords::new(spawn.x, spawn.y);
+        Line {
+            beg: head - spawn.dir.as_coords() * self.snake_start_height(),
+            end: head,
+            dir: spawn.dir,
+            width: self.snake_width,
+        }
+    }
+
+    /// Return the areas taken by the snakes when the game starts.
+    ///
+    pub fn snake_start_rects(&self) -> Vec<Rect> {
+        self.snake_spawns()
+            .into_iter()
+            .map(|spawn| self.snake_start_line(spawn).bounding_box())
+            .collect()
+    }
+
+    /// Return screen size as `Coords`.
+    ///
+    pub fn screen_size(&self) -> Coords {
+        Coords::new(self.screen_width, self.screen_height)
+    }
+
+    /// Return the size of the arena the snakes wrap around, if they do.
+    ///
+    pub fn wrap_size(&self) -> Option<Coords> {
+        self.mode.wraps().then(|| self.screen_size())
+    }
+
+    /// Return the initial length of the snake in pixels.
+    ///
+    pub fn snake_start_height(&self) -> f32 {
+        self.snake_width * self.snake_start_len as f32
+    }
+
+    /// Return duration of a single tick in seconds.
+    ///
+    pub fn tick_duration(&self) -> f32 {
+        1. / self.ticks_per_sec as f32
+    }
+
+    /// Return the distance the snake moves during a single tick.
+    ///
+    pub fn tick_distance(&self) -> f32 {
+        self.speed * self.tick_duration()
+    }
+
+    /// Return the minimal time between two consecutive turns.
+    ///
+    /// The cap is there to make sure that 180 turns always makes enough
+    /// space between both parts of the snake.
+    ///
+    pub fn secs_per_input_update(&self) -> f32 {
+        (self.snake_width + self.turn_margin) / self.speed
+    }
+}
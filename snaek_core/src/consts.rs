@@ -0,0 +1,25 @@
+//! Module that gathers default values of the `GameConfig`
+//! and the few parameters that are not meant to be modified.
+
+pub const SCREEN_WIDTH: f32 = 800.;
+pub const SCREEN_HEIGHT: f32 = 800.;
+pub const SNAKE_WIDTH: f32 = 20.;
+pub const SNAKE_START_LEN: u8 = 8;
+pub const SPEED: f32 = SNAKE_WIDTH * 15.;
+
+pub const WALL_MARGIN: f32 = SNAKE_WIDTH * 0.5;
+pub const FOOD_SIZE: f32 = SNAKE_WIDTH;
+pub const TURN_MARGIN: f32 = SNAKE_WIDTH * 0.15;
+pub const TICKS_PER_SEC: u32 = 120;
+pub const PLAYERS: u8 = 1;
+
+pub const COLLISION_PIXELS_MARGIN: f32 = 1.;
+pub const MAX_FRAME_TIME: f32 = 0.25;
+pub const MAX_PLAYERS: u8 = 4;
+/// Longest name of a player, in the lobby and in the high score table
+pub const MAX_NAME_LEN: usize = 16;
+/// Color of the snake of each player, as RGB
+pub const SNAKE_COLORS: [(u8, u8, u8); MAX_PLAYERS as usize] =
+    [(255, 255, 0), (0, 255, 255), (255, 0, 255), (255, 128, 0)];
+/// Random places tried for the food before looking for a free one in order
+pub const FOOD_SPAWN_ATTEMPTS: u32 = 100;
@@ -0,0 +1,258 @@
+//! Module with the interface of everything that can steer a snake
+//!
+//! Every snake of the `Simulation` is driven by a `Controller`, be it
+//! a player at the keyboard, a recorded replay, a script, a bot or a peer
+//! on the network. The same game can be thus played by any mix of them.
+//!
+//! Bots only have to implement `Controller::decide`, plain closures work too:
+//!
+//! ```
+//! use snaek_core::{controller, Controller, Direction, GameConfig, Simulation};
+//!
+//! // Keeps turning left, whenever it is allowed to
+//! let spinner = |sim: &Simulation, player: usize| {
+//!     let dir = match sim.players[player].snake.dir {
+//!         Direction::Up => Direction::Left,
+//!         Direction::Left => Direction::Down,
+//!         Direction::Down => Direction::Right,
+//!         Direction::Right => Direction::Up,
+//!     };
+//!     Some(dir)
+//! };
+//! let mut controllers: Vec<Box<dyn Controller>> = vec![Box::new(spinner)];
+//! let mut sim = Simulation::new(GameConfig::default(), 42);
+//! let inputs = controller::collect_inputs(&mut controllers, &sim);
+//! sim.tick(&inputs);
+//! ```
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use crate::{direction::Direction, replay::Replay, simulation::Simulation};
+
+/// Brain of a snake, deciding where it goes next.
+///
+/// Controller observes the world through the simulation, in the state
+/// right before the upcoming tick, and returns the direction the snake
+/// should turn to. It is up to the caller to feed it into the simulation,
+/// so all snakes are moved by the very same rules.
+///
+pub trait Controller {
+    /// Return the direction the snake of `player` should turn to,
+    /// or `None` to keep going the way it does.
+    ///
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction>;
+
+    /// Return all directions for the upcoming tick, in order. Controllers
+    /// of players that can press several keys between two ticks give more
+    /// than one, all others just the one from `decide`.
+    ///
+    fn inputs(&mut self, sim: &Simulation, player: usize) -> Vec<Direction> {
+        self.decide(sim, player).into_iter().collect()
+    }
+
+    /// Return the reason this controller was disqualified for, once it broke
+    /// the rules, e.g. did not answer in time. Its snake is then taken out of the game.
+    ///
+    fn disqualified(&self) -> Option<String> {
+        None
+    }
+
+    /// Let the controller know the game is over, e.g. to say goodbye to a remote bot.
+    ///
+    fn game_over(&mut self, _sim: &Simulation, _player: usize) {}
+}
+
+impl<F> Controller for F
+where
+    F: FnMut(&Simulation, usize) -> Option<Direction>,
+{
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction> {
+        self(sim, player)
+    }
+}
+
+/// Ask each of the `controllers`, one for each snake in order,
+/// for its inputs for the upcoming tick. Controllers of dead snakes are skipped.
+///
+pub fn collect_inputs(
+    controllers: &mut [Box<dyn Controller>],
+    sim: &Simulation,
+) -> Vec<(usize, Direction)> {
+    let mut inputs = Vec::new();
+    for (player, controller) in controllers.iter_mut().enumerate() {
+        if sim.players.get(player).is_some_and(|p| p.alive) {
+            let dirs = controller.inputs(sim, player);
+            inputs.extend(dirs.into_iter().map(|dir| (player, dir)));
+        }
+    }
+    inputs
+}
+
+/// Take the snakes of all disqualified `controllers` that are still alive
+/// out of the game. Returns the players taken out, with the reasons.
+///
+pub fn disqualify(
+    controllers: &[Box<dyn Controller>],
+    sim: &mut Simulation,
+) -> Vec<(usize, String)> {
+    let mut disqualified = Vec::new();
+    for (player, controller) in controllers.iter().enumerate() {
+        if !sim.players.get(player).is_some_and(|p| p.alive) {
+            continue;
+        }
+        if let Some(reason) = controller.disqualified() {
+            sim.disqualify(player);
+            disqualified.push((player, reason));
+        }
+    }
+    disqualified
+}
+
+/// Let all `controllers` know the game is over, including the ones of dead snakes.
+///
+pub fn game_over(controllers: &mut [Box<dyn Controller>], sim: &Simulation) {
+    for (player, controller) in controllers.iter_mut().enumerate() {
+        controller.game_over(sim, player);
+    }
+}
+
+/// Controller fed with directions from the outside, like the keyboard
+/// or the network. Clones share the same queue, so one of them can be
+/// handed over to the simulation while the other one receives directions,
+/// also from another thread.
+///
+#[derive(Clone, Default)]
+pub struct InputQueue {
+    queue: Arc<Mutex<VecDeque<Direction>>>,
+}
+
+impl InputQueue {
+    /// Create new, empty `InputQueue`.
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a direction for the upcoming tick.
+    ///
+    pub fn push(&self, dir: Direction) {
+        self.lock().push_back(dir);
+    }
+
+    /// Forget all directions queued so far.
+    ///
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<Direction>> {
+        // Queue of directions stays valid even if some other holder panicked
+        self.queue.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl Controller for InputQueue {
+    fn decide(&mut self, _sim: &Simulation, _player: usize) -> Option<Direction> {
+        self.lock().pop_front()
+    }
+
+    fn inputs(&mut self, _sim: &Simulation, _player: usize) -> Vec<Direction> {
+        self.lock().drain(..).collect()
+    }
+}
+
+/// Controller giving fixed directions on fixed ticks, e.g. recorded in a replay.
+/// Snake can be also scripted to be disqualified on a given tick.
+///
+#[derive(Clone, Debug, Default)]
+pub struct Scripted {
+    moves: Vec<(u64, Direction)>,
+    cursor: usize,
+    out_tick: Option<u64>,
+    is_out: bool,
+}
+
+impl Scripted {
+    /// Create new `Scripted` controller giving each of the `moves` on its tick.
+    /// Moves are expected to be sorted by their ticks.
+    ///
+    pub fn new(moves: Vec<(u64, Direction)>) -> Self {
+        Self {
+            moves,
+            cursor: 0,
+            out_tick: None,
+            is_out: false,
+        }
+    }
+
+    /// Make the controller disqualified once the simulation reaches `tick`.
+    ///
+    pub fn disqualify_at(mut self, tick: u64) -> Self {
+        self.out_tick = Some(tick);
+        self
+    }
+
+    /// Create controller repeating the inputs of `player` recorded in the `replay`,
+    /// disqualified on the same tick it was in the recorded game.
+    ///
+    pub fn from_replay(replay: &Replay, player: usize) -> Self {
+        let scripted = Self::new(
+            replay
+                .inputs
+                .iter()
+                .filter(|&&(_, input_player, _)| input_player == player)
+                .map(|&(tick, _, dir)| (tick, dir))
+                .collect(),
+        );
+        match replay.disqualified.iter().find(|&&(_, out)| out == player) {
+            Some(&(tick, _)) => scripted.disqualify_at(tick),
+            None => scripted,
+        }
+    }
+
+    /// Create controllers of all snakes of the `replay`, repeating the recorded game.
+    ///
+    pub fn all_from_replay(replay: &Replay) -> Vec<Box<dyn Controller>> {
+        (0..replay.config.snake_count())
+            .map(|player| Box::new(Self::from_replay(replay, player)) as Box<dyn Controller>)
+            .collect()
+    }
+
+    /// Start giving moves from the first one again.
+    ///
+    pub fn rewind(&mut self) {
+        self.cursor = 0;
+        self.is_out = false;
+    }
+}
+
+impl Controller for Scripted {
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction> {
+        self.inputs(sim, player).pop()
+    }
+
+    fn inputs(&mut self, sim: &Simulation, _player: usize) -> Vec<Direction> {
+        if self.out_tick.is_some_and(|tick| sim.tick >= tick) {
+            self.is_out = true;
+            return Vec::new();
+        }
+        let mut inputs = Vec::new();
+        while let Some(&(tick, dir)) = self.moves.get(self.cursor) {
+            if tick > sim.tick {
+                break;
+            }
+            if tick == sim.tick {
+                inputs.push(dir);
+            }
+            self.cursor += 1;
+        }
+        inputs
+    }
+
+    fn disqualified(&self) -> Option<String> {
+        self.is_out
+            .then(|| "disqualified in the recorded game".to_string())
+    }
+}
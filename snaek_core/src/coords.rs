@@ -1,10 +1,15 @@
 //! Module with Coords struct
-use rand::distributions::{Distribution, Uniform};
+use serde::{Deserialize, Serialize};
+
+use rand::{
+    distributions::{Distribution, Uniform},
+    Rng,
+};
 use std::ops::{Add, AddAssign, Mul, Sub};
 
 /// Structure for holding 2D coordinates
 ///
-#[derive(Clone, Copy, PartialEq, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Coords {
     pub x: f32,
     pub y: f32,
@@ -17,15 +22,15 @@ impl Coords {
         Self { x, y }
     }
 
-    /// Construct a random Coords struct with both coordinates between min and max
+    /// Construct a random Coords struct with each coordinate between
+    /// the respective coordinates of min and max
     ///
-    pub fn random(min: f32, max: f32) -> Self {
-        let mut rng = rand::thread_rng();
-        let xrand = Uniform::from(min..max);
-        let yrand = Uniform::from(min..max);
+    pub fn random<R: Rng + ?Sized>(min: Coords, max: Coords, rng: &mut R) -> Self {
+        let xrand = Uniform::from(min.x..max.x);
+        let yrand = Uniform::from(min.y..max.y);
         Self {
-            x: xrand.sample(&mut rng),
-            y: yrand.sample(&mut rng),
+            x: xrand.sample(rng),
+            y: yrand.sample(rng),
         }
     }
 }
@@ -93,11 +98,11 @@ impl Mul<Coords> for f32 {
     }
 }
 
-impl Into<ggez::mint::Point2<f32>> for Coords {
-    fn into(self) -> ggez::mint::Point2<f32> {
-        ggez::mint::Point2 {
-            x: self.x,
-            y: self.y,
+impl From<Coords> for mint::Point2<f32> {
+    fn from(coords: Coords) -> Self {
+        mint::Point2 {
+            x: coords.x,
+            y: coords.y,
         }
     }
 }
@@ -1,8 +1,10 @@
-use crate::game::coords::Coords;
+use crate::coords::Coords;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
 
 /// Enumeration for 4 main directions
 ///
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -33,3 +35,23 @@ impl Direction {
         Coords { x, y }
     }
 }
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Up" => Ok(Direction::Up),
+            "Down" => Ok(Direction::Down),
+            "Left" => Ok(Direction::Left),
+            "Right" => Ok(Direction::Right),
+            _ => Err(format!("Unknown direction '{}'", s)),
+        }
+    }
+}
@@ -0,0 +1,582 @@
+//! Module with a gym-style environment for reinforcement learning
+//!
+//! `Env` lets an agent steer the first snake of a `Simulation`, while
+//! the configured bots steer the others. `reset` starts a new episode with
+//! the given seed and `step` runs the chosen action for a few ticks, returning
+//! what the agent observes, its reward, whether the episode is done and
+//! some additional info.
+//!
+//! Unlike grid snake environments, the snake moves continuously and only
+//! turns once its turn timer allows it, so an action may take effect a few
+//! ticks later and the observations are measured in pixels, not cells.
+use std::{convert::TryFrom, f32::consts::TAU};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai,
+    config::{ConfigError, GameConfig},
+    consts,
+    controller::{self, Controller},
+    coords::Coords,
+    direction::Direction,
+    line::Line,
+    rect::Rect,
+    segment::{Collidable, SegmentKind},
+    simulation::Simulation,
+    snake::Snake,
+    turn::Turn,
+};
+
+/// Index of the player steered by the agent
+pub const AGENT: usize = 0;
+/// Number of channels of the occupancy grid, see `Grid`
+pub const GRID_CHANNELS: usize = 5;
+/// Number of distances measured along each ray, see `Observation::rays`
+pub const RAY_VALUES: usize = 3;
+
+/// Action of the agent: keep going or turn to the given direction
+///
+#[derive(PartialEq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub enum Action {
+    #[default]
+    Keep,
+    Turn(Direction),
+}
+
+impl Action {
+    /// All actions, in the order of their indices in a discrete action space
+    pub const ALL: [Action; 5] = [
+        Action::Keep,
+        Action::Turn(Direction::Up),
+        Action::Turn(Direction::Down),
+        Action::Turn(Direction::Left),
+        Action::Turn(Direction::Right),
+    ];
+
+    /// Return the action with the given index in `Action::ALL`.
+    ///
+    pub fn from_index(idx: usize) -> Option<Self> {
+        Self::ALL.get(idx).copied()
+    }
+
+    /// Return the direction the snake should turn to, if any.
+    ///
+    pub fn dir(&self) -> Option<Direction> {
+        match self {
+            Action::Keep => None,
+            Action::Turn(dir) => Some(*dir),
+        }
+    }
+}
+
+impl From<Option<Direction>> for Action {
+    fn from(dir: Option<Direction>) -> Self {
+        dir.map_or(Action::Keep, Action::Turn)
+    }
+}
+
+/// What the agent observes after every step
+///
+/// Every part can be turned on or off on its own, the ones turned
+/// off are left out of the observation.
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ObservationConfig {
+    /// Size of the occupancy grid cells in pixels, 0 turns the grid off
+    pub grid_cell: f32,
+    /// Number of rays cast from the head, evenly spread around it, 0 turns them off
+    pub rays: usize,
+    /// Whether to include the raw segments of all snakes and the food
+    pub segments: bool,
+}
+
+impl Default for ObservationConfig {
+    fn default() -> Self {
+        Self {
+            grid_cell: consts::SNAKE_WIDTH,
+            rays: 8,
+            segments: false,
+        }
+    }
+}
+
+/// Reward shaping: how much every event is worth to the agent
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RewardConfig {
+    /// Reward for every food eaten
+    pub food: f32,
+    /// Reward for dying, usually negative
+    pub death: f32,
+    /// Reward for every second survived
+    pub survival: f32,
+}
+
+impl Default for RewardConfig {
+    fn default() -> Self {
+        Self {
+            food: 1.,
+            death: -1.,
+            survival: 0.,
+        }
+    }
+}
+
+/// All parameters of the environment
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EnvConfig {
+    /// Game played in every episode, its `players` are ignored
+    /// as the agent is the only one
+    pub game: GameConfig,
+    /// Ticks run by every step, the action is queued on the first one
+    pub ticks_per_step: u32,
+    /// Ticks after which the episode is truncated, 0 never truncates it
+    pub max_ticks: u64,
+    pub observation: ObservationConfig,
+    pub reward: RewardConfig,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        Self {
+            game: GameConfig::default(),
+            ticks_per_step: 4,
+            max_ticks: 36000,
+            observation: ObservationConfig::default(),
+            reward: RewardConfig::default(),
+        }
+    }
+}
+
+impl EnvConfig {
+    /// Check that the game can be played with a single agent
+    /// and that all parameters make sense.
+    ///
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |reason: String| Err(ConfigError::Invalid(reason));
+
+        self.game()?;
+        if self.ticks_per_step == 0 {
+            return invalid("ticks_per_step must be positive".to_string());
+        }
+        let cell = self.observation.grid_cell;
+        if !cell.is_finite() || cell < 0. {
+            return invalid(format!("grid_cell must not be negative, got {}", cell));
+        }
+        Ok(())
+    }
+
+    /// Return the game config with the agent as the only player.
+    ///
+    fn game(&self) -> Result<GameConfig, ConfigError> {
+        let game = GameConfig {
+            players: 1,
+            ..self.game.clone()
+        };
+        game.validate()?;
+        Ok(game)
+    }
+}
+
+/// Occupancy grid laid over the arena, stored channel by channel, row by row
+///
+/// Channels are the body of the agent's snake, the tip of its head, the other snakes,
+/// the food and the obstacles. A cell is 1 when anything of the channel
+/// overlaps it and 0 otherwise.
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Grid {
+    pub rows: usize,
+    pub cols: usize,
+    pub data: Vec<f32>,
+}
+
+impl Grid {
+    fn new(config: &GameConfig, cell: f32) -> Self {
+        let rows = (config.screen_height / cell).ceil() as usize;
+        let cols = (config.screen_width / cell).ceil() as usize;
+        Self {
+            rows,
+            cols,
+            data: vec![0.; GRID_CHANNELS * rows * cols],
+        }
+    }
+
+    /// Return the value of the given cell of the channel.
+    ///
+    pub fn get(&self, channel: usize, row: usize, col: usize) -> f32 {
+        self.data[(channel * self.rows + row) * self.cols + col]
+    }
+
+    /// Marks all cells of the channel overlapped by `rect`, wrapping them
+    /// around the arena if asked to and dropping them otherwise.
+    ///
+    fn fill(&mut self, channel: usize, rect: &Rect, cell: f32, wraps: bool) {
+        let span = |beg: f32, end: f32| (beg / cell).floor() as i64..(end / cell).ceil() as i64;
+        let wrap = |idx: i64, len: usize| match wraps {
+            true => Some(idx.rem_euclid(len as i64) as usize),
+            false => usize::try_from(idx).ok().filter(|&idx| idx < len),
+        };
+
+        for row in span(rect.top(), rect.bottom()) {
+            for col in span(rect.left(), rect.right()) {
+                if let (Some(row), Some(col)) = (wrap(row, self.rows), wrap(col, self.cols)) {
+                    self.data[(channel * self.rows + row) * self.cols + col] = 1.;
+                }
+            }
+        }
+    }
+}
+
+/// Owned copy of a snake segment
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RawSegment {
+    Line(Line),
+    Turn(Turn),
+}
+
+impl From<SegmentKind<'_>> for RawSegment {
+    fn from(kind: SegmentKind<'_>) -> Self {
+        match kind {
+            SegmentKind::Line(line) => RawSegment::Line(*line),
+            SegmentKind::Turn(turn) => RawSegment::Turn(*turn),
+        }
+    }
+}
+
+/// Raw state of a snake, with its segments from the head to the tail
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct RawSnake {
+    pub alive: bool,
+    pub dir: Direction,
+    pub segments: Vec<RawSegment>,
+}
+
+/// Raw state of the arena, with the agent's snake first
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct RawState {
+    pub food: Option<Rect>,
+    pub snakes: Vec<RawSnake>,
+}
+
+/// Observation of the agent, with the parts turned on in `ObservationConfig`
+///
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Observation {
+    pub grid: Option<Grid>,
+    /// Distances to the nearest wall or obstacle, snake and food along every
+    /// ray, divided by the diagonal of the arena, 1 when nothing is hit.
+    /// Rays go clockwise, the first one straight ahead.
+    pub rays: Option<Vec<f32>>,
+    pub segments: Option<RawState>,
+}
+
+impl Observation {
+    /// Return the grid and the rays as a single flat feature vector.
+    ///
+    pub fn features(&self) -> Vec<f32> {
+        let grid = self.grid.iter().flat_map(|grid| grid.data.iter());
+        let rays = self.rays.iter().flatten();
+        grid.chain(rays).copied().collect()
+    }
+}
+
+/// Additional info about a step
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct StepInfo {
+    pub tick: u64,
+    pub score: u32,
+    /// Food eaten by the agent during the step
+    pub food_eaten: u32,
+    pub died: bool,
+    /// Whether the agent outlived all the other snakes, which ends the round
+    /// and the episode with it
+    pub won: bool,
+    /// Whether the episode ran out of ticks rather than ended
+    pub truncated: bool,
+}
+
+/// Gym-style environment around the `Simulation`
+///
+pub struct Env {
+    pub config: EnvConfig,
+    sim: Simulation,
+    controllers: Vec<Box<dyn Controller>>,
+}
+
+impl Env {
+    /// Creates new `Env` instance with an episode started with seed 0.
+    ///
+    pub fn new(config: EnvConfig) -> Result<Self, ConfigError> {
+        config.validate()?;
+        let game = config.game()?;
+        Ok(Self {
+            sim: Simulation::new(game.clone(), 0),
+            controllers: Self::controllers(&game),
+            config,
+        })
+    }
+
+    /// Return the agent's placeholder controller followed by the bots.
+    ///
+    fn controllers(game: &GameConfig) -> Vec<Box<dyn Controller>> {
+        let agent = |_: &Simulation, _| None;
+        std::iter::once(Box::new(agent) as Box<dyn Controller>)
+            .chain(ai::bots(game))
+            .collect()
+    }
+
+    /// Return the underlying simulation.
+    ///
+    pub fn sim(&self) -> &Simulation {
+        &self.sim
+    }
+
+    /// Number of actions in the discrete action space.
+    ///
+    pub fn action_count(&self) -> usize {
+        Action::ALL.len()
+    }
+
+    /// Check if the episode is over, either ended by the agent dying or
+    /// winning the round, or truncated.
+    ///
+    pub fn is_done(&self) -> bool {
+        !self.sim.players[AGENT].alive || self.sim.is_over() || self.is_truncated()
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.config.max_ticks > 0 && self.sim.tick >= self.config.max_ticks
+    }
+
+    /// Starts a new episode with the given seed.
+    ///
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.sim.reset(seed);
+        self.controllers = Self::controllers(&self.sim.config);
+        self.observe()
+    }
+
+    /// Runs the `action` for `ticks_per_step` ticks, or until the episode
+    /// is over. Stepping an episode that is already over does nothing.
+    ///
+    pub fn step(&mut self, action: Action) -> (Observation, f32, bool, StepInfo) {
+        let (score, start) = (self.sim.players[AGENT].score, self.sim.tick);
+        let alive = self.sim.players[AGENT].alive;
+        for idx in 0..self.config.ticks_per_step {
+            if self.is_done() {
+                break;
+            }
+            let mut inputs = controller::collect_inputs(&mut self.controllers, &self.sim);
+            if let Some(dir) = action.dir().filter(|_| idx == 0) {
+                inputs.push((AGENT, dir));
+            }
+            self.sim.tick(&inputs);
+        }
+
+        let reward = &self.config.reward;
+        let food_eaten = self.sim.players[AGENT].score - score;
+        let died = alive && !self.sim.players[AGENT].alive;
+        let secs = (self.sim.tick - start) as f32 * self.sim.config.tick_duration();
+        let mut total = reward.food * food_eaten as f32 + reward.survival * secs;
+        if died {
+            total += reward.death;
+        }
+
+        let alive = self.sim.players[AGENT].alive;
+        let won = alive && self.sim.is_over();
+        let info = StepInfo {
+            tick: self.sim.tick,
+            score: self.sim.players[AGENT].score,
+            food_eaten,
+            died,
+            won,
+            truncated: self.is_truncated() && alive && !won,
+        };
+        (self.observe(), total, self.is_done(), info)
+    }
+
+    /// Return what the agent currently observes.
+    ///
+    pub fn observe(&self) -> Observation {
+        let observation = &self.config.observation;
+        Observation {
+            grid: (observation.grid_cell > 0.).then(|| self.grid(observation.grid_cell)),
+            rays: (observation.rays > 0).then(|| self.rays(observation.rays)),
+            segments: observation.segments.then(|| self.raw_state()),
+        }
+    }
+
+    fn grid(&self, cell: f32) -> Grid {
+        let config = &self.sim.config;
+        let wraps = config.mode.wraps();
+        let mut grid = Grid::new(config, cell);
+
+        for (idx, player) in self.sim.players.iter().enumerate() {
+            if !player.alive {
+                continue;
+            }
+            let channel = if idx == AGENT { 0 } else { 2 };
+            for segment in &player.snake.body {
+                grid.fill(channel, &segment.bounding_box(), cell, wraps);
+            }
+        }
+        grid.fill(1, &self.sim.players[AGENT].snake.head_rect(), cell, wraps);
+        if let Some(food) = &self.sim.food {
+            grid.fill(3, &food.bbox, cell, wraps);
+        }
+        for obstacle in &config.obstacles {
+            grid.fill(4, &obstacle.bounding_box(), cell, wraps);
+        }
+        grid
+    }
+
+    fn rays(&self, count: usize) -> Vec<f32> {
+        let config = &self.sim.config;
+        let snake = &self.sim.players[AGENT].snake;
+        let head = snake.body.front().unwrap();
+        let origin = head.end();
+        let range = config.screen_width.hypot(config.screen_height);
+        let offsets = snake.wrap_offsets();
+        let obstacles: Vec<_> = config
+            .obstacles
+            .iter()
+            .map(|obstacle| obstacle.bounding_box())
+            .collect();
+        let snakes = self.snake_rects(snake);
+        let food: Vec<_> = self.sim.food.iter().map(|food| food.bbox).collect();
+        let nearest = |dir: Coords, rects: &[Rect]| {
+            rects
+                .iter()
+                .flat_map(|rect| {
+                    offsets
+                        .iter()
+                        .filter_map(move |&offset| ray_hit(origin, dir, &rect.translated(offset)))
+                })
+                .fold(range, f32::min)
+        };
+
+        let mut rays = Vec::with_capacity(count * RAY_VALUES);
+        for idx in 0..count {
+            let dir = rotate(
+                head.direction().as_coords(),
+                TAU * idx as f32 / count as f32,
+            );
+            let wall = match config.mode.wraps() {
+                true => range,
+                false => wall_distance(origin, dir, config),
+            };
+
+            rays.push(wall.min(nearest(dir, &obstacles)) / range);
+            rays.push(nearest(dir, &snakes) / range);
+            rays.push(nearest(dir, &food) / range);
+        }
+        rays
+    }
+
+    /// Return bounding boxes of all snakes alive a ray from the agent's head
+    /// can hit, leaving out its own segments the head lies in.
+    ///
+    fn snake_rects(&self, agent: &Snake) -> Vec<Rect> {
+        let origin = agent.body.front().unwrap().end();
+        let own = agent
+            .body
+            .iter()
+            .skip(1)
+            .map(|segment| segment.bounding_box())
+            .filter(|rect| !contains(rect, origin));
+        let others = self
+            .sim
+            .players
+            .iter()
+            .enumerate()
+            .filter(|(idx, player)| *idx != AGENT && player.alive)
+            .flat_map(|(_, player)| player.snake.body.iter())
+            .map(|segment| segment.bounding_box());
+        own.chain(others).collect()
+    }
+
+    fn raw_state(&self) -> RawState {
+        RawState {
+            food: self.sim.food.map(|food| food.bbox),
+            snakes: self
+                .sim
+                .players
+                .iter()
+                .map(|player| RawSnake {
+                    alive: player.alive,
+                    dir: player.snake.dir,
+                    segments: player
+                        .snake
+                        .body
+                        .iter()
+                        .map(|segment| segment.kind().into())
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Return `vec` rotated clockwise on the screen by `angle` radians.
+///
+fn rotate(vec: Coords, angle: f32) -> Coords {
+    let (sin, cos) = angle.sin_cos();
+    Coords::new(vec.x * cos - vec.y * sin, vec.x * sin + vec.y * cos)
+}
+
+fn contains(rect: &Rect, point: Coords) -> bool {
+    (rect.left()..=rect.right()).contains(&point.x)
+        && (rect.top()..=rect.bottom()).contains(&point.y)
+}
+
+/// Return the distance along the ray from `origin` in the direction `dir`
+/// to the point it enters `rect`, 0 when it starts inside of it.
+///
+fn ray_hit(origin: Coords, dir: Coords, rect: &Rect) -> Option<f32> {
+    let (mut near, mut far) = (0f32, f32::INFINITY);
+    let axes = [
+        (origin.x, dir.x, rect.left(), rect.right()),
+        (origin.y, dir.y, rect.top(), rect.bottom()),
+    ];
+    for (pos, dir, beg, end) in axes {
+        if dir.abs() < f32::EPSILON {
+            if pos < beg || pos > end {
+                return None;
+            }
+            continue;
+        }
+        let (a, b) = ((beg - pos) / dir, (end - pos) / dir);
+        near = near.max(a.min(b));
+        far = far.min(a.max(b));
+    }
+    (near <= far).then_some(near)
+}
+
+/// Return the distance along the ray to the walls around the arena.
+///
+fn wall_distance(origin: Coords, dir: Coords, config: &GameConfig) -> f32 {
+    let margin = config.wall_margin;
+    let axes = [
+        (origin.x, dir.x, config.screen_width),
+        (origin.y, dir.y, config.screen_height),
+    ];
+    axes.iter()
+        .filter(|(_, dir, _)| dir.abs() >= f32::EPSILON)
+        .map(|&(pos, dir, size)| match dir > 0. {
+            true => (size + margin - pos) / dir,
+            false => (-margin - pos) / dir,
+        })
+        .fold(f32::INFINITY, f32::min)
+        .max(0.)
+}
@@ -0,0 +1,345 @@
+//! Module with bots running as external processes
+//!
+//! External bot is any executable that talks JSON lines over its standard
+//! input and output, so it can be written in any language. Before every tick
+//! the bot receives the whole game state as a single line:
+//!
+//! ```text
+//! {"tick":42,"you":1,"over":false,"arena":{...},"rules":{...},"food":{...},"snakes":[...]}
+//! ```
+//!
+//! * `arena` holds `width`, `height`, `wall_margin`, `mode` and the `obstacles`,
+//! * `rules` hold `ticks_per_sec`, `speed` in pixels per second, `snake_width`,
+//!   `food_size` and `turn_interval`, the seconds that have to pass between two turns,
+//! * `food` is the bounding box of the food, `{"x":..,"y":..,"w":..,"h":..}`,
+//!   or `null` while the arena has no room for it,
+//! * `snakes` are listed in the order of the players, `you` being the index of
+//!   the bot's own one. Each has its `id`, `alive`, `score`, `dir` of the head,
+//!   `head` position, `width` and `segments` from the head to the tail, either
+//!   `{"kind":"line","beg":..,"end":..,"dir":..,"width":..}` or
+//!   `{"kind":"turn","pos":..,"in_dir":..,"out_dir":..,"percentage":..,...}`.
+//!
+//! Bot answers each state with a single line holding the direction to turn to,
+//! `"Up"`, `"Down"`, `"Left"` or `"Right"`, or `null` to keep going. Bot that does
+//! not answer in time, answers with anything else or exits is disqualified.
+//! Answer to the first state may take longer, while the bot is starting up.
+//!
+//! Once the game is over, the bot receives the final state with `"over":true`
+//! and its standard input is closed. Nothing is expected back, the bot gets
+//! the move timeout to exit on its own before it is killed.
+use std::{
+    fmt,
+    io::{self, BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{
+    controller::Controller, coords::Coords, direction::Direction, mode::GameMode,
+    obstacle::Obstacle, rect::Rect, segment::SegmentKind, simulation::Simulation,
+};
+
+/// Time an external bot gets to answer a single state, if not configured otherwise
+pub const DEFAULT_MOVE_TIMEOUT: Duration = Duration::from_millis(100);
+/// Time an external bot gets to answer the first state, giving it a chance to start up
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Error that gets an external bot disqualified
+///
+#[derive(Debug)]
+pub enum BotError {
+    Spawn(io::Error),
+    Io(io::Error),
+    Timeout(Duration),
+    Exited,
+    Malformed(String, serde_json::Error),
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::Spawn(err) => write!(f, "Could not start bot: {}", err),
+            BotError::Io(err) => write!(f, "Could not talk to bot: {}", err),
+            BotError::Timeout(timeout) => {
+                write!(f, "Bot did not answer within {} ms", timeout.as_millis())
+            }
+            BotError::Exited => write!(f, "Bot exited"),
+            BotError::Malformed(line, err) => write!(f, "Bot answered '{}': {}", line, err),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+impl From<io::Error> for BotError {
+    fn from(err: io::Error) -> Self {
+        BotError::Io(err)
+    }
+}
+
+/// Size and contents of the arena, as sent to external bots
+///
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct ArenaState<'a> {
+    pub width: f32,
+    pub height: f32,
+    pub wall_margin: f32,
+    pub mode: GameMode,
+    pub obstacles: &'a [Obstacle],
+}
+
+/// Rules of the movement, as sent to external bots
+///
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct RulesState {
+    pub ticks_per_sec: u32,
+    pub speed: f32,
+    pub snake_width: f32,
+    pub food_size: f32,
+    pub turn_interval: f32,
+}
+
+/// Single snake, as sent to external bots
+///
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct SnakeState<'a> {
+    pub id: usize,
+    pub alive: bool,
+    pub score: u32,
+    pub dir: Direction,
+    pub head: Coords,
+    pub width: f32,
+    pub segments: Vec<SegmentKind<'a>>,
+}
+
+/// Whole game state sent to an external bot before each tick
+///
+#[derive(PartialEq, Clone, Debug, Serialize)]
+pub struct GameState<'a> {
+    pub tick: u64,
+    pub you: usize,
+    /// Whether the game is over, in the last state the bot receives
+    pub over: bool,
+    pub arena: ArenaState<'a>,
+    pub rules: RulesState,
+    pub food: Option<Rect>,
+    pub snakes: Vec<SnakeState<'a>>,
+}
+
+impl<'a> GameState<'a> {
+    /// Create state of the `sim` as seen by the snake of the given player.
+    ///
+    pub fn new(sim: &'a Simulation, you: usize) -> Self {
+        let config = &sim.config;
+        let snakes = sim
+            .players
+            .iter()
+            .enumerate()
+            .map(|(id, player)| SnakeState {
+                id,
+                alive: player.alive,
+                score: player.score,
+                dir: player.snake.dir,
+                head: player.snake.body.front().unwrap().end(),
+                width: player.snake.width,
+                segments: player
+                    .snake
+                    .body
+                    .iter()
+                    .map(|segment| segment.kind())
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            tick: sim.tick,
+            you,
+            over: false,
+            arena: ArenaState {
+                width: config.screen_width,
+                height: config.screen_height,
+                wall_margin: config.wall_margin,
+                mode: config.mode,
+                obstacles: &config.obstacles,
+            },
+            rules: RulesState {
+                ticks_per_sec: config.ticks_per_sec,
+                speed: config.speed,
+                snake_width: config.snake_width,
+                food_size: config.food_size,
+                turn_interval: config.secs_per_input_update(),
+            },
+            food: sim.food.map(|food| food.bbox),
+            snakes,
+        }
+    }
+}
+
+/// Controller backed by an external bot process, see the module docs for the protocol
+///
+pub struct ExternalBot {
+    command: String,
+    child: Option<Child>,
+    stdin: Option<ChildStdin>,
+    answers: Option<Receiver<io::Result<String>>>,
+    timeout: Duration,
+    started: bool,
+    error: Option<BotError>,
+}
+
+impl ExternalBot {
+    /// Spawn the bot with the given `command`, the program followed by its arguments
+    /// separated with whitespace. Bot that fails to start is disqualified right away.
+    ///
+    pub fn spawn(command: &str, timeout: Duration) -> Self {
+        let mut bot = Self {
+            command: command.to_string(),
+            child: None,
+            stdin: None,
+            answers: None,
+            timeout,
+            started: false,
+            error: None,
+        };
+        if let Err(err) = bot.start() {
+            bot.error = Some(err);
+        }
+        bot
+    }
+
+    /// Return the command the bot was started with.
+    ///
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Return the error the bot was disqualified for, if it was.
+    ///
+    pub fn error(&self) -> Option<&BotError> {
+        self.error.as_ref()
+    }
+
+    fn start(&mut self) -> Result<(), BotError> {
+        let mut args = self.command.split_whitespace();
+        let program = args.next().ok_or_else(|| {
+            BotError::Spawn(io::Error::new(io::ErrorKind::InvalidInput, "empty command"))
+        })?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(BotError::Spawn)?;
+
+        // Answers are read on their own thread, so waiting for them can time out
+        let stdout = child.stdout.take().expect("stdout of the bot is piped");
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.stdin = child.stdin.take();
+        self.child = Some(child);
+        self.answers = Some(receiver);
+        Ok(())
+    }
+
+    fn ask(&mut self, sim: &Simulation, player: usize) -> Result<Option<Direction>, BotError> {
+        let (stdin, answers) = match (&mut self.stdin, &self.answers) {
+            (Some(stdin), Some(answers)) => (stdin, answers),
+            _ => return Err(BotError::Exited),
+        };
+        let state = serde_json::to_string(&GameState::new(sim, player))
+            .map_err(|err| BotError::Io(err.into()))?;
+        match writeln!(stdin, "{}", state).and_then(|_| stdin.flush()) {
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => return Err(BotError::Exited),
+            written => written?,
+        }
+
+        let timeout = if self.started {
+            self.timeout
+        } else {
+            self.timeout.max(STARTUP_TIMEOUT)
+        };
+        let line = match answers.recv_timeout(timeout) {
+            Ok(line) => line?,
+            Err(RecvTimeoutError::Timeout) => return Err(BotError::Timeout(timeout)),
+            Err(RecvTimeoutError::Disconnected) => return Err(BotError::Exited),
+        };
+        self.started = true;
+        serde_json::from_str(line.trim()).map_err(|err| BotError::Malformed(line, err))
+    }
+
+    /// Sends the bot the final state of the game and lets it exit,
+    /// killing it if it does not do that in time.
+    ///
+    fn finish(&mut self, sim: &Simulation, player: usize) {
+        if let Some(stdin) = &mut self.stdin {
+            let state = GameState {
+                over: true,
+                ..GameState::new(sim, player)
+            };
+            // Nothing is expected back, bot that went away is not a problem anymore
+            if let Ok(state) = serde_json::to_string(&state) {
+                let _ = writeln!(stdin, "{}", state).and_then(|_| stdin.flush());
+            }
+        }
+        self.stdin = None;
+        if let Some(child) = &mut self.child {
+            let start = Instant::now();
+            while matches!(child.try_wait(), Ok(None)) && start.elapsed() < self.timeout {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.stdin = None;
+        self.answers = None;
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Controller for ExternalBot {
+    fn decide(&mut self, sim: &Simulation, player: usize) -> Option<Direction> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.ask(sim, player) {
+            Ok(dir) => dir,
+            Err(err) => {
+                self.error = Some(err);
+                self.stop();
+                None
+            }
+        }
+    }
+
+    fn disqualified(&self) -> Option<String> {
+        self.error.as_ref().map(BotError::to_string)
+    }
+
+    fn game_over(&mut self, sim: &Simulation, player: usize) {
+        if self.error.is_none() {
+            self.finish(sim, player);
+        }
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -0,0 +1,34 @@
+use crate::config::GameConfig;
+use crate::coords::Coords;
+use crate::rect::Rect;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Structure for holding snake food information
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Food {
+    pub bbox: Rect,
+}
+
+impl Food {
+    /// Construct a `Food` instance with a random position on the screen.
+    ///
+    pub fn random<R: Rng + ?Sized>(config: &GameConfig, rng: &mut R) -> Self {
+        let size = config.food_size;
+        let margin = Coords::new(size, size);
+        Self::at(
+            Coords::random(margin, config.screen_size() - margin, rng),
+            config,
+        )
+    }
+
+    /// Construct a `Food` instance centered on the given `pos`.
+    ///
+    pub fn at(pos: Coords, config: &GameConfig) -> Self {
+        let size = config.food_size;
+        Self {
+            bbox: Rect::new(pos.x - size / 2., pos.y - size / 2., size, size),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Module with the persistent high score table
+use std::{
+    cmp::Reverse,
+    fmt, fs, io,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{json_file, mode::GameMode};
+
+/// How many entries are kept for each game mode
+pub const MAX_HIGH_SCORES: usize = 10;
+
+/// Error that can occur while loading the high score table
+///
+#[derive(Debug)]
+pub enum HighScoreError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+}
+
+impl fmt::Display for HighScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HighScoreError::Io(err) => write!(f, "Could not read high scores: {}", err),
+            HighScoreError::Parse(err) => write!(f, "Could not parse high scores: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for HighScoreError {}
+
+impl json_file::LoadError for HighScoreError {
+    fn is_not_found(&self) -> bool {
+        matches!(self, HighScoreError::Io(err) if err.kind() == io::ErrorKind::NotFound)
+    }
+
+    fn is_corrupt(&self) -> bool {
+        matches!(self, HighScoreError::Parse(_))
+    }
+}
+
+impl From<io::Error> for HighScoreError {
+    fn from(err: io::Error) -> Self {
+        HighScoreError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for HighScoreError {
+    fn from(err: serde_json::Error) -> Self {
+        HighScoreError::Parse(err)
+    }
+}
+
+/// Single entry of the high score table
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub length: f32,
+    pub secs: f32,
+    pub seed: u64,
+    pub mode: GameMode,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl HighScore {
+    /// Return current time as seconds since the Unix epoch.
+    ///
+    pub fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs())
+    }
+
+    /// Return date of the entry in the `YYYY-MM-DD` format.
+    ///
+    pub fn date(&self) -> String {
+        // Civil from days, see http://howardhinnant.github.io/date_algorithms.html
+        let days = (self.timestamp / 86_400) as i64 + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Best results of all game modes, each mode keeping at most `MAX_HIGH_SCORES` entries
+/// sorted from the best one.
+///
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct HighScores {
+    #[serde(default)]
+    pub entries: Vec<HighScore>,
+}
+
+impl HighScores {
+    /// Load high scores from the JSON file under `path`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, HighScoreError> {
+        let mut scores: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        scores.entries.sort_by_key(|entry| Reverse(entry.score));
+        Ok(scores)
+    }
+
+    /// Load high scores from the file under `path`, starting with an empty table
+    /// when it is missing or cannot be loaded, along with the error then.
+    /// Corrupt file is moved aside, so it is not overwritten.
+    ///
+    pub fn load_or_default<P: AsRef<Path>>(path: P) -> (Self, Option<HighScoreError>) {
+        json_file::load_or_default(path.as_ref(), |path| Self::load(path))
+    }
+
+    /// Save high scores into the JSON file under `path`, creating missing directories.
+    ///
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        json_file::save(self, path.as_ref())
+    }
+
+    /// Return entries of the given mode, best first.
+    ///
+    pub fn for_mode(&self, mode: GameMode) -> impl Iterator<Item = &HighScore> {
+        self.entries.iter().filter(move |entry| entry.mode == mode)
+    }
+
+    /// Check if the given score would make it into the table.
+    ///
+    pub fn qualifies(&self, mode: GameMode, score: u32) -> bool {
+        score > 0
+            && (self.for_mode(mode).count() < MAX_HIGH_SCORES
+                || self.for_mode(mode).any(|entry| entry.score < score))
+    }
+
+    /// Insert new entry, dropping the worst one of its mode if there are too many.
+    /// Return its place in the table, starting from 0, if it made it.
+    ///
+    pub fn insert(&mut self, entry: HighScore) -> Option<usize> {
+        let mode = entry.mode;
+        let idx = self
+            .entries
+            .iter()
+            .position(|other| other.score < entry.score)
+            .unwrap_or(self.entries.len());
+        self.entries.insert(idx, entry);
+
+        let mut kept = 0;
+        let mut place = None;
+        let mut current = 0;
+        self.entries.retain(|other| {
+            let is_current = current == idx;
+            current += 1;
+            if other.mode != mode {
+                return true;
+            }
+            kept += 1;
+            if is_current && kept <= MAX_HIGH_SCORES {
+                place = Some(kept - 1);
+            }
+            kept <= MAX_HIGH_SCORES
+        });
+        place
+    }
+}
@@ -0,0 +1,46 @@
+//! Module with the state kept in JSON files between the runs
+//!
+//! High scores and the campaign progress are loaded the same way: a missing
+//! file starts from scratch and a corrupt one is moved aside, so the next
+//! save does not overwrite what might still be recovered by hand.
+use std::{fmt, fs, io, path::Path};
+
+use serde::Serialize;
+
+/// Error of loading a JSON file, telling a missing file from a corrupt one
+///
+pub(crate) trait LoadError: fmt::Display {
+    fn is_not_found(&self) -> bool;
+    fn is_corrupt(&self) -> bool;
+}
+
+/// Load the file under `path` with `load`, falling back to the default
+/// when it is missing or cannot be loaded, along with the error then.
+/// Corrupt file is moved aside, so it is not overwritten.
+///
+pub(crate) fn load_or_default<T, E, F>(path: &Path, load: F) -> (T, Option<E>)
+where
+    T: Default,
+    E: LoadError,
+    F: FnOnce(&Path) -> Result<T, E>,
+{
+    match load(path) {
+        Ok(value) => (value, None),
+        Err(err) if err.is_not_found() => (T::default(), None),
+        Err(err) => {
+            if err.is_corrupt() {
+                let _ = fs::rename(path, path.with_extension("corrupt"));
+            }
+            (T::default(), Some(err))
+        }
+    }
+}
+
+/// Save `value` into the JSON file under `path`, creating missing directories.
+///
+pub(crate) fn save<T: Serialize>(value: &T, path: &Path) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(value)?)
+}
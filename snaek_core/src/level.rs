@@ -0,0 +1,388 @@
+//! Module for loading levels
+//!
+//! Level file is a plain text, line based format, ending with an ASCII map:
+//!
+//! ```text
+//! snaek_level 1
+//! name Corridor
+//! goal score 5
+//! start_len 4
+//! map
+//! ##########
+//! #........#
+//! #.v..*...#
+//! #........#
+//! ##########
+//! ```
+//!
+//! Header holds the format version, the level name and its goal, one of
+//! `endless`, `score N`, `length N` (in snake widths), `survive SECS`
+//! or `food` (eat all of the food placed on the map). Optional `start_len N`,
+//! `mode NAME` and `cell PIXELS` entries override the snake start length,
+//! the game mode and the size of a single map cell, which is the snake width
+//! by default.
+//!
+//! Everything after the `map` line is the arena, one character per cell:
+//! `#` is a wall, `.` or a space is an empty cell, `*` is a food spawn point,
+//! used in the order of appearance, and one of `^`, `v`, `<`, `>` marks
+//! the snake head and the direction it starts moving in.
+use std::{
+    fmt, fs,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, GameConfig, Spawn},
+    coords::Coords,
+    direction::Direction,
+    mode::GameMode,
+    obstacle::Obstacle,
+    rect::Rect,
+    simulation::Simulation,
+};
+
+pub const LEVEL_MAGIC: &str = "snaek_level";
+pub const LEVEL_VERSION: u32 = 1;
+
+/// Error that can occur while loading a level
+///
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Malformed { line: usize, reason: String },
+    Invalid(ConfigError),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(err) => write!(f, "Could not read level: {}", err),
+            LevelError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported level version {}, expected at most {}",
+                version, LEVEL_VERSION
+            ),
+            LevelError::Malformed { line, reason } => {
+                write!(f, "Malformed level at line {}: {}", line, reason)
+            }
+            LevelError::Invalid(err) => write!(f, "Level is not playable: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(err: io::Error) -> Self {
+        LevelError::Io(err)
+    }
+}
+
+impl From<ConfigError> for LevelError {
+    fn from(err: ConfigError) -> Self {
+        LevelError::Invalid(err)
+    }
+}
+
+/// Condition on which a level is won
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    Endless,
+    Score(u32),
+    /// Snake length, in snake widths
+    Length(u32),
+    /// Time survived, in seconds
+    Survive(u32),
+    /// Eat all of the food placed on the map
+    EatAllFood,
+}
+
+impl Goal {
+    /// Check if the goal is reached in the given simulation by the first player.
+    ///
+    pub fn is_reached(&self, sim: &Simulation) -> bool {
+        match *self {
+            Goal::Endless => false,
+            Goal::Score(score) => sim.players[0].score >= score,
+            Goal::Length(length) => {
+                sim.players[0].snake.length() / sim.config.snake_width >= length as f32 - 0.5
+            }
+            Goal::Survive(secs) => sim.elapsed_secs() >= secs as f32,
+            Goal::EatAllFood => sim.food_eaten() as usize >= sim.config.food_spawns.len(),
+        }
+    }
+
+    fn parse(fields: &[&str]) -> Result<Self, String> {
+        let number = |value: &str| {
+            value
+                .parse()
+                .map_err(|_| format!("goal value '{}' is not a number", value))
+        };
+        match fields {
+            ["endless"] => Ok(Goal::Endless),
+            ["score", value] => number(value).map(Goal::Score),
+            ["length", value] => number(value).map(Goal::Length),
+            ["survive", value] => number(value).map(Goal::Survive),
+            ["food"] => Ok(Goal::EatAllFood),
+            _ => Err(format!(
+                "unknown goal '{}', expected one of: endless, score N, length N, survive SECS, food",
+                fields.join(" ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::Endless => write!(f, "Survive as long as you can"),
+            Goal::Score(score) => write!(f, "Reach score {}", score),
+            Goal::Length(length) => write!(f, "Grow to length {}", length),
+            Goal::Survive(secs) => write!(f, "Survive {}:{:02}", secs / 60, secs % 60),
+            Goal::EatAllFood => write!(f, "Eat all of the food"),
+        }
+    }
+}
+
+/// Level loaded from a level file. Everything on the map is kept in cells,
+/// it becomes pixels once the level is turned into a `GameConfig`.
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Level {
+    pub name: String,
+    pub goal: Goal,
+    pub mode: Option<GameMode>,
+    pub start_len: Option<u8>,
+    pub cell: Option<f32>,
+    /// Number of columns and rows of the map
+    pub size: (usize, usize),
+    pub walls: Vec<Rect>,
+    /// Cell of the snake head and the direction it starts moving in
+    pub spawn: (usize, usize, Direction),
+    /// Cells with the food spawn points, in order
+    pub food: Vec<(usize, usize)>,
+}
+
+impl Level {
+    /// Load level from the file under `path`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LevelError> {
+        Self::read_from(BufReader::new(fs::File::open(path)?))
+    }
+
+    /// Parse level from the string in the format described in the module docs.
+    ///
+    pub fn parse(text: &str) -> Result<Self, LevelError> {
+        Self::read_from(text.as_bytes())
+    }
+
+    /// Read level in the format described in the module docs.
+    ///
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Self, LevelError> {
+        let mut name = None;
+        let mut goal = None;
+        let mut mode = None;
+        let mut start_len = None;
+        let mut cell = None;
+        let mut has_header = false;
+        let mut map: Vec<(usize, String)> = Vec::new();
+        let mut map_line = None;
+
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_no = idx + 1;
+            if map_line.is_some() {
+                map.push((line_no, line.trim_end_matches('\r').to_string()));
+                continue;
+            }
+
+            let malformed = |reason: &str| LevelError::Malformed {
+                line: line_no,
+                reason: reason.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            match (has_header, fields.as_slice()) {
+                (_, []) => {}
+                (false, [LEVEL_MAGIC, version]) => {
+                    let version = version
+                        .parse()
+                        .map_err(|_| malformed("version is not a number"))?;
+                    if version == 0 || version > LEVEL_VERSION {
+                        return Err(LevelError::UnsupportedVersion(version));
+                    }
+                    has_header = true;
+                }
+                (false, _) => return Err(malformed("missing level header")),
+                (true, ["name", ..]) => name = Some(line.trim()["name".len()..].trim().to_string()),
+                (true, ["goal", goal_fields @ ..]) => {
+                    goal = Some(Goal::parse(goal_fields).map_err(|err| malformed(&err))?);
+                }
+                (true, ["mode", value]) => {
+                    mode = Some(value.parse().map_err(|err: String| malformed(&err))?);
+                }
+                (true, ["start_len", value]) => {
+                    let value = value
+                        .parse()
+                        .map_err(|_| malformed("start_len must be a number between 1 and 255"))?;
+                    start_len = Some(value);
+                }
+                (true, ["cell", value]) => {
+                    cell = Some(
+                        value
+                            .parse()
+                            .map_err(|_| malformed("cell is not a number"))?,
+                    );
+                }
+                (true, ["map"]) => map_line = Some(line_no),
+                (true, _) => return Err(malformed(&format!("unexpected entry '{}'", line))),
+            }
+        }
+
+        let missing = |line: usize, what: &str| LevelError::Malformed {
+            line,
+            reason: format!("missing {}", what),
+        };
+        if !has_header {
+            return Err(missing(1, "level header"));
+        }
+        let name = name.ok_or_else(|| missing(1, "level name"))?;
+        let goal = goal.ok_or_else(|| missing(1, "level goal"))?;
+        let map_line = map_line.ok_or_else(|| missing(1, "map"))?;
+
+        while map.last().is_some_and(|(_, row)| row.is_empty()) {
+            map.pop();
+        }
+        if map.is_empty() {
+            return Err(missing(map_line, "map rows after the 'map' line"));
+        }
+
+        let width = map[0].1.chars().count();
+        let mut walls: Vec<Rect> = Vec::new();
+        let mut spawn = None;
+        let mut food = Vec::new();
+        for (row, (line_no, line)) in map.iter().enumerate() {
+            let malformed = |reason: String| LevelError::Malformed {
+                line: *line_no,
+                reason,
+            };
+            if line.chars().count() != width {
+                return Err(malformed(format!(
+                    "map row has {} cells, expected {} like the first one",
+                    line.chars().count(),
+                    width
+                )));
+            }
+
+            let mut wall_start = None;
+            for (col, ch) in line.chars().chain(Some('.')).enumerate() {
+                if ch == '#' {
+                    wall_start.get_or_insert(col);
+                    continue;
+                }
+                if let Some(start) = wall_start.take() {
+                    Self::add_wall(&mut walls, start, col, row);
+                }
+                let dir = match ch {
+                    '.' | ' ' => continue,
+                    '*' => {
+                        food.push((col, row));
+                        continue;
+                    }
+                    '^' => Direction::Up,
+                    'v' => Direction::Down,
+                    '<' => Direction::Left,
+                    '>' => Direction::Right,
+                    _ => return Err(malformed(format!("unknown map cell '{}'", ch))),
+                };
+                if spawn.is_some() {
+                    return Err(malformed("map has more than one snake head".to_string()));
+                }
+                spawn = Some((col, row, dir));
+            }
+        }
+        let spawn = spawn.ok_or_else(|| missing(map_line, "snake head on the map"))?;
+        if goal == Goal::EatAllFood && food.is_empty() {
+            return Err(missing(map_line, "food on the map, required by the goal"));
+        }
+
+        Ok(Self {
+            name,
+            goal,
+            mode,
+            start_len,
+            cell,
+            size: (width, map.len()),
+            walls,
+            spawn,
+            food,
+        })
+    }
+
+    /// Add wall spanning columns `start..end` in the `row`, merging it
+    /// with the identical one right above, so walls are few and big.
+    ///
+    fn add_wall(walls: &mut Vec<Rect>, start: usize, end: usize, row: usize) {
+        let (x, w, y) = (start as f32, (end - start) as f32, row as f32);
+        match walls
+            .iter_mut()
+            .find(|wall| wall.x == x && wall.w == w && wall.bottom() == y)
+        {
+            Some(wall) => wall.h += 1.,
+            None => walls.push(Rect::new(x, y, w, 1.)),
+        }
+    }
+
+    /// Create config for playing this level, taking everything
+    /// that the level does not specify from `base`. Levels are always
+    /// played by a single player, without any bots.
+    ///
+    pub fn config(&self, base: &GameConfig) -> Result<GameConfig, LevelError> {
+        let mut config = base.clone();
+        config.players = 1;
+        config.bots = 0;
+        let cell = self.cell.unwrap_or(base.snake_width);
+        let center = |(col, row): (usize, usize)| {
+            Coords::new((col as f32 + 0.5) * cell, (row as f32 + 0.5) * cell)
+        };
+
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        if let Some(start_len) = self.start_len {
+            config.snake_start_len = start_len;
+        }
+        config.screen_width = self.size.0 as f32 * cell;
+        config.screen_height = self.size.1 as f32 * cell;
+        config.obstacles = self
+            .walls
+            .iter()
+            .map(|wall| {
+                Obstacle::Rect(Rect::new(
+                    wall.x * cell,
+                    wall.y * cell,
+                    wall.w * cell,
+                    wall.h * cell,
+                ))
+            })
+            .collect();
+
+        let (col, row, dir) = self.spawn;
+        let head = center((col, row)) + dir.as_coords() * (cell / 2.);
+        config.spawn = Some(Spawn {
+            x: head.x,
+            y: head.y,
+            dir,
+        });
+        config.food_spawns = self.food.iter().copied().map(center).collect();
+
+        config.validate()?;
+        Ok(config)
+    }
+}
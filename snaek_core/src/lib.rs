@@ -0,0 +1,76 @@
+//! # snaek_core
+//! Game logic of snaek_rust, without any dependency on the game engine.
+//!
+//! Contains the snake with its straight and curved segments, the food
+//! and a headless `Simulation` that drives them. Rendering, windowing
+//! and resource loading are left to the frontend.
+pub mod ai;
+pub mod battlesnake;
+pub mod campaign;
+pub mod conditioner;
+pub mod config;
+pub mod consts;
+pub mod controller;
+pub mod coords;
+pub mod direction;
+pub mod env;
+pub mod external;
+pub mod food;
+pub mod highscores;
+mod json_file;
+pub mod level;
+pub mod line;
+pub mod lobby;
+pub mod lockstep;
+pub mod mode;
+pub mod net;
+pub mod obstacle;
+pub mod player;
+pub mod rating;
+pub mod rect;
+pub mod render;
+pub mod replay;
+pub mod rng;
+pub mod rollback;
+pub mod save;
+pub mod segment;
+pub mod simulation;
+pub mod snake;
+pub mod spectate;
+pub mod timestep;
+pub mod tournament;
+pub mod turn;
+
+pub use crate::ai::Difficulty;
+#[cfg(any(test, feature = "test-util"))]
+pub use crate::battlesnake::MockServer;
+pub use crate::battlesnake::{GridProjection, HttpBot};
+pub use crate::campaign::{Campaign, CampaignProgress, LevelStatus};
+pub use crate::config::{ConfigError, GameConfig, Spawn};
+pub use crate::controller::{Controller, InputQueue, Scripted};
+pub use crate::coords::Coords;
+pub use crate::direction::Direction;
+pub use crate::env::{Action, Env, EnvConfig, Observation};
+pub use crate::external::{BotError, ExternalBot};
+pub use crate::food::Food;
+pub use crate::highscores::{HighScore, HighScores};
+pub use crate::level::{Goal, Level, LevelError};
+pub use crate::line::Line;
+pub use crate::lobby::{Discovery, Lobby, LobbyClient};
+pub use crate::lockstep::{LockstepClient, LockstepServer};
+pub use crate::mode::GameMode;
+pub use crate::net::NetError;
+pub use crate::obstacle::Obstacle;
+pub use crate::player::Player;
+pub use crate::rect::Rect;
+pub use crate::replay::{Replay, ReplayError};
+pub use crate::rng::GameRng;
+pub use crate::rollback::RollbackClient;
+pub use crate::save::{SaveError, SavedGame};
+pub use crate::segment::{Collidable, Growable, Segment, SegmentKind};
+pub use crate::simulation::{Simulation, Snapshot, StepEvent};
+pub use crate::snake::Snake;
+pub use crate::spectate::{Broadcaster, Spectator};
+pub use crate::timestep::FixedTimestep;
+pub use crate::tournament::{Tournament, TournamentError, TournamentResults};
+pub use crate::turn::{Turn, TurnType};
@@ -1,30 +1,29 @@
-use ggez::{
-    graphics::{self, Color, Mesh, Rect},
-    Context,
-};
+use serde::{Deserialize, Serialize};
 
-use crate::game::{consts, coords::Coords, direction::Direction};
+use crate::{coords::Coords, direction::Direction, rect::Rect};
 
-use super::segment::{Growable, Renderable};
+use crate::segment::{Collidable, Growable};
 
 /// Straight segment of a snake
 ///
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Line {
     pub beg: Coords,
     pub end: Coords,
     pub dir: Direction,
+    pub width: f32,
 }
 
 impl Line {
     /// Create a new `Line` that starts on the given `pos`
     /// and is aligned in the given direction. Initial length is 0.01.
     ///
-    pub fn new(pos: Coords, dir: Direction) -> Self {
+    pub fn new(pos: Coords, dir: Direction, width: f32) -> Self {
         Self {
             beg: pos,
             end: pos + dir.as_coords() * 0.01,
             dir,
+            width,
         }
     }
 
@@ -69,62 +68,41 @@ impl Growable for Line {
     fn direction(&self) -> Direction {
         self.dir
     }
+
+    fn length(&self) -> f32 {
+        self.size()
+    }
 }
 
-impl Renderable for Line {
+impl Collidable for Line {
     fn bounding_box(&self) -> Rect {
         let (x, y, w, h) = match self.dir {
             Direction::Up => (
-                self.end.x - consts::SNAKE_HALF_WIDTH,
+                self.end.x - self.width / 2.,
                 self.end.y,
-                consts::SNAKE_WIDTH,
+                self.width,
                 (self.end.y - self.beg.y).abs(),
             ),
             Direction::Down => (
-                self.end.x - consts::SNAKE_HALF_WIDTH,
+                self.end.x - self.width / 2.,
                 self.beg.y,
-                consts::SNAKE_WIDTH,
+                self.width,
                 (self.end.y - self.beg.y).abs(),
             ),
             Direction::Left => (
                 self.end.x,
-                self.end.y - consts::SNAKE_HALF_WIDTH,
+                self.end.y - self.width / 2.,
                 (self.end.x - self.beg.x).abs(),
-                consts::SNAKE_WIDTH,
+                self.width,
             ),
             Direction::Right => (
                 self.beg.x,
-                self.end.y - consts::SNAKE_HALF_WIDTH,
+                self.end.y - self.width / 2.,
                 (self.end.x - self.beg.x).abs(),
-                consts::SNAKE_WIDTH,
+                self.width,
             ),
         };
 
         Rect::new(x, y, w, h)
     }
-
-    fn draw(&self, ctx: &mut Context) {
-        let mesh = Mesh::new_rectangle(
-            ctx,
-            graphics::DrawMode::fill(),
-            self.bounding_box(),
-            Color::from_rgb(255, 255, 0),
-        )
-        .unwrap();
-        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
-            .expect("Error while drawing Line");
-
-        #[cfg(feature = "debug")]
-        {
-            let mesh = Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::stroke(1.),
-                self.get_bbox(),
-                Color::from_rgb(255, 0, 0),
-            )
-            .unwrap();
-            graphics::draw(ctx, &mesh, graphics::DrawParam::default())
-                .expect("Error while drawing Line border");
-        }
-    }
 }
@@ -0,0 +1,731 @@
+//! Module with the lobby of online games on the local network
+//!
+//! `LockstepServer` given a `Lobby` does not start the game as soon as it is
+//! full. It advertises itself instead, broadcasting an `Advert` to the
+//! `DISCOVERY_PORT` every `ANNOUNCE_INTERVAL`, where `Discovery` picks it up,
+//! so nothing ever leaves the local network. Players join the lobby with
+//! a `LobbyClient`, pick their names and colours and mark themselves ready.
+//! The first player in the lobby hosts the game: they pick the mode and the
+//! level and start it, which happens once every player is ready. From then on
+//! it is played as any other lockstep game.
+//!
+//! Just like the game, the lobby copes with lost datagrams: clients send their
+//! changes again until the lobby they get back shows them, and the server
+//! sends everybody the whole lobby every `LOBBY_INTERVAL`.
+use std::{
+    io,
+    net::{Ipv4Addr, SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{ConfigError, GameConfig},
+    consts::{MAX_NAME_LEN, SNAKE_COLORS},
+    lockstep::{self, Connection, LockstepClient, DISCONNECT_TIMEOUT, GOODBYES},
+    mode::GameMode,
+    net::{Message, NetError, MAX_DATAGRAM, PROTOCOL_VERSION},
+    rollback::RollbackClient,
+    simulation::Simulation,
+};
+
+/// Port the servers advertise their lobbies to
+pub const DISCOVERY_PORT: u16 = 7173;
+/// How often a server advertises its lobby
+pub const ANNOUNCE_INTERVAL: Duration = Duration::from_millis(500);
+/// How often the server sends the lobby and the clients their changes
+pub const LOBBY_INTERVAL: Duration = Duration::from_millis(100);
+/// How long a game stays listed after its last advert
+const ADVERT_TTL: Duration = Duration::from_secs(2);
+
+/// Lobby of a game, as advertised on the local network
+///
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Advert {
+    pub version: u32,
+    pub name: String,
+    /// Number of players in the lobby
+    pub players: usize,
+    /// Most players the game takes
+    pub slots: usize,
+    pub mode: GameMode,
+    /// Name of the level picked by the host, if any
+    pub level: Option<String>,
+}
+
+/// Player in the lobby
+///
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct Member {
+    pub name: String,
+    /// Index of the colour in the palette of the frontend
+    pub color: u8,
+    pub ready: bool,
+}
+
+/// Lobby as the server sends it to the clients
+///
+#[derive(PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
+pub struct LobbyState {
+    /// Players in the order they joined, the first one hosts the game
+    pub members: Vec<Member>,
+    /// Most players the game takes
+    pub slots: usize,
+    pub mode: GameMode,
+    /// Index of the level picked by the host, if any
+    pub level: Option<usize>,
+    /// Names of the levels the host can pick from
+    pub levels: Vec<String>,
+    /// Why the game could not start, until the lobby changes
+    pub problem: Option<String>,
+}
+
+impl LobbyState {
+    /// Check if there is anybody in the lobby and all of them are ready.
+    ///
+    pub fn all_ready(&self) -> bool {
+        !self.members.is_empty() && self.members.iter().all(|member| member.ready)
+    }
+}
+
+/// Lobby a `LockstepServer` lets the players in through, see the module docs
+///
+pub struct Lobby {
+    name: String,
+    announce: SocketAddr,
+    /// Names of the levels along with the configs to play them with
+    levels: Vec<(String, GameConfig)>,
+}
+
+/// Player in the lobby, as the server sees it
+///
+struct Seat {
+    addr: SocketAddr,
+    member: Member,
+    last_seen: Instant,
+}
+
+/// Players the host started the game with
+///
+pub(crate) struct Seated {
+    pub(crate) addrs: Vec<SocketAddr>,
+    pub(crate) roster: Vec<Member>,
+    pub(crate) config: GameConfig,
+}
+
+impl Lobby {
+    /// Creates new `Lobby` instance advertised on the local network under the given `name`.
+    ///
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            announce: (Ipv4Addr::BROADCAST, DISCOVERY_PORT).into(),
+            levels: Vec::new(),
+        }
+    }
+
+    /// Return the lobby advertised to `addr` only, instead of the whole local network.
+    ///
+    pub fn announce_to(mut self, addr: SocketAddr) -> Self {
+        self.announce = addr;
+        self
+    }
+
+    /// Return the lobby the host can pick the level with the given `name` in,
+    /// played with the `config` made for it. Players and bots still come
+    /// from the config of the server.
+    ///
+    pub fn with_level(mut self, name: &str, config: GameConfig) -> Self {
+        self.levels.push((name.to_string(), config));
+        self
+    }
+
+    /// Lets players in over the `socket`, up to the number of players in the `base`
+    /// config, until the host starts the game. Players silent for `timeout` leave.
+    ///
+    pub(crate) fn run(
+        self,
+        socket: &UdpSocket,
+        base: &GameConfig,
+        timeout: Duration,
+    ) -> Result<Seated, NetError> {
+        socket.set_broadcast(true)?;
+        let mut state = LobbyState {
+            members: Vec::new(),
+            slots: base.players as usize,
+            mode: base.mode,
+            level: None,
+            levels: self.levels.iter().map(|(name, _)| name.clone()).collect(),
+            problem: None,
+        };
+        let mut seats: Vec<Seat> = Vec::new();
+        let mut buf = vec![0; MAX_DATAGRAM];
+        let mut last_advert: Option<Instant> = None;
+        let mut last_update = Instant::now();
+
+        loop {
+            if last_advert.is_none_or(|sent| sent.elapsed() >= ANNOUNCE_INTERVAL) {
+                // Without a network there is nobody to broadcast to, players
+                // can still join when they know the address
+                let _ = Message::Advert(self.advert(&state)).send(socket, Some(self.announce));
+                last_advert = Some(Instant::now());
+            }
+
+            let mut changed = false;
+            let mut problem = None;
+            if let Some((message, addr)) = lockstep::recv(socket, &mut buf)? {
+                let seat = seats.iter().position(|seat| seat.addr == addr);
+                if let Some(seat) = seat {
+                    seats[seat].last_seen = Instant::now();
+                }
+                match (message, seat) {
+                    (Message::Join { version }, _) if version != PROTOCOL_VERSION => {
+                        lockstep::reject_version(socket, addr, version)?;
+                    }
+                    (Message::Join { .. }, Some(_)) => {}
+                    (Message::Join { .. }, None) if seats.len() >= state.slots => {
+                        let reason = "the game is full".to_string();
+                        Message::Rejected { reason }.send(socket, Some(addr))?;
+                    }
+                    (Message::Join { .. }, None) => {
+                        let member = Member {
+                            name: format!("Player {}", seats.len() + 1),
+                            color: free_color(&seats),
+                            ready: false,
+                        };
+                        seats.push(Seat {
+                            addr,
+                            member,
+                            last_seen: Instant::now(),
+                        });
+                        changed = true;
+                    }
+                    (Message::Profile { .. } | Message::Settings { .. } | Message::Start, None) => {
+                        let reason = "you are not in the lobby".to_string();
+                        Message::Rejected { reason }.send(socket, Some(addr))?;
+                    }
+                    (Message::Profile { name, color, ready }, Some(seat)) => {
+                        let member = &mut seats[seat].member;
+                        if let Some(name) = clean_name(&name) {
+                            member.name = name;
+                        }
+                        member.ready = ready;
+                        changed = true;
+                        match color_problem(&seats, seat, color) {
+                            Some(reason) => problem = Some(reason),
+                            None => seats[seat].member.color = color,
+                        }
+                    }
+                    (Message::Settings { mode, level }, Some(0))
+                        if level.is_none_or(|level| level < self.levels.len()) =>
+                    {
+                        state.mode = mode;
+                        state.level = level;
+                        changed = true;
+                    }
+                    (Message::Start, Some(0)) if seats.iter().all(|seat| seat.member.ready) => {
+                        match self.seat(&seats, &state, base) {
+                            Ok(seated) => return Ok(seated),
+                            Err(err) => problem = Some(format!("The game can't start: {}", err)),
+                        }
+                    }
+                    (Message::Leave, Some(seat)) => {
+                        seats.remove(seat);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+
+            let present = seats.len();
+            seats.retain(|seat| seat.last_seen.elapsed() <= timeout);
+            changed |= seats.len() != present;
+            // Another try may go well once the players or the settings change
+            if changed {
+                state.problem = None;
+            }
+            if problem.is_some() && problem != state.problem {
+                state.problem = problem;
+                changed = true;
+            }
+
+            if changed || last_update.elapsed() >= LOBBY_INTERVAL {
+                state.members = seats.iter().map(|seat| seat.member.clone()).collect();
+                for (player, seat) in seats.iter().enumerate() {
+                    let lobby = Message::Lobby {
+                        player,
+                        lobby: state.clone(),
+                    };
+                    lobby.send(socket, Some(seat.addr))?;
+                }
+                last_update = Instant::now();
+            }
+        }
+    }
+
+    fn advert(&self, state: &LobbyState) -> Advert {
+        Advert {
+            version: PROTOCOL_VERSION,
+            name: self.name.clone(),
+            players: state.members.len(),
+            slots: state.slots,
+            mode: state.mode,
+            level: state.level.map(|level| self.levels[level].0.clone()),
+        }
+    }
+
+    /// Sets up the game the host started with the players in the `seats`.
+    /// Fails if the config is not valid for them, as the level was checked
+    /// for the most players, but the snakes spawn elsewhere when there are fewer.
+    ///
+    fn seat(
+        &self,
+        seats: &[Seat],
+        state: &LobbyState,
+        base: &GameConfig,
+    ) -> Result<Seated, ConfigError> {
+        let mut config = match state.level {
+            Some(level) => self.levels[level].1.clone(),
+            None => base.clone(),
+        };
+        config.mode = state.mode;
+        config.players = seats.len() as u8;
+        config.bots = base.bots;
+        config.bot_difficulty = base.bot_difficulty;
+        config.validate()?;
+        Ok(Seated {
+            addrs: seats.iter().map(|seat| seat.addr).collect(),
+            roster: seats.iter().map(|seat| seat.member.clone()).collect(),
+            config,
+        })
+    }
+}
+
+/// Return the first colour nobody in the lobby has yet.
+///
+fn free_color(seats: &[Seat]) -> u8 {
+    (0..=u8::MAX)
+        .find(|&color| seats.iter().all(|seat| seat.member.color != color))
+        .unwrap_or_default()
+}
+
+/// Return why the player in the `seat` cannot take the `color`, if it is not
+/// in the palette or another player has it already.
+///
+fn color_problem(seats: &[Seat], seat: usize, color: u8) -> Option<String> {
+    let name = &seats[seat].member.name;
+    if color as usize >= SNAKE_COLORS.len() {
+        let count = SNAKE_COLORS.len();
+        return Some(format!(
+            "{} picked colour {} of only {}",
+            name,
+            color + 1,
+            count
+        ));
+    }
+    seats
+        .iter()
+        .enumerate()
+        .find(|&(other, other_seat)| other != seat && other_seat.member.color == color)
+        .map(|(_, other)| format!("{} can't take the colour of {}", name, other.member.name))
+}
+
+/// Return the name without surrounding whitespace and control characters,
+/// cut to `MAX_NAME_LEN`, or `None` if nothing is left of it.
+///
+fn clean_name(name: &str) -> Option<String> {
+    let name: String = name
+        .trim()
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_NAME_LEN)
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
+
+/// Game found on the local network
+///
+#[derive(Clone, Debug)]
+pub struct DiscoveredGame {
+    /// Address of the server, to join the lobby at
+    pub addr: SocketAddr,
+    pub advert: Advert,
+    last_seen: Instant,
+}
+
+/// Listener of the adverts of the lobbies on the local network
+///
+/// ```no_run
+/// # use std::{thread, time::Duration};
+/// # use snaek_core::lobby::Discovery;
+/// let mut discovery = Discovery::listen()?;
+/// thread::sleep(Duration::from_secs(1));
+/// discovery.poll()?;
+/// for game in discovery.games() {
+///     println!("{} on {}", game.advert.name, game.addr);
+/// }
+/// # Ok::<(), snaek_core::net::NetError>(())
+/// ```
+///
+pub struct Discovery {
+    socket: UdpSocket,
+    /// Buffer the adverts are received into
+    buf: Vec<u8>,
+    games: Vec<DiscoveredGame>,
+}
+
+impl Discovery {
+    /// Listens for the adverts on the `DISCOVERY_PORT` of every network interface.
+    ///
+    pub fn listen() -> Result<Self, NetError> {
+        Self::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT))
+    }
+
+    /// Listens for the adverts on `addr`.
+    ///
+    pub fn bind<A: ToSocketAddrs>(addr: A) -> Result<Self, NetError> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            buf: vec![0; MAX_DATAGRAM],
+            games: Vec::new(),
+        })
+    }
+
+    /// Return the address the adverts are listened for on.
+    ///
+    pub fn local_addr(&self) -> Result<SocketAddr, NetError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Takes in every advert that arrived, without waiting, and forgets
+    /// the games that were not advertised for a while.
+    ///
+    pub fn poll(&mut self) -> Result<(), NetError> {
+        while let Some((message, addr)) = lockstep::recv(&self.socket, &mut self.buf)? {
+            let advert = match message {
+                Message::Advert(advert) if advert.version == PROTOCOL_VERSION => advert,
+                _ => continue,
+            };
+            let game = DiscoveredGame {
+                addr,
+                advert,
+                last_seen: Instant::now(),
+            };
+            match self.games.iter_mut().find(|known| known.addr == addr) {
+                Some(known) => *known = game,
+                None => self.games.push(game),
+            }
+        }
+        self.games
+            .retain(|game| game.last_seen.elapsed() <= ADVERT_TTL);
+        Ok(())
+    }
+
+    /// Return the games found, in the order they were first advertised. Games
+    /// of servers speaking another version of the protocol are left out.
+    ///
+    pub fn games(&self) -> &[DiscoveredGame] {
+        &self.games
+    }
+}
+
+/// Client of a player in the lobby of a `LockstepServer`
+///
+/// ```no_run
+/// # use std::{thread, time::Duration};
+/// # use snaek_core::{lobby::LobbyClient, lockstep::DISCONNECT_TIMEOUT};
+/// let mut client = LobbyClient::join("127.0.0.1:7171", DISCONNECT_TIMEOUT)?;
+/// client.set_name("Alice");
+/// client.set_ready(true);
+/// client.start();
+/// let game = loop {
+///     if let Some(game) = client.poll()? {
+///         break game;
+///     }
+///     thread::sleep(Duration::from_millis(10));
+/// };
+/// let (client, sim) = game.into_lockstep();
+/// # Ok::<(), snaek_core::net::NetError>(())
+/// ```
+///
+pub struct LobbyClient {
+    socket: UdpSocket,
+    /// Buffer the datagrams are received into
+    buf: Vec<u8>,
+    player: usize,
+    lobby: LobbyState,
+    /// Own profile the server did not confirm yet
+    profile: Option<Member>,
+    /// Mode and level picked by the host the server did not confirm yet
+    settings: Option<(GameMode, Option<usize>)>,
+    starting: bool,
+    started: bool,
+    /// Whether there are changes to send right away
+    unsent: bool,
+    timeout: Duration,
+    last_heard: Instant,
+    last_sent: Instant,
+}
+
+impl LobbyClient {
+    /// Joins the lobby of the server at `addr`, waiting up to `timeout` for it to answer.
+    ///
+    pub fn join<A: ToSocketAddrs>(addr: A, timeout: Duration) -> Result<Self, NetError> {
+        let (socket, (player, lobby)) = lockstep::join(addr, timeout, |message| match message {
+            Message::Lobby { player, lobby } => Some((player, lobby)),
+            _ => None,
+        })?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            buf: vec![0; MAX_DATAGRAM],
+            player,
+            lobby,
+            profile: None,
+            settings: None,
+            starting: false,
+            started: false,
+            unsent: false,
+            timeout: DISCONNECT_TIMEOUT,
+            last_heard: Instant::now(),
+            last_sent: Instant::now(),
+        })
+    }
+
+    /// Return the client giving up on the server after `timeout` of silence.
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Return the index of this player in the lobby, and later in the game.
+    ///
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    /// Check if this player hosts the game, picking the mode and the level and starting it.
+    ///
+    pub fn is_host(&self) -> bool {
+        self.player == 0
+    }
+
+    /// Return the lobby as the server last sent it.
+    ///
+    pub fn lobby(&self) -> &LobbyState {
+        &self.lobby
+    }
+
+    /// Return own profile, with the changes the server did not confirm yet.
+    ///
+    pub fn member(&self) -> &Member {
+        self.profile
+            .as_ref()
+            .unwrap_or(&self.lobby.members[self.player])
+    }
+
+    pub fn set_name(&mut self, name: &str) {
+        let name = name.to_string();
+        self.change_profile(|member| member.name = name);
+    }
+
+    /// Picks the colour with the given index in `consts::SNAKE_COLORS`.
+    /// The server keeps the old one if another player has it already, telling
+    /// why in the `problem` of the lobby.
+    ///
+    pub fn set_color(&mut self, color: u8) {
+        self.change_profile(|member| member.color = color);
+    }
+
+    pub fn set_ready(&mut self, ready: bool) {
+        self.change_profile(|member| member.ready = ready);
+    }
+
+    fn change_profile<F: FnOnce(&mut Member)>(&mut self, change: F) {
+        let mut member = self.member().clone();
+        change(&mut member);
+        self.profile = Some(member);
+        self.unsent = true;
+    }
+
+    /// Return the mode and the level picked by the host, with the changes
+    /// the server did not confirm yet.
+    ///
+    pub fn settings(&self) -> (GameMode, Option<usize>) {
+        self.settings.unwrap_or((self.lobby.mode, self.lobby.level))
+    }
+
+    /// Picks the mode and the level, by its index in the lobby, or none
+    /// for the arena of the server. Only the host can do that.
+    ///
+    pub fn choose(&mut self, mode: GameMode, level: Option<usize>) {
+        self.settings = Some((mode, level));
+        self.unsent = true;
+    }
+
+    /// Asks the server to start the game as soon as every player is ready.
+    /// Only the host can do that. The `problem` of the lobby tells why the
+    /// game did not start when everybody is ready.
+    ///
+    pub fn start(&mut self) {
+        self.starting = true;
+        self.unsent = true;
+    }
+
+    /// Exchanges the changes with the server, without waiting.
+    /// Return the game once the host started it.
+    ///
+    pub fn poll(&mut self) -> Result<Option<StartedGame>, NetError> {
+        while let Some(message) = self.recv()? {
+            self.last_heard = Instant::now();
+            match message {
+                Message::Lobby { player, lobby } => {
+                    // Colour the server refused is not asked for again
+                    if let (Some(profile), Some(member)) =
+                        (&mut self.profile, lobby.members.get(player))
+                    {
+                        let color = profile.color;
+                        let mut others = lobby.members.iter().enumerate();
+                        let taken = others.any(|(other, m)| other != player && m.color == color);
+                        if taken || color as usize >= SNAKE_COLORS.len() {
+                            profile.color = member.color;
+                        }
+                    }
+                    if self.profile.as_ref() == lobby.members.get(player) {
+                        self.profile = None;
+                    }
+                    if self.settings == Some((lobby.mode, lobby.level)) {
+                        self.settings = None;
+                    }
+                    self.player = player;
+                    self.lobby = lobby;
+                }
+                Message::Welcome {
+                    player,
+                    seed,
+                    input_delay,
+                    config,
+                    roster,
+                } => {
+                    self.started = true;
+                    self.socket.set_nonblocking(false)?;
+                    let conn = Connection::new(self.socket.try_clone()?, player, input_delay);
+                    let game = StartedGame {
+                        conn,
+                        sim: Simulation::new(config, seed),
+                        roster,
+                    };
+                    return Ok(Some(game));
+                }
+                Message::Rejected { reason } => return Err(NetError::Rejected(reason)),
+                _ => {}
+            }
+        }
+        if self.last_heard.elapsed() > self.timeout {
+            return Err(NetError::Disconnected);
+        }
+        if self.unsent || self.last_sent.elapsed() >= LOBBY_INTERVAL {
+            self.send()?;
+        }
+        Ok(None)
+    }
+
+    /// Receives the next message from the server, if there is any. Server that
+    /// is gone is not an error until it stays silent for too long.
+    ///
+    fn recv(&mut self) -> Result<Option<Message>, NetError> {
+        match Message::recv(&self.socket, &mut self.buf) {
+            Err(NetError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(None),
+            received => Ok(received?.map(|(message, _)| message)),
+        }
+    }
+
+    /// Sends the changes the server did not confirm yet,
+    /// or just that the player is still there.
+    ///
+    fn send(&mut self) -> Result<(), NetError> {
+        let mut messages = Vec::new();
+        if let Some(member) = &self.profile {
+            messages.push(Message::Profile {
+                name: member.name.clone(),
+                color: member.color,
+                ready: member.ready,
+            });
+        }
+        if let Some((mode, level)) = self.settings {
+            messages.push(Message::Settings { mode, level });
+        }
+        if self.starting {
+            messages.push(Message::Start);
+        }
+        if messages.is_empty() {
+            messages.push(Message::Join {
+                version: PROTOCOL_VERSION,
+            });
+        }
+
+        self.unsent = false;
+        self.last_sent = Instant::now();
+        for message in messages {
+            match message.send(&self.socket, None) {
+                Err(NetError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused => {}
+                sent => sent?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for LobbyClient {
+    fn drop(&mut self) {
+        if !self.started {
+            for _ in 0..GOODBYES {
+                let _ = Message::Leave.send(&self.socket, None);
+            }
+        }
+    }
+}
+
+/// Game the host started, to be played with either of the clients
+///
+pub struct StartedGame {
+    conn: Connection,
+    sim: Simulation,
+    roster: Vec<Member>,
+}
+
+impl StartedGame {
+    /// Return the names and colours of all players, in the order of their snakes.
+    ///
+    pub fn roster(&self) -> &[Member] {
+        &self.roster
+    }
+
+    /// Return the player's snake of the game.
+    ///
+    pub fn player(&self) -> usize {
+        self.conn.player
+    }
+
+    /// Return the simulation of the game, before its first tick.
+    ///
+    pub fn sim(&self) -> &Simulation {
+        &self.sim
+    }
+
+    /// Plays the game waiting for the inputs of the other players.
+    ///
+    pub fn into_lockstep(self) -> (LockstepClient, Simulation) {
+        (LockstepClient::new(self.conn), self.sim)
+    }
+
+    /// Plays the game predicting the inputs of the other players.
+    ///
+    pub fn into_rollback(self) -> (RollbackClient, Simulation) {
+        (RollbackClient::new(self.conn), self.sim)
+    }
+}
@@ -0,0 +1,738 @@
+//! Module with the online multiplayer running the simulation in lockstep
+//!
+//! Every peer runs the same deterministic `Simulation`, so it is enough
+//! to exchange the inputs. Clients send the server the direction of their
+//! snake for each tick, scheduled `input_delay` ticks ahead to hide the
+//! latency. Once the server has the inputs of every player for a tick,
+//! it adds the bots' ones and sends the complete `Frame` to everybody.
+//! Nobody runs a tick before its frame arrives, so a slow peer stalls
+//! the others rather than letting them go out of sync.
+//!
+//! The server runs the simulation as well and compares its state hash after
+//! every tick with the ones the clients report, ending the game on the first
+//! difference. Players that leave or go silent for too long are taken out
+//! of the game on the next tick, on every peer alike.
+//!
+//! Given a `Lobby`, the server lets the players in through it instead,
+//! with the game set up by its host, see the `lobby` module.
+use std::{
+    collections::VecDeque,
+    io,
+    net::{SocketAddr, ToSocketAddrs, UdpSocket},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    ai,
+    config::GameConfig,
+    controller::{self, Controller},
+    direction::Direction,
+    lobby::{Lobby, Member},
+    net::{self, Frame, Message, NetError, MAX_DATAGRAM, PROTOCOL_VERSION},
+    simulation::{Simulation, StepEvent},
+    spectate::{Broadcaster, Delta, MatchFeed},
+};
+
+/// Ticks the inputs are delayed by, unless configured otherwise
+pub const DEFAULT_INPUT_DELAY: u64 = 6;
+/// How long a peer may stay silent before it is considered gone
+pub const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// How often unconfirmed data is sent again while waiting for the other side
+pub(crate) const RESEND_INTERVAL: Duration = Duration::from_millis(15);
+/// Most frames sent in a single message
+const MAX_FRAMES: usize = 64;
+/// Times the end of a game is announced, in case some datagrams get lost
+pub(crate) const GOODBYES: usize = 3;
+
+/// Client of the server, as the server sees it
+///
+struct Peer {
+    addr: SocketAddr,
+    /// Inputs of the player, one for every tick so far
+    inputs: Vec<Option<Direction>>,
+    /// Number of frames the client confirmed to have received
+    acked: usize,
+    /// Number of ticks the hashes of the client were checked for
+    checked: usize,
+    last_seen: Instant,
+    connected: bool,
+}
+
+impl Peer {
+    fn new(addr: SocketAddr) -> Self {
+        Self {
+            addr,
+            inputs: Vec::new(),
+            acked: 0,
+            checked: 0,
+            last_seen: Instant::now(),
+            connected: true,
+        }
+    }
+}
+
+/// Dedicated server relaying the inputs of the players and steering the bots
+///
+pub struct LockstepServer {
+    socket: UdpSocket,
+    /// Buffer the datagrams are received into
+    buf: Vec<u8>,
+    sim: Simulation,
+    input_delay: u64,
+    timeout: Duration,
+    peers: Vec<Peer>,
+    /// Placeholders of the players followed by the bots
+    controllers: Vec<Box<dyn Controller>>,
+    frames: Vec<Frame>,
+    /// State hash after each tick
+    hashes: Vec<u64>,
+    /// Players that left since the last frame
+    dropped: Vec<usize>,
+    desync: Option<(u64, usize)>,
+    /// Lobby the players wait in until the host starts the game
+    lobby: Option<Lobby>,
+    /// Names and colours the players picked in the lobby
+    roster: Vec<Member>,
+    /// Broadcaster to stream the game to, with the name of the game, until it starts
+    broadcast: Option<(Broadcaster, String)>,
+    /// Spectators watching the game
+    feed: Option<MatchFeed>,
+}
+
+impl LockstepServer {
+    /// Creates new `LockstepServer` instance listening on `addr` for
+    /// as many clients as there are players in the `config`.
+    ///
+    pub fn bind<A: ToSocketAddrs>(
+        addr: A,
+        config: GameConfig,
+        seed: u64,
+        input_delay: u64,
+    ) -> Result<Self, NetError> {
+        let socket = UdpSocket::bind(addr)?;
+        socket.set_read_timeout(Some(RESEND_INTERVAL))?;
+        Ok(Self {
+            socket,
+            buf: vec![0; MAX_DATAGRAM],
+            peers: Vec::with_capacity(config.players as usize),
+            controllers: Self::controllers(&config),
+            sim: Simulation::new(config, seed),
+            input_delay: input_delay.max(1),
+            timeout: DISCONNECT_TIMEOUT,
+            frames: Vec::new(),
+            hashes: Vec::new(),
+            dropped: Vec::new(),
+            desync: None,
+            lobby: None,
+            roster: Vec::new(),
+            broadcast: None,
+            feed: None,
+        })
+    }
+
+    /// Return placeholders of the players followed by the bots of the `config`.
+    ///
+    fn controllers(config: &GameConfig) -> Vec<Box<dyn Controller>> {
+        (0..config.players)
+            .map(|_| Box::new(|_: &Simulation, _| None) as Box<dyn Controller>)
+            .chain(ai::bots(config))
+            .collect()
+    }
+
+    /// Return the server with silent clients dropped after `timeout`.
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Return the server streaming the game under the given `name`
+    /// to the spectators of the `broadcaster`.
+    ///
+    pub fn with_broadcast(mut self, broadcaster: &Broadcaster, name: &str) -> Self {
+        self.broadcast = Some((broadcaster.clone(), name.to_string()));
+        self
+    }
+
+    /// Return the server letting the players in through the `lobby`. The config
+    /// the server was created with gives the most players the game takes,
+    /// along with everything the host does not pick.
+    ///
+    pub fn with_lobby(mut self, lobby: Lobby) -> Self {
+        self.lobby = Some(lobby);
+        self
+    }
+
+    /// Return the address the server listens on.
+    ///
+    pub fn local_addr(&self) -> Result<SocketAddr, NetError> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Return the simulation as the server runs it.
+    ///
+    pub fn sim(&self) -> &Simulation {
+        &self.sim
+    }
+
+    /// Return the state hash after each tick played so far.
+    ///
+    pub fn hashes(&self) -> &[u64] {
+        &self.hashes
+    }
+
+    /// Return the names and colours the players picked in the lobby, empty without one.
+    ///
+    pub fn roster(&self) -> &[Member] {
+        &self.roster
+    }
+
+    /// Waits for all players to join and plays the game until it is over
+    /// and every client got all of its frames, or until all clients left.
+    ///
+    pub fn run(&mut self) -> Result<(), NetError> {
+        // Game set up in the lobby is not known until it starts
+        if self.lobby.is_none() {
+            self.start_broadcast();
+        }
+        self.wait_for_players()?;
+        self.start_broadcast();
+        let mut last_resend = Instant::now();
+
+        loop {
+            if let Some((tick, player)) = self.desync {
+                let desync = Message::Desync { tick, player };
+                for _ in 0..GOODBYES {
+                    self.broadcast(&desync)?;
+                }
+                return Err(NetError::Desync { tick, player });
+            }
+            let connected: Vec<_> = self.peers.iter().filter(|peer| peer.connected).collect();
+            let delivered = connected.iter().all(|peer| peer.acked >= self.frames.len());
+            if connected.is_empty() || (self.sim.is_over() && delivered) {
+                return Ok(());
+            }
+
+            if let Some((message, addr)) = self.recv()? {
+                self.handle(message, addr)?;
+            }
+            self.drop_silent();
+            if self.advance() || last_resend.elapsed() >= RESEND_INTERVAL {
+                for player in 0..self.peers.len() {
+                    self.send_frames(player)?;
+                }
+                last_resend = Instant::now();
+            }
+        }
+    }
+
+    fn start_broadcast(&mut self) {
+        if let Some((broadcaster, name)) = self.broadcast.take() {
+            self.feed = Some(broadcaster.start_match(&name, &self.sim, &self.roster));
+        }
+    }
+
+    /// Lets clients in until the game is full, or the host of the lobby
+    /// starts it, then welcomes them all.
+    ///
+    fn wait_for_players(&mut self) -> Result<(), NetError> {
+        if let Some(lobby) = self.lobby.take() {
+            let seated = lobby.run(&self.socket, &self.sim.config, self.timeout)?;
+            self.controllers = Self::controllers(&seated.config);
+            self.sim = Simulation::new(seated.config, self.sim.seed);
+            self.roster = seated.roster;
+            for addr in seated.addrs {
+                self.peers.push(Peer::new(addr));
+            }
+        }
+        while self.peers.len() < self.sim.config.players as usize {
+            let (message, addr) = match self.recv()? {
+                Some(received) => received,
+                None => continue,
+            };
+            match message {
+                Message::Join { version } if version != PROTOCOL_VERSION => {
+                    reject_version(&self.socket, addr, version)?;
+                }
+                Message::Join { .. } if self.peer(addr).is_none() => {
+                    self.peers.push(Peer::new(addr))
+                }
+                Message::Leave => self.peers.retain(|peer| peer.addr != addr),
+                _ => {}
+            }
+        }
+
+        for player in 0..self.peers.len() {
+            self.peers[player].last_seen = Instant::now();
+            self.welcome(player)?;
+        }
+        Ok(())
+    }
+
+    fn recv(&mut self) -> Result<Option<(Message, SocketAddr)>, NetError> {
+        recv(&self.socket, &mut self.buf)
+    }
+
+    fn peer(&self, addr: SocketAddr) -> Option<usize> {
+        self.peers.iter().position(|peer| peer.addr == addr)
+    }
+
+    fn welcome(&self, player: usize) -> Result<(), NetError> {
+        let welcome = Message::Welcome {
+            player,
+            seed: self.sim.seed,
+            input_delay: self.input_delay,
+            config: self.sim.config.clone(),
+            roster: self.roster.clone(),
+        };
+        welcome.send(&self.socket, Some(self.peers[player].addr))
+    }
+
+    fn handle(&mut self, message: Message, addr: SocketAddr) -> Result<(), NetError> {
+        let player = match self.peer(addr) {
+            Some(player) if self.peers[player].connected => player,
+            Some(_) => {
+                let reason = "you were dropped from the game".to_string();
+                return Message::Rejected { reason }.send(&self.socket, Some(addr));
+            }
+            None => {
+                let reason = "the game has already started".to_string();
+                return Message::Rejected { reason }.send(&self.socket, Some(addr));
+            }
+        };
+        self.peers[player].last_seen = Instant::now();
+
+        match message {
+            // Client still in the lobby missed its welcome
+            Message::Join { .. }
+            | Message::Profile { .. }
+            | Message::Settings { .. }
+            | Message::Start => self.welcome(player)?,
+            Message::Inputs {
+                ack,
+                first,
+                inputs,
+                hashes,
+            } => {
+                let peer = &mut self.peers[player];
+                peer.acked = peer.acked.max(ack as usize);
+                for (tick, input) in (first as usize..).zip(inputs) {
+                    if tick == peer.inputs.len() {
+                        peer.inputs.push(input);
+                    }
+                }
+                let differs = |&(tick, hash): &(u64, u64)| {
+                    self.hashes
+                        .get(tick as usize)
+                        .is_some_and(|&own| own != hash)
+                };
+                if let Some(&(tick, _)) = hashes.iter().find(|hash| differs(hash)) {
+                    self.desync = Some((tick, player));
+                }
+                if let Some(&(tick, _)) = hashes.last() {
+                    let checked = &mut self.peers[player].checked;
+                    *checked = (*checked).max(tick as usize + 1);
+                }
+            }
+            Message::Leave => self.disconnect(player),
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn disconnect(&mut self, player: usize) {
+        if self.peers[player].connected {
+            self.peers[player].connected = false;
+            self.dropped.push(player);
+        }
+    }
+
+    fn drop_silent(&mut self) {
+        for player in 0..self.peers.len() {
+            if self.peers[player].last_seen.elapsed() > self.timeout {
+                self.disconnect(player);
+            }
+        }
+    }
+
+    /// Plays every tick all connected players sent their inputs for.
+    /// Return whether there are new frames.
+    ///
+    fn advance(&mut self) -> bool {
+        let start = self.frames.len();
+        while !self.sim.is_over() {
+            let tick = self.frames.len();
+            let ready = self
+                .peers
+                .iter()
+                .all(|peer| !peer.connected || peer.inputs.len() > tick);
+            if !ready {
+                break;
+            }
+
+            let mut inputs: Vec<_> = self
+                .peers
+                .iter()
+                .map(|peer| peer.inputs.get(tick).copied().flatten())
+                .collect();
+            inputs.resize(self.sim.players.len(), None);
+            for (player, dir) in controller::collect_inputs(&mut self.controllers, &self.sim) {
+                inputs[player] = Some(dir);
+            }
+            let frame = Frame {
+                inputs,
+                dropped: std::mem::take(&mut self.dropped),
+            };
+            frame.apply(&mut self.sim);
+            if let Some(feed) = &self.feed {
+                feed.push(Delta::from(&frame));
+            }
+            self.hashes.push(net::state_hash(&self.sim));
+            self.frames.push(frame);
+        }
+        self.frames.len() > start
+    }
+
+    fn send_frames(&self, player: usize) -> Result<(), NetError> {
+        let peer = &self.peers[player];
+        if !peer.connected || peer.acked >= self.frames.len() {
+            return Ok(());
+        }
+        let end = self.frames.len().min(peer.acked + MAX_FRAMES);
+        let frames = Message::Frames {
+            first: peer.acked as u64,
+            frames: self.frames[peer.acked..end].to_vec(),
+            checked: peer.checked as u64,
+        };
+        frames.send(&self.socket, Some(peer.addr))
+    }
+
+    fn broadcast(&self, message: &Message) -> Result<(), NetError> {
+        for peer in self.peers.iter().filter(|peer| peer.connected) {
+            message.send(&self.socket, Some(peer.addr))?;
+        }
+        Ok(())
+    }
+}
+
+/// Receives the next message into `buf`, skipping malformed ones anybody could send.
+///
+pub(crate) fn recv(
+    socket: &UdpSocket,
+    buf: &mut [u8],
+) -> Result<Option<(Message, SocketAddr)>, NetError> {
+    match Message::recv(socket, buf) {
+        Err(NetError::Malformed(_)) => Ok(None),
+        received => received,
+    }
+}
+
+/// Tells the client at `addr` the server does not speak its protocol `version`.
+///
+pub(crate) fn reject_version(
+    socket: &UdpSocket,
+    addr: SocketAddr,
+    version: u32,
+) -> Result<(), NetError> {
+    let reason = format!(
+        "protocol version {} is not supported, the server speaks {}",
+        version, PROTOCOL_VERSION
+    );
+    Message::Rejected { reason }.send(socket, Some(addr))
+}
+
+/// Asks the server at `addr` to let the client in until `answer` makes something
+/// of its reply, for up to `timeout`. Return the socket connected to the server
+/// along with the answer.
+///
+pub(crate) fn join<A, T, F>(
+    addr: A,
+    timeout: Duration,
+    mut answer: F,
+) -> Result<(UdpSocket, T), NetError>
+where
+    A: ToSocketAddrs,
+    F: FnMut(Message) -> Option<T>,
+{
+    let server = addr.to_socket_addrs()?.next().ok_or(NetError::Timeout)?;
+    let local: SocketAddr = match server {
+        SocketAddr::V4(_) => ([0, 0, 0, 0], 0).into(),
+        SocketAddr::V6(_) => ([0; 8], 0).into(),
+    };
+    let socket = UdpSocket::bind(local)?;
+    socket.connect(server)?;
+    socket.set_read_timeout(Some(RESEND_INTERVAL * 10))?;
+
+    let join = Message::Join {
+        version: PROTOCOL_VERSION,
+    };
+    let mut buf = vec![0; MAX_DATAGRAM];
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        join.send(&socket, None)?;
+        let message = match Message::recv(&socket, &mut buf) {
+            Ok(Some((message, _))) => message,
+            // Nobody listens on the port yet
+            Err(NetError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused => {
+                std::thread::sleep(RESEND_INTERVAL * 10);
+                continue;
+            }
+            Ok(None) => continue,
+            Err(err) => return Err(err),
+        };
+        if let Message::Rejected { reason } = message {
+            return Err(NetError::Rejected(reason));
+        }
+        if let Some(answer) = answer(message) {
+            socket.set_read_timeout(Some(RESEND_INTERVAL))?;
+            return Ok((socket, answer));
+        }
+    }
+    Err(NetError::Timeout)
+}
+
+/// Client playing a single snake in a game run by a `LockstepServer`
+///
+/// ```no_run
+/// # use snaek_core::{lockstep::{LockstepClient, DISCONNECT_TIMEOUT}, Direction};
+/// let (mut client, mut sim) = LockstepClient::connect("127.0.0.1:7171", DISCONNECT_TIMEOUT)?;
+/// client.push_input(Direction::Left);
+/// while !sim.is_over() {
+///     client.wait_step(&mut sim)?;
+/// }
+/// # Ok::<(), snaek_core::net::NetError>(())
+/// ```
+///
+pub struct LockstepClient {
+    conn: Connection,
+}
+
+impl LockstepClient {
+    /// Joins the game of the server at `addr`, waiting up to `timeout` for all
+    /// players to join. Return the client with the simulation of the game.
+    ///
+    pub fn connect<A: ToSocketAddrs>(
+        addr: A,
+        timeout: Duration,
+    ) -> Result<(Self, Simulation), NetError> {
+        let (conn, sim) = Connection::open(addr, timeout)?;
+        Ok((Self::new(conn), sim))
+    }
+
+    pub(crate) fn new(conn: Connection) -> Self {
+        Self { conn }
+    }
+
+    /// Return the client giving up on the server after `timeout` of silence.
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.conn.timeout = timeout;
+        self
+    }
+
+    /// Return the index of the player steered by this client.
+    ///
+    pub fn player(&self) -> usize {
+        self.conn.player
+    }
+
+    /// Return the number of ticks the inputs are delayed by.
+    ///
+    pub fn input_delay(&self) -> u64 {
+        self.conn.input_delay
+    }
+
+    /// Queues a new direction for the snake. Each tick takes at most one,
+    /// to be applied `input_delay` ticks later.
+    ///
+    pub fn push_input(&mut self, dir: Direction) {
+        self.conn.push_input(dir);
+    }
+
+    /// Runs the next tick of `sim` if its frame has already arrived, without
+    /// waiting for it. Return what happened in the tick, if it was run.
+    ///
+    pub fn step(&mut self, sim: &mut Simulation) -> Result<Option<StepEvent>, NetError> {
+        self.conn.socket.set_nonblocking(true)?;
+        self.conn.receive()?;
+        self.run_tick(sim)
+    }
+
+    /// Runs the next tick of `sim`, waiting for its frame as long as needed.
+    ///
+    pub fn wait_step(&mut self, sim: &mut Simulation) -> Result<StepEvent, NetError> {
+        loop {
+            if let Some(event) = self.run_tick(sim)? {
+                return Ok(event);
+            }
+            self.conn.receive()?;
+        }
+    }
+
+    fn run_tick(&mut self, sim: &mut Simulation) -> Result<Option<StepEvent>, NetError> {
+        let conn = &mut self.conn;
+        let event = match conn.frames.get(sim.tick as usize) {
+            Some(frame) => {
+                let event = frame.apply(sim);
+                conn.hashes.push((sim.tick - 1, net::state_hash(sim)));
+                conn.schedule_input();
+                Some(event)
+            }
+            None => None,
+        };
+        if event.is_some() || conn.last_sent.elapsed() >= RESEND_INTERVAL {
+            conn.send()?;
+        }
+        Ok(event)
+    }
+}
+
+/// Client side of the protocol, shared by the lockstep and rollback clients:
+/// own inputs on their way to the server and frames on their way back
+///
+pub(crate) struct Connection {
+    pub(crate) socket: UdpSocket,
+    /// Buffer the datagrams are received into
+    buf: Vec<u8>,
+    pub(crate) player: usize,
+    pub(crate) input_delay: u64,
+    pub(crate) timeout: Duration,
+    /// Own inputs, one for every tick up to `input_delay` ticks ahead
+    inputs: Vec<Option<Direction>>,
+    /// Directions waiting to be scheduled, one per tick
+    queued: VecDeque<Direction>,
+    /// Frames confirmed by the server
+    pub(crate) frames: Vec<Frame>,
+    /// Hashes of the confirmed ticks run, that the server did not check yet
+    pub(crate) hashes: Vec<(u64, u64)>,
+    last_heard: Instant,
+    pub(crate) last_sent: Instant,
+}
+
+impl Connection {
+    /// Joins the game of the server at `addr`, waiting up to `timeout` for all
+    /// players to join. Return the connection with the simulation of the game.
+    ///
+    pub(crate) fn open<A: ToSocketAddrs>(
+        addr: A,
+        timeout: Duration,
+    ) -> Result<(Self, Simulation), NetError> {
+        let (socket, (player, seed, input_delay, config)) =
+            join(addr, timeout, |message| match message {
+                Message::Welcome {
+                    player,
+                    seed,
+                    input_delay,
+                    config,
+                    ..
+                } => Some((player, seed, input_delay, config)),
+                _ => None,
+            })?;
+        let conn = Self::new(socket, player, input_delay);
+        Ok((conn, Simulation::new(config, seed)))
+    }
+
+    /// Creates new `Connection` instance over the `socket` connected to the server,
+    /// once it let the client in as the given `player`.
+    ///
+    pub(crate) fn new(socket: UdpSocket, player: usize, input_delay: u64) -> Self {
+        Self {
+            socket,
+            buf: vec![0; MAX_DATAGRAM],
+            player,
+            input_delay,
+            timeout: DISCONNECT_TIMEOUT,
+            inputs: vec![None; input_delay as usize],
+            queued: VecDeque::new(),
+            frames: Vec::new(),
+            hashes: Vec::new(),
+            last_heard: Instant::now(),
+            last_sent: Instant::now(),
+        }
+    }
+
+    pub(crate) fn push_input(&mut self, dir: Direction) {
+        if self.queued.back() != Some(&dir) {
+            self.queued.push_back(dir);
+        }
+    }
+
+    /// Return own input for the given tick, if it is already scheduled.
+    ///
+    pub(crate) fn input(&self, tick: u64) -> Option<Option<Direction>> {
+        self.inputs.get(tick as usize).copied()
+    }
+
+    /// Schedules the next queued direction `input_delay` ticks ahead,
+    /// once a tick that was not run before has been run.
+    ///
+    pub(crate) fn schedule_input(&mut self) {
+        self.inputs.push(self.queued.pop_front());
+    }
+
+    /// Receives the next message from the server, if there is any. Server that
+    /// is gone is not an error until it stays silent for too long.
+    ///
+    fn recv(&mut self) -> Result<Option<Message>, NetError> {
+        match Message::recv(&self.socket, &mut self.buf) {
+            Err(NetError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(None),
+            received => Ok(received?.map(|(message, _)| message)),
+        }
+    }
+
+    /// Receives all messages there are, waiting up to the socket's timeout for the first one.
+    ///
+    pub(crate) fn receive(&mut self) -> Result<(), NetError> {
+        while let Some(message) = self.recv()? {
+            self.last_heard = Instant::now();
+            match message {
+                Message::Frames {
+                    first,
+                    frames,
+                    checked,
+                } => {
+                    for (tick, frame) in (first as usize..).zip(frames) {
+                        if tick == self.frames.len() {
+                            self.frames.push(frame);
+                        }
+                    }
+                    self.hashes.retain(|&(tick, _)| tick >= checked);
+                }
+                Message::Desync { tick, player } => return Err(NetError::Desync { tick, player }),
+                Message::Rejected { reason } => return Err(NetError::Rejected(reason)),
+                _ => {}
+            }
+            self.socket.set_nonblocking(true)?;
+        }
+        self.socket.set_nonblocking(false)?;
+        if self.last_heard.elapsed() > self.timeout {
+            return Err(NetError::Disconnected);
+        }
+        Ok(())
+    }
+
+    /// Sends the inputs and the oldest hashes the server did not confirm yet.
+    ///
+    pub(crate) fn send(&mut self) -> Result<(), NetError> {
+        let ack = self.frames.len();
+        let inputs = Message::Inputs {
+            ack: ack as u64,
+            first: ack as u64,
+            inputs: self.inputs[ack.min(self.inputs.len())..].to_vec(),
+            hashes: self.hashes.iter().take(MAX_FRAMES).copied().collect(),
+        };
+        self.last_sent = Instant::now();
+        match inputs.send(&self.socket, None) {
+            Err(NetError::Io(err)) if err.kind() == io::ErrorKind::ConnectionRefused => Ok(()),
+            sent => sent,
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        for _ in 0..GOODBYES {
+            let _ = Message::Leave.send(&self.socket, None);
+        }
+    }
+}
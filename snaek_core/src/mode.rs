@@ -0,0 +1,59 @@
+//! Module with GameMode enum
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Enumeration of the rule sets the game can be played with
+///
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GameMode {
+    #[default]
+    Classic,
+    /// Snake leaving the arena on one edge enters it again on the opposite one
+    Wrap,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 2] = [GameMode::Classic, GameMode::Wrap];
+
+    /// Return the name used for this mode in config files and on the command line
+    ///
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Classic => "classic",
+            GameMode::Wrap => "wrap",
+        }
+    }
+
+    /// Check if the arena wraps around its edges in this mode
+    ///
+    pub fn wraps(&self) -> bool {
+        matches!(self, GameMode::Wrap)
+    }
+}
+
+impl fmt::Display for GameMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for GameMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|mode| mode.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(GameMode::name).collect();
+                format!(
+                    "Unknown mode '{}', expected one of: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
@@ -0,0 +1,275 @@
+//! Module with the networking basics shared by the online game modes
+//!
+//! Peers talk over UDP, every datagram holding a single JSON encoded
+//! `Message`. Lost datagrams are never resent as they are: every message
+//! carries all the data the other side did not confirm yet, so the next
+//! one that makes it through makes up for the lost ones.
+use std::{
+    fmt, io,
+    net::{SocketAddr, UdpSocket},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig,
+    direction::Direction,
+    lobby::{Advert, LobbyState, Member},
+    mode::GameMode,
+    segment::SegmentKind,
+    simulation::{Simulation, StepEvent},
+};
+
+/// Version of the protocol, peers with different ones cannot play together
+pub const PROTOCOL_VERSION: u32 = 2;
+/// Port the server listens on by default
+pub const DEFAULT_PORT: u16 = 7171;
+/// Largest datagram peers send to each other
+pub const MAX_DATAGRAM: usize = 65507;
+
+/// Error that can occur while playing online
+///
+#[derive(Debug)]
+pub enum NetError {
+    Io(io::Error),
+    Malformed(serde_json::Error),
+    /// Server refused to let the client join
+    Rejected(String),
+    /// Nobody answered in time
+    Timeout,
+    /// Peer stopped answering in the middle of the game
+    Disconnected,
+    /// State of a player's simulation differed from the server's after the tick
+    Desync {
+        tick: u64,
+        player: usize,
+    },
+}
+
+impl fmt::Display for NetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetError::Io(err) => write!(f, "Network error: {}", err),
+            NetError::Malformed(err) => write!(f, "Malformed message: {}", err),
+            NetError::Rejected(reason) => write!(f, "Rejected by the server: {}", reason),
+            NetError::Timeout => write!(f, "Nobody answered in time"),
+            NetError::Disconnected => write!(f, "Connection lost"),
+            NetError::Desync { tick, player } => write!(
+                f,
+                "Game of player {} went out of sync on tick {}",
+                player + 1,
+                tick
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+impl From<io::Error> for NetError {
+    fn from(err: io::Error) -> Self {
+        NetError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for NetError {
+    fn from(err: serde_json::Error) -> Self {
+        NetError::Malformed(err)
+    }
+}
+
+/// Inputs of all snakes for a single tick, confirmed by the server
+///
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Frame {
+    /// Direction chosen by each snake, if any
+    pub inputs: Vec<Option<Direction>>,
+    /// Players that left the game right before the tick
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dropped: Vec<usize>,
+}
+
+impl Frame {
+    /// Advances `sim` by a tick, taking the dropped players out of the game first.
+    ///
+    pub fn apply(&self, sim: &mut Simulation) -> StepEvent {
+        for &player in &self.dropped {
+            sim.disqualify(player);
+        }
+        let inputs: Vec<_> = self
+            .inputs
+            .iter()
+            .enumerate()
+            .filter_map(|(player, dir)| dir.map(|dir| (player, dir)))
+            .collect();
+        sim.tick(&inputs)
+    }
+}
+
+/// Message exchanged between the server and the clients
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    /// Client asks to join the game
+    Join {
+        version: u32,
+    },
+    /// Server lets the client in as the given player, once everybody joined
+    Welcome {
+        player: usize,
+        seed: u64,
+        input_delay: u64,
+        config: GameConfig,
+        /// Names and colours the players picked in the lobby, if there was one
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        roster: Vec<Member>,
+    },
+    Rejected {
+        reason: String,
+    },
+    /// Server advertises its lobby on the local network
+    Advert(Advert),
+    /// Server sends the client the lobby, in which it is the given player
+    Lobby {
+        player: usize,
+        lobby: LobbyState,
+    },
+    /// Client in the lobby changes its name, colour or whether it is ready
+    Profile {
+        name: String,
+        color: u8,
+        ready: bool,
+    },
+    /// Host of the lobby picks the mode and the level, by its index
+    Settings {
+        mode: GameMode,
+        level: Option<usize>,
+    },
+    /// Host asks to start the game as soon as every player is ready
+    Start,
+    /// Client sends its inputs from the `first` tick on, the number of frames
+    /// it received and the state hashes of the ticks it ran that the server
+    /// did not check yet
+    Inputs {
+        ack: u64,
+        first: u64,
+        inputs: Vec<Option<Direction>>,
+        hashes: Vec<(u64, u64)>,
+    },
+    /// Server sends the frames from the `first` tick on, along with
+    /// the number of ticks it checked the hashes of the client for
+    Frames {
+        first: u64,
+        frames: Vec<Frame>,
+        checked: u64,
+    },
+    /// Server ends the game as the given player went out of sync
+    Desync {
+        tick: u64,
+        player: usize,
+    },
+    /// Client leaves the game
+    Leave,
+}
+
+impl Message {
+    /// Sends the message to `addr`, or to the connected peer if there is no address.
+    ///
+    pub fn send(&self, socket: &UdpSocket, addr: Option<SocketAddr>) -> Result<(), NetError> {
+        let bytes = serde_json::to_vec(self)?;
+        match addr {
+            Some(addr) => socket.send_to(&bytes, addr)?,
+            None => socket.send(&bytes)?,
+        };
+        Ok(())
+    }
+
+    /// Receives the next message along with the address of its sender into
+    /// `buf`, which fits any of them with `MAX_DATAGRAM` bytes.
+    /// Returns `None` when the read timed out or would block.
+    ///
+    pub fn recv(
+        socket: &UdpSocket,
+        buf: &mut [u8],
+    ) -> Result<Option<(Message, SocketAddr)>, NetError> {
+        match socket.recv_from(buf) {
+            Ok((len, addr)) => Ok(Some((serde_json::from_slice(&buf[..len])?, addr))),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// FNV-1a hasher, unlike `DefaultHasher` guaranteed to give the same
+/// hashes on every machine and with every build of the game
+///
+struct StateHasher(u64);
+
+impl StateHasher {
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes(&value.to_bits().to_le_bytes());
+    }
+
+    fn dir(&mut self, dir: Direction) {
+        self.bytes(&[dir as u8]);
+    }
+}
+
+/// Return the hash of the state peers have to agree on after every tick:
+/// bodies of all snakes, whether they are alive, their scores and the food.
+///
+pub fn state_hash(sim: &Simulation) -> u64 {
+    let mut hasher = StateHasher(0xcbf2_9ce4_8422_2325);
+    hasher.u64(sim.tick);
+    for player in &sim.players {
+        hasher.bytes(&[player.alive as u8]);
+        hasher.u64(player.score as u64);
+        hasher.dir(player.snake.dir);
+        for segment in &player.snake.body {
+            match segment.kind() {
+                SegmentKind::Line(line) => {
+                    hasher.bytes(&[0]);
+                    hasher.f32(line.beg.x);
+                    hasher.f32(line.beg.y);
+                    hasher.f32(line.end.x);
+                    hasher.f32(line.end.y);
+                    hasher.dir(line.dir);
+                }
+                SegmentKind::Turn(turn) => {
+                    hasher.bytes(&[1, turn.is_growing as u8]);
+                    hasher.f32(turn.percentage);
+                    hasher.f32(turn.pos.x);
+                    hasher.f32(turn.pos.y);
+                    hasher.dir(turn.in_dir);
+                    hasher.dir(turn.out_dir);
+                }
+            }
+        }
+    }
+    hasher.bytes(&[sim.food.is_some() as u8]);
+    if let Some(food) = sim.food {
+        let food = food.bbox;
+        for value in [food.x, food.y, food.w, food.h] {
+            hasher.f32(value);
+        }
+    }
+    hasher.0
+}
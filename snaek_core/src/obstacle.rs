@@ -0,0 +1,23 @@
+//! Module with static obstacles placed inside the arena
+use serde::{Deserialize, Serialize};
+
+use crate::{rect::Rect, segment::Collidable};
+
+/// Static obstacle that kills the snake on contact.
+///
+/// In config files each obstacle is a table with its `shape` and dimensions, e.g.
+/// `{ shape = "rect", x = 100.0, y = 100.0, w = 40.0, h = 200.0 }`.
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(tag = "shape", rename_all = "snake_case")]
+pub enum Obstacle {
+    Rect(Rect),
+}
+
+impl Collidable for Obstacle {
+    fn bounding_box(&self) -> Rect {
+        match self {
+            Obstacle::Rect(rect) => *rect,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! Module with the state of a single player taking part in the simulation
+use std::collections::VecDeque;
+
+use itertools::{self as it, Itertools};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::GameConfig, direction::Direction, snake::Snake};
+
+/// Snake of a single player together with its pending input and score
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Player {
+    pub snake: Snake,
+    pub prev_snake: Snake,
+    pub inputs: VecDeque<Direction>,
+    pub input_timer: f32,
+    pub score: u32,
+    pub alive: bool,
+    pub(crate) last_move: f32,
+}
+
+impl Player {
+    /// Creates new `Player` controlling the given snake.
+    ///
+    pub fn new(snake: Snake) -> Self {
+        Self {
+            prev_snake: snake.clone(),
+            snake,
+            inputs: VecDeque::new(),
+            input_timer: 0.,
+            score: 0,
+            alive: true,
+            last_move: 0.,
+        }
+    }
+
+    /// Queues a new direction for the snake.
+    /// Repeated directions are ignored.
+    ///
+    pub fn push_input(&mut self, dir: Direction) {
+        if self.inputs.is_empty() || self.inputs.back().unwrap() != &dir {
+            self.inputs.push_front(dir);
+        }
+    }
+
+    /// Processes queued input, capped to `GameConfig::secs_per_input_update`.
+    ///
+    pub fn update_input(&mut self, time_delta: f32, config: &GameConfig) {
+        self.input_timer += time_delta;
+        if self.input_timer < config.secs_per_input_update() {
+            return;
+        }
+
+        if let Some((idx, &new_dir)) =
+            it::rev(&self.inputs).find_position(|dir| !dir.is_colinear(self.snake.dir))
+        {
+            let truncated_len = self.inputs.len() - idx - 1;
+            self.inputs.truncate(truncated_len);
+
+            self.snake.dir = new_dir;
+            self.input_timer = 0.;
+        } else {
+            self.inputs.clear();
+        }
+    }
+
+    /// Return snake state between the previous and the current tick,
+    /// `alpha` being 0 for the previous and 1 for the current one.
+    ///
+    /// Movement of the last tick is replayed partially on the previous state,
+    /// which gives the same shape the snake would have if it moved that far.
+    ///
+    pub fn interpolated_snake(&self, alpha: f32) -> Snake {
+        if self.last_move <= 0. {
+            return self.snake.clone();
+        }
+
+        let mut snake = self.prev_snake.clone();
+        snake.dir = self.snake.dir;
+        snake.do_move(self.last_move * f32::clamp(alpha, 0., 1.));
+        snake
+    }
+}
@@ -0,0 +1,102 @@
+//! Module with the rating systems ranking the bots of a tournament
+//!
+//! `Elo` updates the ratings after every single game, `Glicko` after each
+//! rating period, e.g. a round of a tournament, and also tracks how reliable
+//! each rating is with its rating deviation.
+use std::f64::consts::{LN_10, PI};
+
+use serde::{Deserialize, Serialize};
+
+/// Rating every entrant starts with, in both systems
+pub const INITIAL_RATING: f64 = 1500.;
+/// Rating deviation of an entrant that did not play any game yet
+pub const INITIAL_DEVIATION: f64 = 350.;
+
+/// Outcome of a game for one of its sides: 1 for a win, 0.5 for a draw, 0 for a loss
+///
+pub type Outcome = f64;
+
+/// Elo rating system with a fixed `k` factor
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct Elo {
+    pub k: f64,
+}
+
+impl Default for Elo {
+    fn default() -> Self {
+        Self { k: 32. }
+    }
+}
+
+impl Elo {
+    /// Return the expected outcome of a game between players rated `rating` and `other`.
+    ///
+    pub fn expected(rating: f64, other: f64) -> Outcome {
+        1. / (1. + 10f64.powf((other - rating) / 400.))
+    }
+
+    /// Return both ratings updated by the `outcome` of the game between them,
+    /// as seen by the first player.
+    ///
+    pub fn update(&self, ratings: (f64, f64), outcome: Outcome) -> (f64, f64) {
+        let change = self.k * (outcome - Self::expected(ratings.0, ratings.1));
+        (ratings.0 + change, ratings.1 - change)
+    }
+}
+
+/// Glicko rating together with its deviation
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Glicko {
+    pub rating: f64,
+    pub deviation: f64,
+}
+
+impl Default for Glicko {
+    fn default() -> Self {
+        Self {
+            rating: INITIAL_RATING,
+            deviation: INITIAL_DEVIATION,
+        }
+    }
+}
+
+impl Glicko {
+    const Q: f64 = LN_10 / 400.;
+
+    fn g(deviation: f64) -> f64 {
+        1. / (1. + 3. * Self::Q.powi(2) * deviation.powi(2) / PI.powi(2)).sqrt()
+    }
+
+    /// Return the expected outcome of a game against the `other` player.
+    ///
+    pub fn expected(&self, other: &Glicko) -> Outcome {
+        1. / (1. + 10f64.powf(-Self::g(other.deviation) * (self.rating - other.rating) / 400.))
+    }
+
+    /// Return the rating updated by all games of a single rating period,
+    /// given as the opponents' ratings from before the period and the outcomes.
+    /// Rating of a player who did not play stays as it is.
+    ///
+    pub fn update(&self, games: &[(Glicko, Outcome)]) -> Glicko {
+        if games.is_empty() {
+            return *self;
+        }
+
+        let (mut variance_inv, mut improvement) = (0., 0.);
+        for (other, outcome) in games {
+            let g = Self::g(other.deviation);
+            let expected = self.expected(other);
+            variance_inv += g.powi(2) * expected * (1. - expected);
+            improvement += g * (outcome - expected);
+        }
+        let d_squared_inv = Self::Q.powi(2) * variance_inv;
+        let precision = 1. / self.deviation.powi(2) + d_squared_inv;
+
+        Glicko {
+            rating: self.rating + Self::Q / precision * improvement,
+            deviation: (1. / precision).sqrt(),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Module with Rect struct
+use serde::{Deserialize, Serialize};
+
+use crate::coords::Coords;
+
+/// Axis-aligned rectangle, described by its top left corner and size
+///
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    /// Construct a Rect struct with given top left corner and size
+    ///
+    pub fn new(x: f32, y: f32, w: f32, h: f32) -> Self {
+        Self { x, y, w, h }
+    }
+
+    pub fn left(&self) -> f32 {
+        self.x
+    }
+
+    pub fn right(&self) -> f32 {
+        self.x + self.w
+    }
+
+    pub fn top(&self) -> f32 {
+        self.y
+    }
+
+    pub fn bottom(&self) -> f32 {
+        self.y + self.h
+    }
+
+    /// Return the same rectangle moved by `offset`
+    ///
+    pub fn translated(&self, offset: Coords) -> Self {
+        Self::new(self.x + offset.x, self.y + offset.y, self.w, self.h)
+    }
+
+    /// Check if the rectangles have any common area
+    ///
+    pub fn overlaps(&self, other: &Rect) -> bool {
+        self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    /// Check if `other` lies entirely inside of this rectangle
+    ///
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.left() <= other.left()
+            && other.right() <= self.right()
+            && self.top() <= other.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// Return the middle point of the rectangle
+    ///
+    pub fn center(&self) -> Coords {
+        Coords::new(self.x + self.w / 2., self.y + self.h / 2.)
+    }
+}
@@ -0,0 +1,129 @@
+//! Software renderer drawing the arena into an RGB pixel buffer
+//!
+//! Used where there is no ggez window to draw into, like in the Python
+//! bindings. Every shape paints the pixels whose centres it covers, turns
+//! as the quarter rings they are, and in the wrap-around arena each shape
+//! is also drawn on the opposite edges it sticks out of.
+use std::f32::consts::FRAC_PI_2;
+
+use crate::{
+    consts::SNAKE_COLORS,
+    coords::Coords,
+    rect::Rect,
+    segment::{Collidable, SegmentKind},
+    simulation::Simulation,
+    snake,
+    turn::Turn,
+};
+
+const BACKGROUND: [u8; 3] = [0, 0, 0];
+const OBSTACLE: [u8; 3] = [128, 128, 128];
+const FOOD: [u8; 3] = [255, 0, 0];
+
+/// RGB image stored row by row
+///
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            data: BACKGROUND.repeat(width * height),
+        }
+    }
+
+    /// Return the color of the pixel in the column `x` and the row `y`.
+    ///
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+        let idx = (y * self.width + x) * 3;
+        [self.data[idx], self.data[idx + 1], self.data[idx + 2]]
+    }
+
+    /// Paints the pixels inside of `rect` which centres pass the `inside` test.
+    ///
+    fn paint<F>(&mut self, rect: &Rect, scale: f32, color: [u8; 3], inside: F)
+    where
+        F: Fn(Coords) -> bool,
+    {
+        let span = |beg: f32, end: f32, len: usize| {
+            let clamp = |pos: f32| (pos * scale).round().clamp(0., len as f32) as usize;
+            clamp(beg)..clamp(end)
+        };
+
+        for y in span(rect.top(), rect.bottom(), self.height) {
+            for x in span(rect.left(), rect.right(), self.width) {
+                let center = Coords::new((x as f32 + 0.5) / scale, (y as f32 + 0.5) / scale);
+                if inside(center) {
+                    let idx = (y * self.width + x) * 3;
+                    self.data[idx..idx + 3].copy_from_slice(&color);
+                }
+            }
+        }
+    }
+}
+
+/// Check if `pos` lies on the part of the quarter ring the turn already covers.
+///
+pub fn turn_contains(turn: &Turn, pos: Coords) -> bool {
+    let dot = |a: Coords, b: Coords| a.x * b.x + a.y * b.y;
+    let pivot = turn.pos + turn.out_dir.as_coords() * (turn.width / 2.);
+    let rel = pos - pivot;
+    let (along_in, along_out) = (
+        dot(rel, turn.out_dir.as_coords() * -1.),
+        dot(rel, turn.in_dir.as_coords()),
+    );
+    if along_in < 0. || along_out < 0. || along_in.hypot(along_out) > turn.width {
+        return false;
+    }
+
+    let swept = along_out.atan2(along_in) / FRAC_PI_2;
+    if turn.is_growing {
+        swept <= turn.percentage
+    } else {
+        swept >= 1. - turn.percentage
+    }
+}
+
+/// Draws the obstacles, the food and the snakes still alive,
+/// with `scale` pixels for every pixel of the arena.
+///
+pub fn render(sim: &Simulation, scale: f32) -> Image {
+    let config = &sim.config;
+    let mut image = Image::new(
+        (config.screen_width * scale).round() as usize,
+        (config.screen_height * scale).round() as usize,
+    );
+
+    for offset in snake::wrap_offsets(config.wrap_size()) {
+        for obstacle in &config.obstacles {
+            let rect = obstacle.bounding_box().translated(offset);
+            image.paint(&rect, scale, OBSTACLE, |_| true);
+        }
+        if let Some(food) = &sim.food {
+            image.paint(&food.bbox.translated(offset), scale, FOOD, |_| true);
+        }
+
+        for (idx, player) in sim.players.iter().enumerate() {
+            if !player.alive {
+                continue;
+            }
+            let (r, g, b) = SNAKE_COLORS[idx % SNAKE_COLORS.len()];
+            let color = [r, g, b];
+            for segment in player.snake.body.iter().rev() {
+                let rect = segment.bounding_box().translated(offset);
+                match segment.kind() {
+                    SegmentKind::Line(_) => image.paint(&rect, scale, color, |_| true),
+                    SegmentKind::Turn(turn) => {
+                        image.paint(&rect, scale, color, |pos| turn_contains(turn, pos - offset))
+                    }
+                }
+            }
+        }
+    }
+    image
+}
@@ -0,0 +1,282 @@
+//! Module for recording games and playing them back
+//!
+//! Replay file is a plain text, line based format:
+//!
+//! ```text
+//! snaek_replay 1
+//! seed 1234
+//! config snake_width = 20.0
+//! config ticks_per_sec = 120
+//! input 15 0 Left
+//! input 42 1 Up
+//! out 300 1
+//! end 512 3
+//! ```
+//!
+//! Header holds the format version, followed by everything needed to
+//! recreate the `Simulation`: the seed and the `GameConfig`, one TOML
+//! line per `config` entry. Each `input` line holds the tick the direction
+//! was fed into, the player it belongs to and the direction itself. Each `out`
+//! line holds the tick on which the snake of the given player was disqualified,
+//! e.g. when its external bot stopped answering. The optional `end` line holds
+//! the number of ticks played and the score of the first player on which the
+//! recorded game was over.
+use std::{
+    fmt,
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig,
+    controller::{self, Scripted},
+    direction::Direction,
+    simulation::{Simulation, StepEvent},
+};
+
+pub const REPLAY_MAGIC: &str = "snaek_replay";
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Error that can occur while loading a replay
+///
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    UnsupportedVersion(u32),
+    Malformed { line: usize, reason: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "Could not read replay: {}", err),
+            ReplayError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported replay version {}, expected {}",
+                version, REPLAY_VERSION
+            ),
+            ReplayError::Malformed { line, reason } => {
+                write!(f, "Malformed replay at line {}: {}", line, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+/// Final outcome of the recorded game: number of ticks played and the score of the first player
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ReplayEnd {
+    pub tick: u64,
+    pub score: u32,
+}
+
+/// Everything needed to reproduce a game: its seed, settings
+/// and all directions fed into the simulation, indexed by tick and player,
+/// together with the players disqualified on the way.
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    #[serde(with = "crate::save::config")]
+    pub config: GameConfig,
+    pub inputs: Vec<(u64, usize, Direction)>,
+    pub disqualified: Vec<(u64, usize)>,
+    pub end: Option<ReplayEnd>,
+}
+
+impl Replay {
+    /// Create an empty `Replay` for the game that is about to be played by `sim`.
+    ///
+    pub fn new(sim: &Simulation) -> Self {
+        Self {
+            seed: sim.seed,
+            config: sim.config.clone(),
+            inputs: Vec::new(),
+            disqualified: Vec::new(),
+            end: None,
+        }
+    }
+
+    /// Create a fresh `Simulation` in the state the recorded game started in.
+    ///
+    pub fn simulation(&self) -> Simulation {
+        Simulation::new(self.config.clone(), self.seed)
+    }
+
+    /// Store `inputs` fed into the given tick.
+    ///
+    pub fn record(&mut self, tick: u64, inputs: &[(usize, Direction)]) {
+        self.inputs
+            .extend(inputs.iter().map(|&(player, dir)| (tick, player, dir)));
+    }
+
+    /// Store that the snake of `player` was disqualified on the given tick.
+    ///
+    pub fn disqualify(&mut self, tick: u64, player: usize) {
+        self.disqualified.push((tick, player));
+    }
+
+    /// Mark the recorded game as over after `tick` ticks with the given score.
+    ///
+    pub fn finish(&mut self, tick: u64, score: u32) {
+        self.end = Some(ReplayEnd { tick, score });
+    }
+
+    /// Play the whole replay without any window and return the resulting simulation.
+    ///
+    /// Stops once the game is over or, for unfinished replays, after the last recorded event.
+    ///
+    pub fn play_headless(&self) -> Simulation {
+        let mut sim = self.simulation();
+        let mut controllers = Scripted::all_from_replay(self);
+        let ticks = match self.end {
+            Some(end) => end.tick,
+            None => {
+                let last_input = self.inputs.last().map_or(0, |&(tick, _, _)| tick + 1);
+                let last_out = self.disqualified.last().map_or(0, |&(tick, _)| tick + 1);
+                last_input.max(last_out)
+            }
+        };
+
+        while sim.tick < ticks {
+            let inputs = controller::collect_inputs(&mut controllers, &sim);
+            controller::disqualify(&controllers, &mut sim);
+            if sim.tick(&inputs) == StepEvent::Died {
+                break;
+            }
+        }
+        sim
+    }
+
+    /// Save replay to the file under `path`.
+    ///
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()
+    }
+
+    /// Load replay from the file under `path`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ReplayError> {
+        Self::read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Write replay in the text format described in the module docs.
+    ///
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(writer, "{} {}", REPLAY_MAGIC, REPLAY_VERSION)?;
+        writeln!(writer, "seed {}", self.seed)?;
+        for line in self
+            .config
+            .to_toml()
+            .lines()
+            .filter(|line| !line.is_empty())
+        {
+            writeln!(writer, "config {}", line)?;
+        }
+        for (tick, player, dir) in &self.inputs {
+            writeln!(writer, "input {} {} {}", tick, player, dir)?;
+        }
+        for (tick, player) in &self.disqualified {
+            writeln!(writer, "out {} {}", tick, player)?;
+        }
+        if let Some(end) = self.end {
+            writeln!(writer, "end {} {}", end.tick, end.score)?;
+        }
+        Ok(())
+    }
+
+    /// Read replay in the text format described in the module docs.
+    ///
+    pub fn read_from<R: BufRead>(reader: R) -> Result<Self, ReplayError> {
+        let mut replay = Self {
+            seed: 0,
+            config: GameConfig::default(),
+            inputs: Vec::new(),
+            disqualified: Vec::new(),
+            end: None,
+        };
+        let mut config = String::new();
+        let (mut has_header, mut has_seed) = (false, false);
+
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line_no = idx + 1;
+            let malformed = |reason: &str| ReplayError::Malformed {
+                line: line_no,
+                reason: reason.to_string(),
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            match (has_header, fields.as_slice()) {
+                (_, []) => {}
+                (false, [REPLAY_MAGIC, version]) => {
+                    let version = version
+                        .parse()
+                        .map_err(|_| malformed("version is not a number"))?;
+                    if version != REPLAY_VERSION {
+                        return Err(ReplayError::UnsupportedVersion(version));
+                    }
+                    has_header = true;
+                }
+                (false, _) => return Err(malformed("missing replay header")),
+                (true, ["seed", seed]) => {
+                    replay.seed = seed.parse().map_err(|_| malformed("invalid seed"))?;
+                    has_seed = true;
+                }
+                (true, ["config", ..]) => {
+                    config.push_str(line.trim_start()["config".len()..].trim());
+                    config.push('\n');
+                }
+                (true, ["input", tick, player, dir]) => {
+                    let tick = tick.parse().map_err(|_| malformed("invalid tick"))?;
+                    let player = player.parse().map_err(|_| malformed("invalid player"))?;
+                    let dir = dir.parse().map_err(|err: String| malformed(&err))?;
+                    replay.inputs.push((tick, player, dir));
+                }
+                (true, ["out", tick, player]) => {
+                    let tick = tick.parse().map_err(|_| malformed("invalid tick"))?;
+                    let player = player.parse().map_err(|_| malformed("invalid player"))?;
+                    replay.disqualified.push((tick, player));
+                }
+                (true, ["end", tick, score]) => {
+                    replay.end = Some(ReplayEnd {
+                        tick: tick.parse().map_err(|_| malformed("invalid tick"))?,
+                        score: score.parse().map_err(|_| malformed("invalid score"))?,
+                    });
+                }
+                (true, _) => return Err(malformed(&format!("unexpected entry '{}'", line))),
+            }
+        }
+
+        if !has_header {
+            return Err(ReplayError::Malformed {
+                line: 1,
+                reason: "missing replay header".to_string(),
+            });
+        }
+        if !has_seed {
+            return Err(ReplayError::Malformed {
+                line: 1,
+                reason: "missing seed".to_string(),
+            });
+        }
+        replay.config = GameConfig::from_toml(&config).map_err(|err| ReplayError::Malformed {
+            line: 1,
+            reason: err.to_string(),
+        })?;
+        Ok(replay)
+    }
+}
@@ -0,0 +1,22 @@
+//! Module with the random number generator owned by the game
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+/// Random number generator used by all random systems of the game.
+///
+/// ChaCha gives the same sequence for the same seed on every platform,
+/// which keeps seeded games reproducible.
+///
+pub type GameRng = ChaCha8Rng;
+
+/// Create new `GameRng` from the given seed.
+///
+pub fn from_seed(seed: u64) -> GameRng {
+    GameRng::seed_from_u64(seed)
+}
+
+/// Draw a fresh seed from the OS-backed thread RNG.
+///
+pub fn random_seed() -> u64 {
+    rand::random()
+}
@@ -0,0 +1,233 @@
+//! Module with the online multiplayer hiding the latency with rollback
+//!
+//! The client talks to the same `LockstepServer` as the lockstep one, but
+//! it does not wait for the frames. Ticks are run right away, predicting
+//! that the snakes whose inputs did not arrive yet keep going straight,
+//! with a `Snapshot` of the simulation taken before each of them. Once
+//! a confirmed frame arrives that differs from the predicted one, the
+//! simulation is rolled back to the snapshot before it and the ticks since
+//! then are run again, this time with the inputs known to be right.
+//!
+//! Only confirmed ticks are hashed and checked by the server, so lockstep
+//! and rollback clients can play the same game.
+use std::{collections::VecDeque, net::ToSocketAddrs, time::Duration};
+
+use crate::{
+    direction::Direction,
+    lockstep::{Connection, RESEND_INTERVAL},
+    net::{self, Frame, NetError},
+    simulation::{Simulation, Snapshot, StepEvent},
+};
+
+/// Ticks the client may run ahead of the last confirmed one, unless configured otherwise
+pub const DEFAULT_MAX_PREDICTION: u64 = 30;
+
+/// Tick run on a predicted frame, not confirmed by the server yet
+///
+struct Prediction {
+    frame: Frame,
+    /// State right before the tick
+    before: Snapshot,
+    /// State hash right after the tick
+    hash: u64,
+}
+
+/// Client playing a single snake in a game run by a `LockstepServer`,
+/// predicting the inputs of the others instead of waiting for them
+///
+/// ```no_run
+/// # use std::{thread, time::Duration};
+/// # use snaek_core::{lockstep::DISCONNECT_TIMEOUT, rollback::RollbackClient, Direction, StepEvent};
+/// let (mut client, mut sim) = RollbackClient::connect("127.0.0.1:7171", DISCONNECT_TIMEOUT)?;
+/// client.push_input(Direction::Left);
+/// while client.step(&mut sim)? != Some(StepEvent::Died) {
+///     thread::sleep(Duration::from_secs_f32(sim.config.tick_duration()));
+/// }
+/// # Ok::<(), snaek_core::net::NetError>(())
+/// ```
+///
+pub struct RollbackClient {
+    conn: Connection,
+    max_prediction: u64,
+    /// Ticks run on predicted frames, from the first one not confirmed yet
+    predicted: VecDeque<Prediction>,
+    rollbacks: u64,
+    reported_over: bool,
+}
+
+impl RollbackClient {
+    /// Joins the game of the server at `addr`, waiting up to `timeout` for all
+    /// players to join. Return the client with the simulation of the game.
+    ///
+    pub fn connect<A: ToSocketAddrs>(
+        addr: A,
+        timeout: Duration,
+    ) -> Result<(Self, Simulation), NetError> {
+        let (conn, sim) = Connection::open(addr, timeout)?;
+        Ok((Self::new(conn), sim))
+    }
+
+    pub(crate) fn new(conn: Connection) -> Self {
+        Self {
+            conn,
+            max_prediction: DEFAULT_MAX_PREDICTION,
+            predicted: VecDeque::new(),
+            rollbacks: 0,
+            reported_over: false,
+        }
+    }
+
+    /// Return the client giving up on the server after `timeout` of silence.
+    ///
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.conn.timeout = timeout;
+        self
+    }
+
+    /// Return the client running at most `ticks` ahead of the last confirmed tick.
+    /// With no ticks at all it waits for every frame, the same as in lockstep.
+    ///
+    pub fn with_max_prediction(mut self, ticks: u64) -> Self {
+        self.max_prediction = ticks;
+        self
+    }
+
+    /// Return the index of the player steered by this client.
+    ///
+    pub fn player(&self) -> usize {
+        self.conn.player
+    }
+
+    /// Return the number of ticks the inputs are delayed by.
+    ///
+    pub fn input_delay(&self) -> u64 {
+        self.conn.input_delay
+    }
+
+    /// Return the number of ticks confirmed by the server and run on their real inputs.
+    ///
+    pub fn confirmed(&self, sim: &Simulation) -> u64 {
+        sim.tick - self.predicted.len() as u64
+    }
+
+    /// Return how many times the simulation was rolled back so far.
+    ///
+    pub fn rollbacks(&self) -> u64 {
+        self.rollbacks
+    }
+
+    /// Queues a new direction for the snake. Each tick takes at most one,
+    /// to be applied `input_delay` ticks later.
+    ///
+    pub fn push_input(&mut self, dir: Direction) {
+        self.conn.push_input(dir);
+    }
+
+    /// Corrects `sim` with the frames that arrived and runs its next tick,
+    /// unless it is too far ahead of the server already. Never waits.
+    ///
+    /// Return what happened in the tick, if it was run. The game is only over
+    /// once the server confirms it: predicted deaths give `None` and the death
+    /// is reported once the last tick of the game is confirmed.
+    ///
+    pub fn step(&mut self, sim: &mut Simulation) -> Result<Option<StepEvent>, NetError> {
+        self.conn.socket.set_nonblocking(true)?;
+        self.conn.receive()?;
+        let confirmed = self.conn.hashes.len();
+        self.reconcile(sim);
+
+        let event = if sim.is_over() {
+            match self.predicted.is_empty() && !self.reported_over {
+                true => Some(StepEvent::Died),
+                false => None,
+            }
+        } else if (self.predicted.len() as u64) < self.max_prediction
+            || self.conn.frames.len() > sim.tick as usize
+        {
+            let event = self.run_tick(sim);
+            self.conn.schedule_input();
+            match event {
+                StepEvent::Died if !self.predicted.is_empty() => None,
+                event => Some(event),
+            }
+        } else {
+            None
+        };
+        self.reported_over |= event == Some(StepEvent::Died);
+
+        if event.is_some()
+            || self.conn.hashes.len() > confirmed
+            || self.conn.last_sent.elapsed() >= RESEND_INTERVAL
+        {
+            self.conn.send()?;
+        }
+        Ok(event)
+    }
+
+    /// Return the frame of the given tick, as far as it is known: the own input
+    /// is, while the other snakes are expected to keep going straight.
+    ///
+    fn predict(&self, sim: &Simulation) -> Frame {
+        let mut inputs = vec![None; sim.players.len()];
+        inputs[self.conn.player] = self.conn.input(sim.tick).flatten();
+        Frame {
+            inputs,
+            dropped: Vec::new(),
+        }
+    }
+
+    /// Runs the next tick of `sim`, on its confirmed frame if every tick before
+    /// it is confirmed and the frame already arrived, on a predicted one otherwise.
+    ///
+    fn run_tick(&mut self, sim: &mut Simulation) -> StepEvent {
+        let tick = sim.tick;
+        match self.conn.frames.get(tick as usize) {
+            Some(frame) if self.predicted.is_empty() => {
+                let event = frame.apply(sim);
+                self.conn.hashes.push((tick, net::state_hash(sim)));
+                event
+            }
+            _ => {
+                let frame = self.predict(sim);
+                let before = sim.snapshot();
+                let event = frame.apply(sim);
+                self.predicted.push_back(Prediction {
+                    frame,
+                    before,
+                    hash: net::state_hash(sim),
+                });
+                event
+            }
+        }
+    }
+
+    /// Confirms the predicted ticks the server agrees with. On the first one
+    /// it does not, rolls `sim` back to right before it and runs every tick
+    /// since then again.
+    ///
+    fn reconcile(&mut self, sim: &mut Simulation) {
+        while let Some(prediction) = self.predicted.front() {
+            let tick = self.confirmed(sim);
+            match self.conn.frames.get(tick as usize) {
+                Some(frame) if *frame == prediction.frame => {
+                    self.conn.hashes.push((tick, prediction.hash));
+                    self.predicted.pop_front();
+                }
+                Some(_) => break,
+                None => return,
+            }
+        }
+        let mispredicted = match self.predicted.pop_front() {
+            Some(prediction) => prediction,
+            None => return,
+        };
+
+        let target = sim.tick;
+        sim.restore(&mispredicted.before);
+        self.predicted.clear();
+        self.rollbacks += 1;
+        while sim.tick < target && !sim.is_over() {
+            self.run_tick(sim);
+        }
+    }
+}
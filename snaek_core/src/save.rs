@@ -0,0 +1,434 @@
+//! Module with the versioned serialization of the complete simulation state
+//!
+//! Saved state holds everything needed to go on with the game exactly where
+//! it stopped: the config and the seed, the tick, the players with their
+//! snakes, inputs and scores, the food and the position of the RNG in its
+//! stream. It comes in two formats:
+//!
+//! - JSON for debugging, an object with the `version` next to the state
+//! - compact binary for the network and save files, `SAVE_MAGIC` followed by
+//!   the postcard encoding of the state, which starts with the version
+//!
+//! States of any other `SAVE_VERSION` are refused rather than misread.
+//!
+//! `SavedGame` adds what the frontend needs to resume a game in progress:
+//! the level or the campaign being played along with the progress through it,
+//! the players of the match and the replays being recorded or played back.
+//! It is saved in the binary format only, `SAVED_GAME_MAGIC` followed by the
+//! postcard encoding, which starts with the saved state of the simulation.
+use std::{
+    convert::{TryFrom, TryInto},
+    fmt,
+};
+
+use rand::SeedableRng;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    ai::Difficulty,
+    campaign::CampaignProgress,
+    config::{GameConfig, Spawn},
+    coords::Coords,
+    food::Food,
+    level::Level,
+    lobby::Member,
+    mode::GameMode,
+    obstacle::Obstacle,
+    player::Player,
+    rect::Rect,
+    replay::Replay,
+    rng::GameRng,
+    simulation::Simulation,
+    snake::Snake,
+};
+
+pub const SAVE_MAGIC: &[u8] = b"snaek_save";
+pub const SAVED_GAME_MAGIC: &[u8] = b"snaek_game";
+pub const SAVE_VERSION: u32 = 1;
+
+/// Error that can occur while loading a saved state
+///
+#[derive(Debug)]
+pub enum SaveError {
+    Json(serde_json::Error),
+    Binary(postcard::Error),
+    NotASave,
+    UnsupportedVersion(u32),
+    /// State does not agree with its own config
+    Mismatch(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Json(err) => write!(f, "Malformed saved state: {}", err),
+            SaveError::Binary(err) => write!(f, "Malformed saved state: {}", err),
+            SaveError::NotASave => write!(f, "Not a saved state"),
+            SaveError::UnsupportedVersion(version) => write!(
+                f,
+                "Unsupported saved state version {}, expected {}",
+                version, SAVE_VERSION
+            ),
+            SaveError::Mismatch(reason) => write!(f, "Inconsistent saved state: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveError::Json(err)
+    }
+}
+
+impl From<postcard::Error> for SaveError {
+    fn from(err: postcard::Error) -> Self {
+        SaveError::Binary(err)
+    }
+}
+
+/// Version of the saved state, read on its own before the rest
+///
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Position of the RNG in its stream, independent of how the RNG
+/// keeps it internally
+///
+#[derive(Serialize, Deserialize)]
+struct SavedRng {
+    seed: [u8; 32],
+    stream: u64,
+    word_pos: u128,
+}
+
+impl From<&GameRng> for SavedRng {
+    fn from(rng: &GameRng) -> Self {
+        Self {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos(),
+        }
+    }
+}
+
+impl From<SavedRng> for GameRng {
+    fn from(saved: SavedRng) -> Self {
+        let mut rng = GameRng::from_seed(saved.seed);
+        rng.set_stream(saved.stream);
+        rng.set_word_pos(saved.word_pos);
+        rng
+    }
+}
+
+/// Complete state of the `Simulation`, the way it is serialized
+///
+#[derive(Serialize, Deserialize)]
+struct SavedSimulation {
+    version: u32,
+    #[serde(with = "config")]
+    config: GameConfig,
+    seed: u64,
+    tick: u64,
+    rng: SavedRng,
+    food: Option<Food>,
+    players: Vec<Player>,
+}
+
+/// Obstacle, the way it is serialized in the binary format
+///
+#[derive(Serialize, Deserialize)]
+enum SavedObstacle {
+    Rect(Rect),
+}
+
+/// Config, the way it is serialized in the binary format, which can read
+/// neither the obstacles tagged with their shape nor the fields skipped
+/// when they are empty
+///
+#[derive(Serialize, Deserialize)]
+struct SavedConfig {
+    mode: GameMode,
+    screen_width: f32,
+    screen_height: f32,
+    snake_width: f32,
+    snake_start_len: u8,
+    speed: f32,
+    wall_margin: f32,
+    food_size: f32,
+    turn_margin: f32,
+    ticks_per_sec: u32,
+    players: u8,
+    bots: u8,
+    bot_difficulty: Difficulty,
+    spawn: Option<Spawn>,
+    obstacles: Vec<SavedObstacle>,
+    food_spawns: Vec<Coords>,
+}
+
+impl From<&GameConfig> for SavedConfig {
+    fn from(config: &GameConfig) -> Self {
+        let GameConfig {
+            mode,
+            screen_width,
+            screen_height,
+            snake_width,
+            snake_start_len,
+            speed,
+            wall_margin,
+            food_size,
+            turn_margin,
+            ticks_per_sec,
+            players,
+            bots,
+            bot_difficulty,
+            spawn,
+            ref obstacles,
+            ref food_spawns,
+        } = *config;
+        Self {
+            mode,
+            screen_width,
+            screen_height,
+            snake_width,
+            snake_start_len,
+            speed,
+            wall_margin,
+            food_size,
+            turn_margin,
+            ticks_per_sec,
+            players,
+            bots,
+            bot_difficulty,
+            spawn,
+            obstacles: obstacles
+                .iter()
+                .map(|obstacle| match *obstacle {
+                    Obstacle::Rect(rect) => SavedObstacle::Rect(rect),
+                })
+                .collect(),
+            food_spawns: food_spawns.clone(),
+        }
+    }
+}
+
+impl From<SavedConfig> for GameConfig {
+    fn from(saved: SavedConfig) -> Self {
+        Self {
+            mode: saved.mode,
+            screen_width: saved.screen_width,
+            screen_height: saved.screen_height,
+            snake_width: saved.snake_width,
+            snake_start_len: saved.snake_start_len,
+            speed: saved.speed,
+            wall_margin: saved.wall_margin,
+            food_size: saved.food_size,
+            turn_margin: saved.turn_margin,
+            ticks_per_sec: saved.ticks_per_sec,
+            players: saved.players,
+            bots: saved.bots,
+            bot_difficulty: saved.bot_difficulty,
+            spawn: saved.spawn,
+            obstacles: saved
+                .obstacles
+                .into_iter()
+                .map(|obstacle| match obstacle {
+                    SavedObstacle::Rect(rect) => Obstacle::Rect(rect),
+                })
+                .collect(),
+            food_spawns: saved.food_spawns,
+        }
+    }
+}
+
+/// Serialization of the config, as it is for the readable formats
+/// and as `SavedConfig` for the binary ones.
+///
+pub(crate) mod config {
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::SavedConfig;
+    use crate::config::GameConfig;
+
+    pub fn serialize<S: Serializer>(config: &GameConfig, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            config.serialize(serializer)
+        } else {
+            SavedConfig::from(config).serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<GameConfig, D::Error> {
+        let config = if deserializer.is_human_readable() {
+            GameConfig::deserialize(deserializer)?
+        } else {
+            SavedConfig::deserialize(deserializer)?.into()
+        };
+        config.validate().map_err(D::Error::custom)?;
+        Ok(config)
+    }
+}
+
+impl Serialize for Simulation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedSimulation {
+            version: SAVE_VERSION,
+            config: self.config.clone(),
+            seed: self.seed,
+            tick: self.tick,
+            rng: SavedRng::from(&self.rng),
+            food: self.food,
+            players: self.players.clone(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Simulation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedSimulation::deserialize(deserializer)?;
+        Self::try_from(saved).map_err(D::Error::custom)
+    }
+}
+
+impl TryFrom<SavedSimulation> for Simulation {
+    type Error = SaveError;
+
+    fn try_from(saved: SavedSimulation) -> Result<Self, SaveError> {
+        check_version(saved.version)?;
+        check_players(&saved.players, &saved.config)?;
+        Ok(Self {
+            players: saved.players,
+            food: saved.food,
+            tick: saved.tick,
+            config: saved.config,
+            seed: saved.seed,
+            rng: saved.rng.into(),
+        })
+    }
+}
+
+impl Simulation {
+    /// Serialize the complete state into pretty printed JSON.
+    ///
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Simulation is always representable in JSON")
+    }
+
+    /// Restore the simulation from the state serialized with `to_json`.
+    ///
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        let Version { version } = serde_json::from_str(json)?;
+        check_version(version)?;
+        serde_json::from_str::<SavedSimulation>(json)?.try_into()
+    }
+
+    /// Serialize the complete state into the compact binary format.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = SAVE_MAGIC.to_vec();
+        let state =
+            postcard::to_allocvec(self).expect("Simulation is always representable in binary");
+        bytes.extend(state);
+        bytes
+    }
+
+    /// Restore the simulation from the state serialized with `to_bytes`.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        let state = bytes.strip_prefix(SAVE_MAGIC).ok_or(SaveError::NotASave)?;
+        let (version, _) = postcard::take_from_bytes::<u32>(state)?;
+        check_version(version)?;
+        postcard::from_bytes::<SavedSimulation>(state)?.try_into()
+    }
+}
+
+/// Game in progress in the frontend, see the module docs
+///
+#[derive(Serialize, Deserialize)]
+pub struct SavedGame {
+    pub sim: Simulation,
+    /// Seed every game is played with, if it is fixed
+    pub fixed_seed: Option<u64>,
+    /// Level given upfront, if any
+    pub level: Option<Level>,
+    /// Index of the campaign level being played
+    pub campaign_level: Option<usize>,
+    pub progress: CampaignProgress,
+    /// Config used outside of the campaign
+    #[serde(with = "config")]
+    pub base_config: GameConfig,
+    /// Names and colours the players of the online game picked in the lobby
+    pub roster: Vec<Member>,
+    /// Rounds won by each player in the current match
+    pub round_wins: Vec<u32>,
+    pub recording: Option<Replay>,
+    pub playback: Option<Replay>,
+}
+
+impl SavedGame {
+    /// Serialize the game into the binary format.
+    ///
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = SAVED_GAME_MAGIC.to_vec();
+        let game =
+            postcard::to_allocvec(self).expect("SavedGame is always representable in binary");
+        bytes.extend(game);
+        bytes
+    }
+
+    /// Restore the game serialized with `to_bytes`.
+    ///
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SaveError> {
+        let game = bytes
+            .strip_prefix(SAVED_GAME_MAGIC)
+            .ok_or(SaveError::NotASave)?;
+        let (version, _) = postcard::take_from_bytes::<u32>(game)?;
+        check_version(version)?;
+        let game: Self = postcard::from_bytes(game)?;
+        let players = game.sim.players.len();
+        if !game.round_wins.is_empty() && game.round_wins.len() != players {
+            return Err(SaveError::Mismatch(format!(
+                "round wins of {} players for {} snakes",
+                game.round_wins.len(),
+                players
+            )));
+        }
+        Ok(game)
+    }
+}
+
+fn check_version(version: u32) -> Result<(), SaveError> {
+    match version {
+        SAVE_VERSION => Ok(()),
+        version => Err(SaveError::UnsupportedVersion(version)),
+    }
+}
+
+/// Check that there is a player for every snake of the `config`
+/// and that their snakes are made for its arena.
+///
+fn check_players(players: &[Player], config: &GameConfig) -> Result<(), SaveError> {
+    if players.len() != config.snake_count() {
+        return Err(SaveError::Mismatch(format!(
+            "{} players for {} snakes",
+            players.len(),
+            config.snake_count()
+        )));
+    }
+    let wrap = config.mode.wraps().then(|| config.screen_size());
+    let fits = |snake: &Snake| snake.width == config.snake_width && snake.wrap == wrap;
+    match players
+        .iter()
+        .position(|player| !fits(&player.snake) || !fits(&player.prev_snake))
+    {
+        Some(player) => Err(SaveError::Mismatch(format!(
+            "snake of player {} does not fit the arena",
+            player + 1
+        ))),
+        None => Ok(()),
+    }
+}
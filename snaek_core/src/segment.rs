@@ -0,0 +1,89 @@
+use serde::Serialize;
+
+use crate::{consts, coords::Coords, direction::Direction, line::Line, rect::Rect, turn::Turn};
+
+/// Trait for growth functionality of the snake segments
+///
+pub trait Growable {
+    fn grow(&mut self, dist: f32) -> f32;
+    fn shrink(&mut self, dist: f32) -> f32;
+    fn end(&self) -> Coords;
+    fn direction(&self) -> Direction;
+    fn length(&self) -> f32;
+}
+
+/// Trait for collision functionality of the snake segments
+///
+pub trait Collidable {
+    fn bounding_box(&self) -> Rect;
+
+    fn collision(&self, other: &Rect) -> bool {
+        let rect = self.bounding_box();
+
+        if rect.left() >= other.right()
+            || rect.right() <= other.left()
+            || rect.top() >= other.bottom()
+            || rect.bottom() <= other.top()
+        {
+            return false;
+        }
+
+        let ll = f32::max(rect.left(), other.left());
+        let rr = f32::min(rect.right(), other.right());
+        let tt = f32::max(rect.top(), other.top());
+        let bb = f32::min(rect.bottom(), other.bottom());
+
+        (rr - ll) * (bb - tt) >= consts::COLLISION_PIXELS_MARGIN
+    }
+}
+
+/// Concrete type of the segment, used by frontends to pick a drawing routine
+/// and serialized as the segment geometry sent to external bots
+///
+#[derive(PartialEq, Clone, Copy, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SegmentKind<'a> {
+    Line(&'a Line),
+    Turn(&'a Turn),
+}
+
+pub trait Segment: Growable + Collidable {
+    fn kind(&self) -> SegmentKind<'_>;
+    fn box_clone(&self) -> Box<dyn Segment>;
+    fn translate(&mut self, offset: Coords);
+}
+
+impl Segment for Line {
+    fn kind(&self) -> SegmentKind<'_> {
+        SegmentKind::Line(self)
+    }
+
+    fn translate(&mut self, offset: Coords) {
+        self.beg += offset;
+        self.end += offset;
+    }
+
+    fn box_clone(&self) -> Box<dyn Segment> {
+        Box::new(*self)
+    }
+}
+
+impl Segment for Turn {
+    fn kind(&self) -> SegmentKind<'_> {
+        SegmentKind::Turn(self)
+    }
+
+    fn translate(&mut self, offset: Coords) {
+        self.pos += offset;
+    }
+
+    fn box_clone(&self) -> Box<dyn Segment> {
+        Box::new(*self)
+    }
+}
+
+impl Clone for Box<dyn Segment> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
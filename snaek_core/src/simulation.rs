@@ -0,0 +1,302 @@
+use crate::{
+    config::GameConfig,
+    consts,
+    coords::Coords,
+    direction::Direction,
+    food::Food,
+    player::Player,
+    rng::{self, GameRng},
+    segment::Collidable,
+    snake::Snake,
+};
+
+/// Outcome of a single simulation step
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum StepEvent {
+    Moved,
+    FoodEaten,
+    Died,
+}
+
+/// Copy of everything in the simulation that changes while it runs:
+/// the players with their snakes, inputs and scores, the food, the tick
+/// and the state of the RNG. The config and the seed never change.
+///
+#[derive(Clone)]
+pub struct Snapshot {
+    players: Vec<Player>,
+    food: Option<Food>,
+    tick: u64,
+    rng: GameRng,
+}
+
+/// Headless game simulation: the snakes of all players, the food and the score.
+///
+/// It does not know anything about windows or rendering, so it can be driven
+/// by the ggez frontend as well as by tests, bots or servers.
+///
+/// The simulation advances in fixed ticks and draws all randomness from
+/// its own seeded RNG, so the same seed and inputs always produce
+/// the same game, no matter the framerate or the machine.
+///
+/// With more than one player the game is a round, which ends
+/// when at most one snake is left alive.
+///
+/// There is no `food` while the snakes and the obstacles leave no room
+/// for it. Placing it is then tried again on every tick.
+///
+#[derive(Clone)]
+pub struct Simulation {
+    pub players: Vec<Player>,
+    pub food: Option<Food>,
+    pub tick: u64,
+    pub config: GameConfig,
+    pub seed: u64,
+    pub rng: GameRng,
+}
+
+impl Simulation {
+    /// Creates new `Simulation` instance with the given config and seed.
+    ///
+    /// Snakes are created on the configured spawns, a single one
+    /// by default on the middle of the screen.
+    ///
+    pub fn new(config: GameConfig, seed: u64) -> Self {
+        let mut rng = rng::from_seed(seed);
+        let players: Vec<_> = config
+            .snake_spawns()
+            .into_iter()
+            .map(|spawn| Player::new(Snake::new(&config, spawn)))
+            .collect();
+        let food = Self::spawn_food(&players, &config, &mut rng, 0);
+        Self {
+            players,
+            food,
+            tick: 0,
+            config,
+            seed,
+            rng,
+        }
+    }
+
+    /// Brings the simulation back to its initial state, using a new seed.
+    ///
+    pub fn reset(&mut self, seed: u64) {
+        *self = Self::new(self.config.clone(), seed);
+    }
+
+    /// Places food on the next of the fixed spawn points, given how much
+    /// was `eaten` already, or randomly, away from the snakes and the obstacles.
+    ///
+    /// After `consts::FOOD_SPAWN_ATTEMPTS` random places turned out blocked,
+    /// the arena is searched row by row for a free cell as big as the food.
+    /// Return `None` when there is none.
+    ///
+    fn spawn_food(
+        players: &[Player],
+        config: &GameConfig,
+        rng: &mut GameRng,
+        eaten: u32,
+    ) -> Option<Food> {
+        if let Some(&pos) = config.food_spawns.get(eaten as usize) {
+            return Some(Food::at(pos, config));
+        }
+
+        let blocked = |food: &Food| {
+            players
+                .iter()
+                .any(|player| player.alive && player.snake.collide(&food.bbox))
+                || config
+                    .obstacles
+                    .iter()
+                    .any(|obstacle| obstacle.collision(&food.bbox))
+        };
+        let food = (0..consts::FOOD_SPAWN_ATTEMPTS)
+            .map(|_| Food::random(config, rng))
+            .find(|food| !blocked(food));
+        if food.is_some() {
+            return food;
+        }
+
+        let size = config.food_size;
+        let cols = (config.screen_width / size) as usize;
+        let rows = (config.screen_height / size) as usize;
+        itertools::iproduct!(1..rows - 1, 1..cols - 1)
+            .map(|(row, col)| {
+                let cell = Coords::new(col as f32 + 0.5, row as f32 + 0.5);
+                Food::at(cell * size, config)
+            })
+            .find(|food| !blocked(food))
+    }
+
+    /// Return a copy of the current state, to be restored later on.
+    ///
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            players: self.players.clone(),
+            food: self.food,
+            tick: self.tick,
+            rng: self.rng.clone(),
+        }
+    }
+
+    /// Brings the simulation back to the state of the `snapshot`,
+    /// taken from this simulation or from one with the same config.
+    ///
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.players.clone_from(&snapshot.players);
+        self.food = snapshot.food;
+        self.tick = snapshot.tick;
+        self.rng.clone_from(&snapshot.rng);
+    }
+
+    /// Return time spent in the game so far, in seconds.
+    ///
+    pub fn elapsed_secs(&self) -> f32 {
+        self.tick as f32 * self.config.tick_duration()
+    }
+
+    /// Return food eaten by all of the players together.
+    ///
+    pub fn food_eaten(&self) -> u32 {
+        self.players.iter().map(|player| player.score).sum()
+    }
+
+    /// Check if the game is over: the only snake died
+    /// or, with more players, at most one of them is still alive.
+    ///
+    pub fn is_over(&self) -> bool {
+        let alive = self.players.iter().filter(|player| player.alive).count();
+        if self.players.len() == 1 {
+            alive == 0
+        } else {
+            alive <= 1
+        }
+    }
+
+    /// Return the index of the player that won the round, which is the last
+    /// one standing. There is none in a single player game, nor when the last
+    /// snakes died together.
+    ///
+    pub fn winner(&self) -> Option<usize> {
+        if self.players.len() == 1 || !self.is_over() {
+            return None;
+        }
+        self.players.iter().position(|player| player.alive)
+    }
+
+    /// Take the snake of the given player out of the game, e.g. when its
+    /// controller broke the rules. The game may be over once the next tick runs.
+    ///
+    pub fn disqualify(&mut self, player: usize) {
+        if let Some(player) = self.players.get_mut(player) {
+            player.alive = false;
+        }
+    }
+
+    /// Queues a new direction for the snake of the given player.
+    /// Repeated directions are ignored.
+    ///
+    pub fn push_input(&mut self, player: usize, dir: Direction) {
+        if let Some(player) = self.players.get_mut(player) {
+            player.push_input(dir);
+        }
+    }
+
+    /// Advances the simulation by a single fixed tick,
+    /// queueing given `inputs` of the players beforehand.
+    ///
+    pub fn tick(&mut self, inputs: &[(usize, Direction)]) -> StepEvent {
+        self.tick += 1;
+        self.step(self.config.tick_duration(), inputs)
+    }
+
+    /// Advances the simulation by `time_delta` seconds,
+    /// queueing given `inputs` of the players beforehand.
+    ///
+    pub fn step(&mut self, time_delta: f32, inputs: &[(usize, Direction)]) -> StepEvent {
+        for &(player, dir) in inputs {
+            self.push_input(player, dir);
+        }
+        self.update_input(time_delta);
+        self.update_snakes(time_delta)
+    }
+
+    /// Processes input of all players that are still alive.
+    ///
+    pub fn update_input(&mut self, time_delta: f32) {
+        for player in self.players.iter_mut().filter(|player| player.alive) {
+            player.update_input(time_delta, &self.config);
+        }
+    }
+
+    /// Updates snakes (collision, movement, growth)
+    /// Returns what happened, so the caller can react to it.
+    ///
+    /// All collisions are checked before anything moves, so the order of
+    /// players does not matter: head touching the body of another snake kills
+    /// its owner only, while two heads running into each other kill both snakes.
+    ///
+    pub fn update_snakes(&mut self, time_delta: f32) -> StepEvent {
+        for player in &mut self.players {
+            player.prev_snake.clone_from(&player.snake);
+            player.last_move = 0.;
+        }
+
+        let eater = self.food.and_then(|food| {
+            self.players
+                .iter()
+                .position(|player| player.alive && player.snake.collide(&food.bbox))
+        });
+        let dying: Vec<bool> = (0..self.players.len())
+            .map(|idx| Some(idx) != eater && self.players[idx].alive && self.is_deadly(idx))
+            .collect();
+
+        for (idx, player) in self.players.iter_mut().enumerate() {
+            if !player.alive {
+                continue;
+            }
+            if Some(idx) == eater {
+                player.snake.grow(self.config.food_size);
+                player.score += 1;
+            } else if dying[idx] {
+                player.alive = false;
+            } else {
+                player.last_move = time_delta * self.config.speed;
+                player.snake.do_move(player.last_move);
+            }
+        }
+        if eater.is_some() || self.food.is_none() {
+            let eaten = self.food_eaten();
+            self.food = Self::spawn_food(&self.players, &self.config, &mut self.rng, eaten);
+        }
+
+        if self.is_over() {
+            StepEvent::Died
+        } else if eater.is_some() {
+            StepEvent::FoodEaten
+        } else {
+            StepEvent::Moved
+        }
+    }
+
+    /// Check if the snake of the given player runs into anything deadly:
+    /// itself, a wall, an obstacle or another snake that is still alive.
+    ///
+    fn is_deadly(&self, idx: usize) -> bool {
+        let snake = &self.players[idx].snake;
+        snake.self_collide()
+            || snake.wall_collide(&self.config)
+            || snake.obstacle_collide(&self.config.obstacles)
+            || self.players.iter().enumerate().any(|(other, player)| {
+                other != idx && player.alive && snake.snake_collide(&player.snake)
+            })
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new(GameConfig::default(), rng::random_seed())
+    }
+}
@@ -0,0 +1,290 @@
+use crate::{
+    config::{GameConfig, Spawn},
+    coords::Coords,
+    direction::Direction,
+    line::Line,
+    obstacle::Obstacle,
+    rect::Rect,
+    turn::Turn,
+};
+use std::collections::VecDeque;
+
+use itertools as it;
+use serde::{Deserialize, Serialize};
+
+use crate::segment::{Collidable, Segment, SegmentKind};
+
+/// Snake structure that consists of a list of either
+/// straight or curved segments and the direction of the head.
+///
+/// In the wrap-around arena `wrap` holds its size. Head leaving the arena
+/// is then split at the edge and continues from the opposite one.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Snake {
+    #[serde(with = "body")]
+    pub body: VecDeque<Box<dyn Segment>>,
+    pub dir: Direction,
+    pub width: f32,
+    pub wrap: Option<Coords>,
+}
+
+impl Snake {
+    /// Create new `Snake` of the configured length and width
+    /// on the given spawn position and direction.
+    ///
+    pub fn new(config: &GameConfig, spawn: Spawn) -> Self {
+        let first = config.snake_start_line(spawn);
+        let dir = first.dir;
+        let boxed: Box<dyn Segment> = Box::new(first);
+        let mut body = VecDeque::new();
+        body.push_back(boxed);
+
+        Self {
+            body,
+            dir,
+            width: config.snake_width,
+            wrap: config.wrap_size(),
+        }
+    }
+
+    /// Move `Snake` in the current direction by a given distance.
+    ///
+    pub fn do_move(&mut self, dist: f32) {
+        self.grow(dist);
+        self.shrink(dist);
+    }
+
+    fn shrink(&mut self, dist: f32) {
+        let back = self.body.back_mut().unwrap();
+        let shrink_left = back.shrink(dist);
+        if shrink_left > 0. {
+            self.body.pop_back();
+            self.body.back_mut().unwrap().shrink(shrink_left);
+        }
+    }
+
+    /// Extend `Snake` towards the current direction by a given distance.
+    /// When `Snake` direction changes, add a new Turn at the begining.
+    /// When Turn at the begining is fully extended, add a new Line at the begining.
+    ///
+    pub fn grow(&mut self, dist: f32) {
+        let mut front = self.body.front_mut().unwrap();
+
+        if front.direction() != self.dir {
+            let pos = front.end();
+            let in_dir = front.direction();
+            self.body
+                .push_front(Box::new(Turn::new(pos, in_dir, self.dir, self.width)));
+            front = self.body.front_mut().unwrap();
+        }
+
+        let growth_left = front.grow(dist);
+        if growth_left > 0. {
+            let pos = front.end();
+            let dir = front.direction();
+            let pos = self.wrap_pos(pos);
+            self.body
+                .push_front(Box::new(Line::new(pos, dir, self.width)));
+            self.body.front_mut().unwrap().grow(growth_left);
+        }
+
+        self.split_head();
+    }
+
+    /// Wrap given position into the arena, if it wraps around.
+    ///
+    fn wrap_pos(&self, pos: Coords) -> Coords {
+        match self.wrap {
+            Some(size) => Coords::new(pos.x.rem_euclid(size.x), pos.y.rem_euclid(size.y)),
+            None => pos,
+        }
+    }
+
+    /// When straight head crossed the edge of the wrap-around arena,
+    /// cut it at the edge and put the rest of it on the opposite side.
+    ///
+    /// Curved head can't be cut, so it is moved to the opposite side whole
+    /// once its end is outside the arena. It then starts beyond the edge,
+    /// just like the cut straight head, while the head stays in the arena.
+    ///
+    fn split_head(&mut self) {
+        let size = match self.wrap {
+            Some(size) => size,
+            None => return,
+        };
+        let front = self.body.front_mut().unwrap();
+        if let SegmentKind::Turn(_) = front.kind() {
+            let end = front.end();
+            let offset = |pos: f32, size: f32| match pos {
+                pos if pos < 0. => size,
+                pos if pos > size => -size,
+                _ => 0.,
+            };
+            let offset = Coords::new(offset(end.x, size.x), offset(end.y, size.y));
+            if offset != Coords::new(0., 0.) {
+                front.translate(offset);
+            }
+            return;
+        }
+
+        let end = front.end();
+        let dir = front.direction();
+        let overflow = match dir {
+            Direction::Up => -end.y,
+            Direction::Down => end.y - size.y,
+            Direction::Left => -end.x,
+            Direction::Right => end.x - size.x,
+        };
+        if overflow <= 0. {
+            return;
+        }
+
+        front.grow(-overflow);
+        let edge = front.end();
+        let beg = edge - dir.as_coords() * size;
+        self.body
+            .push_front(Box::new(Line::new(beg, dir, self.width)));
+        self.body.front_mut().unwrap().grow(overflow);
+    }
+
+    /// Return offsets of the arena copies that have to be checked for collisions,
+    /// starting with the zero one. Only the wrap-around arena has more than one.
+    ///
+    pub fn wrap_offsets(&self) -> Vec<Coords> {
+        wrap_offsets(self.wrap)
+    }
+
+    /// Return total length of all segments.
+    ///
+    pub fn length(&self) -> f32 {
+        self.body.iter().map(|segment| segment.length()).sum()
+    }
+
+    /// Check if any of the segments collides with given `Rect`.
+    ///
+    pub fn collide(&self, other: &Rect) -> bool {
+        self.wrap_offsets().into_iter().any(|offset| {
+            let other = other.translated(offset);
+            self.body.iter().any(|segment| segment.collision(&other))
+        })
+    }
+
+    /// Check if head is colliding with screen boundaries.
+    /// There are none in the wrap-around arena.
+    ///
+    pub fn wall_collide(&self, config: &GameConfig) -> bool {
+        if self.wrap.is_some() {
+            return false;
+        }
+        let head = self.body.front().unwrap().bounding_box();
+        head.left() < -config.wall_margin
+            || head.top() < -config.wall_margin
+            || head.bottom() > config.screen_height + config.wall_margin
+            || head.right() > config.screen_width + config.wall_margin
+    }
+
+    /// Check if head is colliding with any of the obstacles.
+    ///
+    pub fn obstacle_collide(&self, obstacles: &[Obstacle]) -> bool {
+        let head = self.body.front().unwrap();
+        self.wrap_offsets().into_iter().any(|offset| {
+            obstacles
+                .iter()
+                .any(|obstacle| head.collision(&obstacle.bounding_box().translated(offset)))
+        })
+    }
+
+    /// Return the square of the snake's width right behind the tip of its head.
+    ///
+    pub fn head_rect(&self) -> Rect {
+        let head = self.body.front().unwrap();
+        let center = head.end() - head.direction().as_coords() * (self.width / 2.);
+        let half = self.width / 2.;
+        Rect::new(center.x - half, center.y - half, self.width, self.width)
+    }
+
+    /// Check if the tip of the head is colliding with any segment of the `other`
+    /// snake. Only the tip counts, as the first segment may be as long as the snake.
+    ///
+    pub fn snake_collide(&self, other: &Snake) -> bool {
+        let head = self.head_rect();
+        self.wrap_offsets().into_iter().any(|offset| {
+            let head = head.translated(offset);
+            other.body.iter().any(|segment| segment.collision(&head))
+        })
+    }
+
+    /// Check if head is colliding with any other segment.
+    ///
+    pub fn self_collide(&self) -> bool {
+        let head = self.body.front().unwrap();
+        let offsets = self.wrap_offsets();
+        self.body.iter().skip(1).map(|x| x.bounding_box()).any(|x| {
+            offsets
+                .iter()
+                .any(|&offset| head.collision(&x.translated(offset)))
+        })
+    }
+}
+
+/// Owned segment of the snake body, the way it is serialized
+///
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SavedSegment {
+    Line(Line),
+    Turn(Turn),
+}
+
+/// Serialization of the snake body as a list of `SavedSegment`s,
+/// as boxed segments can't be serialized on their own.
+///
+mod body {
+    use std::collections::VecDeque;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use super::SavedSegment;
+    use crate::segment::{Segment, SegmentKind};
+
+    pub fn serialize<S: Serializer>(
+        body: &VecDeque<Box<dyn Segment>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(body.iter().map(|segment| match segment.kind() {
+            SegmentKind::Line(line) => SavedSegment::Line(*line),
+            SegmentKind::Turn(turn) => SavedSegment::Turn(*turn),
+        }))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<VecDeque<Box<dyn Segment>>, D::Error> {
+        let segments = Vec::<SavedSegment>::deserialize(deserializer)?;
+        if segments.is_empty() {
+            return Err(D::Error::custom("snake has no segments"));
+        }
+        Ok(segments
+            .into_iter()
+            .map(|segment| -> Box<dyn Segment> {
+                match segment {
+                    SavedSegment::Line(line) => Box::new(line),
+                    SavedSegment::Turn(turn) => Box::new(turn),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Return offsets of the copies of the arena of the given `wrap` size, starting
+/// with the zero one. Arena that does not wrap around has no other copies.
+///
+pub fn wrap_offsets(wrap: Option<Coords>) -> Vec<Coords> {
+    match wrap {
+        Some(size) => it::iproduct!([0., -size.y, size.y], [0., -size.x, size.x])
+            .map(|(y, x)| Coords::new(x, y))
+            .collect(),
+        None => vec![Coords::new(0., 0.)],
+    }
+}
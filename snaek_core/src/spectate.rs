@@ -0,0 +1,591 @@
+//! Module with live streaming of games to spectators
+//!
+//! `Broadcaster` listens for spectators on a TCP socket and streams them
+//! the matches fed to it, be it online games, tournament games or any
+//! other. Both sides talk JSON lines, a single `SpectatorMessage` each.
+//!
+//! The simulation is deterministic, so a match is streamed as its config,
+//! seed and the players seated in its lobby, followed by a `Delta` for every
+//! tick: the inputs of the snakes and the ones disqualified. Spectators
+//! joining in the middle get all the deltas so far and catch up at once.
+//! Deltas are held back for the delay of the broadcaster, keeping
+//! the spectators behind the players.
+//!
+//! Spectators get the list of matches whenever it changes and pick the
+//! match to watch, switching between them at any time.
+use std::{
+    collections::VecDeque,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+        Arc, Mutex, MutexGuard, Weak,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::GameConfig,
+    direction::Direction,
+    lobby::Member,
+    net::{Frame, NetError},
+    simulation::{Simulation, StepEvent},
+};
+
+/// Port the broadcaster listens on by default
+pub const DEFAULT_SPECTATOR_PORT: u16 = 7172;
+/// How often the broadcaster looks for news for each spectator
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Finished matches kept for the spectators to watch, the oldest ones go first
+const MAX_FINISHED: usize = 32;
+
+/// Everything that happened to the snakes in a single tick
+///
+#[derive(PartialEq, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Delta {
+    pub inputs: Vec<(usize, Direction)>,
+    /// Players disqualified right before the tick
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disqualified: Vec<usize>,
+}
+
+impl Delta {
+    /// Advances `sim` by a tick, taking the disqualified players out of the game first.
+    ///
+    pub fn apply(&self, sim: &mut Simulation) -> StepEvent {
+        for &player in &self.disqualified {
+            sim.disqualify(player);
+        }
+        sim.tick(&self.inputs)
+    }
+}
+
+impl From<&Frame> for Delta {
+    fn from(frame: &Frame) -> Self {
+        Self {
+            inputs: frame
+                .inputs
+                .iter()
+                .enumerate()
+                .filter_map(|(player, dir)| dir.map(|dir| (player, dir)))
+                .collect(),
+            disqualified: frame.dropped.clone(),
+        }
+    }
+}
+
+/// Match as listed to the spectators
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct MatchInfo {
+    pub id: u64,
+    pub name: String,
+    pub over: bool,
+}
+
+/// Message exchanged between the broadcaster and the spectators
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpectatorMessage {
+    /// Broadcaster lists all matches there are to watch
+    Matches { matches: Vec<MatchInfo> },
+    /// Spectator picks the match to watch
+    Watch { id: u64 },
+    /// Broadcaster starts streaming the match, the first `live_from`
+    /// deltas are the ones that were played before
+    Start {
+        id: u64,
+        name: String,
+        seed: u64,
+        config: GameConfig,
+        /// Players as seated in the lobby, empty if the match had none
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        roster: Vec<Member>,
+        live_from: u64,
+    },
+    /// Broadcaster sends the deltas from the `first` tick on
+    Deltas {
+        id: u64,
+        first: u64,
+        deltas: Vec<Delta>,
+    },
+    /// Match has no more deltas to come
+    End { id: u64 },
+}
+
+impl SpectatorMessage {
+    fn write_to<W: Write>(&self, writer: &mut W) -> Result<(), NetError> {
+        let mut line = serde_json::to_vec(self)?;
+        line.push(b'\n');
+        writer.write_all(&line)?;
+        Ok(())
+    }
+}
+
+/// Match fed to the broadcaster
+///
+struct Match {
+    id: u64,
+    name: String,
+    seed: u64,
+    config: GameConfig,
+    roster: Vec<Member>,
+    /// Delta of every tick with the moment it was played
+    deltas: Vec<(Instant, Delta)>,
+    ended: Option<Instant>,
+}
+
+impl Match {
+    /// Return the number of deltas old enough to be shown with the given `delay`.
+    ///
+    fn released(&self, delay: Duration) -> usize {
+        self.deltas
+            .partition_point(|(played, _)| played.elapsed() >= delay)
+    }
+
+    fn is_over(&self, delay: Duration) -> bool {
+        self.ended.is_some_and(|ended| ended.elapsed() >= delay)
+            && self.released(delay) == self.deltas.len()
+    }
+
+    fn info(&self, delay: Duration) -> MatchInfo {
+        MatchInfo {
+            id: self.id,
+            name: self.name.clone(),
+            over: self.is_over(delay),
+        }
+    }
+}
+
+/// State shared by the broadcaster, its feeds and its spectators
+///
+struct Shared {
+    delay: Duration,
+    matches: Mutex<Vec<Match>>,
+    next_id: AtomicU64,
+}
+
+impl Shared {
+    fn matches(&self) -> MutexGuard<'_, Vec<Match>> {
+        self.matches.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Server streaming matches to any number of spectators, delayed by the
+/// configured time. Cloned handles feed the same spectators.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use snaek_core::{spectate::Broadcaster, GameConfig, Simulation};
+/// let broadcaster = Broadcaster::bind("0.0.0.0:7172", Duration::from_secs(10))?;
+/// let mut sim = Simulation::new(GameConfig::default(), 42);
+/// let feed = broadcaster.start_match("Exhibition", &sim, &[]);
+/// while !sim.is_over() {
+///     sim.tick(&[]);
+///     feed.tick(&[], &[]);
+/// }
+/// # Ok::<(), snaek_core::net::NetError>(())
+/// ```
+///
+#[derive(Clone)]
+pub struct Broadcaster {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+}
+
+impl Broadcaster {
+    /// Creates new `Broadcaster` listening for spectators on `addr`
+    /// until the broadcaster and all of its feeds are dropped.
+    ///
+    pub fn bind<A: ToSocketAddrs>(addr: A, delay: Duration) -> Result<Self, NetError> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let shared = Arc::new(Shared {
+            delay,
+            matches: Mutex::new(Vec::new()),
+            next_id: AtomicU64::new(1),
+        });
+        let broadcaster = Self {
+            addr: listener.local_addr()?,
+            shared: Arc::clone(&shared),
+        };
+
+        let shared = Arc::downgrade(&shared);
+        thread::spawn(move || {
+            while shared.strong_count() > 0 {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let shared = Weak::clone(&shared);
+                        thread::spawn(move || {
+                            // Spectator that went away is no error of the broadcaster
+                            let _ = serve(stream, shared);
+                        });
+                    }
+                    // Nobody is knocking, or somebody gave up before getting in
+                    Err(_) => thread::sleep(POLL_INTERVAL),
+                }
+            }
+        });
+        Ok(broadcaster)
+    }
+
+    /// Return the address the broadcaster listens on.
+    ///
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Lists a new match under the given `name`, starting from the state of `sim`,
+    /// which has to be a fresh one, with the players of the `roster` if it was
+    /// set up in a lobby. Return the feed to stream its ticks through.
+    ///
+    pub fn start_match(&self, name: &str, sim: &Simulation, roster: &[Member]) -> MatchFeed {
+        let id = self.shared.next_id.fetch_add(1, Ordering::SeqCst);
+        let mut matches = self.shared.matches();
+        matches.push(Match {
+            id,
+            name: name.to_string(),
+            seed: sim.seed,
+            config: sim.config.clone(),
+            roster: roster.to_vec(),
+            deltas: Vec::new(),
+            ended: None,
+        });
+        let finished = matches.iter().filter(|m| m.ended.is_some()).count();
+        if finished > MAX_FINISHED {
+            if let Some(oldest) = matches.iter().position(|m| m.ended.is_some()) {
+                matches.remove(oldest);
+            }
+        }
+
+        MatchFeed {
+            id,
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+/// Handle streaming the ticks of a single match, which ends once it is dropped
+///
+pub struct MatchFeed {
+    id: u64,
+    shared: Arc<Shared>,
+}
+
+impl MatchFeed {
+    /// Return the id the match is listed under.
+    ///
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Streams the next tick: the `inputs` it was run with and the players
+    /// `disqualified` right before it.
+    ///
+    pub fn tick(&self, inputs: &[(usize, Direction)], disqualified: &[usize]) {
+        let delta = Delta {
+            inputs: inputs.to_vec(),
+            disqualified: disqualified.to_vec(),
+        };
+        self.push(delta);
+    }
+
+    /// Streams the next tick.
+    ///
+    pub fn push(&self, delta: Delta) {
+        let mut matches = self.shared.matches();
+        if let Some(m) = matches.iter_mut().find(|m| m.id == self.id) {
+            m.deltas.push((Instant::now(), delta));
+        }
+    }
+}
+
+impl Drop for MatchFeed {
+    fn drop(&mut self) {
+        let mut matches = self.shared.matches();
+        if let Some(m) = matches.iter_mut().find(|m| m.id == self.id) {
+            m.ended = Some(Instant::now());
+        }
+    }
+}
+
+/// Match streamed to a spectator, as far as it got
+///
+struct Stream {
+    id: u64,
+    started: bool,
+    /// Number of deltas sent so far
+    sent: usize,
+    ended: bool,
+}
+
+/// Streams the matches to a single spectator, for as long as it stays
+/// connected and the broadcaster is there.
+///
+fn serve(stream: TcpStream, shared: Weak<Shared>) -> Result<(), NetError> {
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    let mut writer = stream.try_clone()?;
+    let watch = read_messages(stream);
+
+    let mut listed = None;
+    let mut watched: Option<Stream> = None;
+    loop {
+        let shared = match shared.upgrade() {
+            Some(shared) => shared,
+            None => return Ok(()),
+        };
+        loop {
+            match watch.try_recv() {
+                Ok(Ok(SpectatorMessage::Watch { id })) => {
+                    watched = Some(Stream {
+                        id,
+                        started: false,
+                        sent: 0,
+                        ended: false,
+                    })
+                }
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+
+        let mut messages = Vec::new();
+        {
+            let delay = shared.delay;
+            let matches = shared.matches();
+            let infos: Vec<_> = matches.iter().map(|m| m.info(delay)).collect();
+            if listed.as_ref() != Some(&infos) {
+                messages.push(SpectatorMessage::Matches {
+                    matches: infos.clone(),
+                });
+                listed = Some(infos);
+            }
+
+            if let Some(stream) = &mut watched {
+                match matches.iter().find(|m| m.id == stream.id) {
+                    Some(m) => {
+                        let released = m.released(delay);
+                        if !stream.started {
+                            stream.started = true;
+                            messages.push(SpectatorMessage::Start {
+                                id: m.id,
+                                name: m.name.clone(),
+                                seed: m.seed,
+                                config: m.config.clone(),
+                                roster: m.roster.clone(),
+                                live_from: released as u64,
+                            });
+                        }
+                        if released > stream.sent {
+                            messages.push(SpectatorMessage::Deltas {
+                                id: m.id,
+                                first: stream.sent as u64,
+                                deltas: m.deltas[stream.sent..released]
+                                    .iter()
+                                    .map(|(_, delta)| delta.clone())
+                                    .collect(),
+                            });
+                            stream.sent = released;
+                        }
+                        if m.is_over(delay) && !stream.ended {
+                            messages.push(SpectatorMessage::End { id: m.id });
+                            stream.ended = true;
+                        }
+                    }
+                    None => {
+                        messages.push(SpectatorMessage::End { id: stream.id });
+                        watched = None;
+                    }
+                }
+            }
+        }
+
+        for message in messages {
+            message.write_to(&mut writer)?;
+        }
+        drop(shared);
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Reads the messages coming over `stream` on a thread of their own.
+/// The channel gets disconnected once the stream ends.
+///
+fn read_messages(stream: TcpStream) -> Receiver<Result<SpectatorMessage, NetError>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in BufReader::new(stream).lines() {
+            let message = line
+                .map_err(NetError::from)
+                .and_then(|line| Ok(serde_json::from_str(&line)?));
+            let failed = message.is_err();
+            if sender.send(message).is_err() || failed {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Match watched by a spectator
+///
+struct Watched {
+    id: u64,
+    name: String,
+    sim: Simulation,
+    roster: Vec<Member>,
+    /// Deltas received but not played yet
+    pending: VecDeque<Delta>,
+    /// Number of deltas received so far
+    received: u64,
+    /// Deltas before this one are played at once on arrival
+    live_from: u64,
+    ended: bool,
+}
+
+/// Client watching the matches of a `Broadcaster`
+///
+/// Deltas are played back one per tick with `advance`, at whatever pace
+/// the caller runs it, apart from the ones played before the spectator
+/// joined the match, which are caught up with as soon as they arrive.
+///
+pub struct Spectator {
+    stream: TcpStream,
+    messages: Receiver<Result<SpectatorMessage, NetError>>,
+    matches: Vec<MatchInfo>,
+    watched: Option<Watched>,
+}
+
+impl Spectator {
+    /// Connects to the broadcaster at `addr`.
+    ///
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, NetError> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        let messages = read_messages(stream.try_clone()?);
+        Ok(Self {
+            stream,
+            messages,
+            matches: Vec::new(),
+            watched: None,
+        })
+    }
+
+    /// Return all matches there are to watch, as last listed by the broadcaster.
+    ///
+    pub fn matches(&self) -> &[MatchInfo] {
+        &self.matches
+    }
+
+    /// Starts watching the match with the given id, leaving the current one.
+    /// It is shown once the broadcaster starts streaming it.
+    ///
+    pub fn watch(&mut self, id: u64) -> Result<(), NetError> {
+        SpectatorMessage::Watch { id }.write_to(&mut self.stream)
+    }
+
+    /// Return the id and the name of the match being watched.
+    ///
+    pub fn watching(&self) -> Option<(u64, &str)> {
+        self.watched
+            .as_ref()
+            .map(|watched| (watched.id, watched.name.as_str()))
+    }
+
+    /// Return the simulation of the match being watched.
+    ///
+    pub fn sim(&self) -> Option<&Simulation> {
+        self.watched.as_ref().map(|watched| &watched.sim)
+    }
+
+    /// Return the players of the watched match as seated in its lobby,
+    /// empty if it had none.
+    ///
+    pub fn roster(&self) -> &[Member] {
+        self.watched
+            .as_ref()
+            .map_or(&[], |watched| watched.roster.as_slice())
+    }
+
+    /// Check if the watched match has ended and all of it has been played back.
+    ///
+    pub fn is_over(&self) -> bool {
+        self.watched
+            .as_ref()
+            .is_some_and(|watched| watched.ended && watched.pending.is_empty())
+    }
+
+    /// Receives all messages there are, without waiting for any.
+    ///
+    pub fn receive(&mut self) -> Result<(), NetError> {
+        loop {
+            let message = match self.messages.try_recv() {
+                Ok(message) => message?,
+                Err(TryRecvError::Empty) => return Ok(()),
+                Err(TryRecvError::Disconnected) => return Err(NetError::Disconnected),
+            };
+            self.handle(message);
+        }
+    }
+
+    /// Plays back the next delta of the watched match, if it already arrived.
+    /// Return what happened in the tick.
+    ///
+    pub fn advance(&mut self) -> Option<StepEvent> {
+        let watched = self.watched.as_mut()?;
+        let delta = watched.pending.pop_front()?;
+        Some(delta.apply(&mut watched.sim))
+    }
+
+    fn handle(&mut self, message: SpectatorMessage) {
+        match message {
+            SpectatorMessage::Matches { matches } => self.matches = matches,
+            SpectatorMessage::Start {
+                id,
+                name,
+                seed,
+                config,
+                roster,
+                live_from,
+            } => {
+                self.watched = Some(Watched {
+                    id,
+                    name,
+                    sim: Simulation::new(config, seed),
+                    roster,
+                    pending: VecDeque::new(),
+                    received: 0,
+                    live_from,
+                    ended: false,
+                })
+            }
+            SpectatorMessage::Deltas { id, first, deltas } => {
+                let watched = match &mut self.watched {
+                    Some(watched) if watched.id == id && watched.received == first => watched,
+                    _ => return,
+                };
+                for delta in deltas {
+                    if watched.received < watched.live_from {
+                        delta.apply(&mut watched.sim);
+                    } else {
+                        watched.pending.push_back(delta);
+                    }
+                    watched.received += 1;
+                }
+            }
+            SpectatorMessage::End { id } => {
+                if let Some(watched) = self.watched.as_mut().filter(|watched| watched.id == id) {
+                    watched.ended = true;
+                }
+            }
+            SpectatorMessage::Watch { .. } => {}
+        }
+    }
+}
@@ -0,0 +1,48 @@
+//! Module with FixedTimestep struct
+use crate::consts;
+
+/// Accumulator that turns variable frame times into a whole number
+/// of fixed-length simulation ticks.
+///
+/// Whatever is left in the accumulator after the ticks are consumed
+/// is exposed as `alpha`, so the frontend can interpolate between
+/// the last two simulated states.
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct FixedTimestep {
+    pub tick_duration: f32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    /// Create new `FixedTimestep` running `ticks_per_sec` ticks per second.
+    ///
+    pub fn new(ticks_per_sec: u32) -> Self {
+        Self {
+            tick_duration: 1. / ticks_per_sec as f32,
+            accumulator: 0.,
+        }
+    }
+
+    /// Accumulate `frame_time` seconds and return how many ticks should be simulated.
+    ///
+    /// Frame time is capped to `consts::MAX_FRAME_TIME`, so a long hiccup
+    /// does not make the game try to catch up on it all at once.
+    ///
+    pub fn advance(&mut self, frame_time: f32) -> u32 {
+        self.accumulator += f32::min(frame_time, consts::MAX_FRAME_TIME);
+
+        let mut ticks = 0;
+        while self.accumulator >= self.tick_duration {
+            self.accumulator -= self.tick_duration;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    /// Return how far between the last and the next tick we are, between 0 and 1.
+    ///
+    pub fn alpha(&self) -> f32 {
+        f32::clamp(self.accumulator / self.tick_duration, 0., 1.)
+    }
+}
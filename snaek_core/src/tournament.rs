@@ -0,0 +1,787 @@
+//! Module with tournaments played between bots without any window
+//!
+//! Tournament file is a TOML file listing the entrants, each of them being
+//! a built-in bot of the given difficulty, an external bot process or an HTTP bot:
+//!
+//! ```toml
+//! format = "swiss"       # or "round_robin", the default
+//! rounds = 5             # only used by the Swiss format
+//! games_per_pairing = 2  # sides are swapped every other game
+//! seed = 42
+//! max_ticks = 36000
+//!
+//! [[entrant]]
+//! name = "hard"
+//! difficulty = "hard"
+//!
+//! [[entrant]]
+//! name = "chaser"
+//! command = "python3 bots/food_chaser.py"
+//!
+//! [[entrant]]
+//! name = "web"
+//! url = "http://127.0.0.1:8000"
+//! ```
+//!
+//! Every game is a duel with its own seed derived from the tournament one,
+//! so the results do not depend on the number of threads the games are played on.
+//! Round robin knows all of its games up front and plays them all at once,
+//! Swiss pairs entrants with similar points, avoiding rematches, round by round.
+use std::{
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ai::Difficulty,
+    battlesnake::{HttpBot, DEFAULT_HTTP_TIMEOUT},
+    config::{ConfigError, GameConfig},
+    controller::{self, Controller},
+    external::{ExternalBot, DEFAULT_MOVE_TIMEOUT},
+    rating::{Elo, Glicko, Outcome, INITIAL_RATING},
+    replay::Replay,
+    rng,
+    simulation::{Simulation, StepEvent},
+    spectate::Broadcaster,
+};
+
+/// Ticks after which a game is stopped and decided by the score, 5 minutes by default
+pub const DEFAULT_MAX_TICKS: u64 = 36000;
+/// Rounds of a Swiss tournament, if not configured otherwise
+pub const DEFAULT_SWISS_ROUNDS: usize = 5;
+
+/// Error that can occur while loading or running a tournament
+///
+#[derive(Debug)]
+pub enum TournamentError {
+    Io(io::Error),
+    Parse(toml::de::Error),
+    Config(ConfigError),
+    Invalid(String),
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TournamentError::Io(err) => write!(f, "Tournament I/O failed: {}", err),
+            TournamentError::Parse(err) => write!(f, "Could not parse tournament: {}", err),
+            TournamentError::Config(err) => write!(f, "Invalid game config: {}", err),
+            TournamentError::Invalid(reason) => write!(f, "Invalid tournament: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for TournamentError {}
+
+impl From<io::Error> for TournamentError {
+    fn from(err: io::Error) -> Self {
+        TournamentError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for TournamentError {
+    fn from(err: toml::de::Error) -> Self {
+        TournamentError::Parse(err)
+    }
+}
+
+impl From<ConfigError> for TournamentError {
+    fn from(err: ConfigError) -> Self {
+        TournamentError::Config(err)
+    }
+}
+
+/// What drives the snake of an entrant
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntrantKind {
+    /// Built-in bot of the given difficulty
+    Difficulty(Difficulty),
+    /// External bot process started with the given command
+    Command(String),
+    /// HTTP bot under the given base url
+    Url(String),
+}
+
+/// Bot taking part in a tournament
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Entrant {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: EntrantKind,
+}
+
+/// How the entrants are paired
+///
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// Everyone plays everyone
+    #[default]
+    RoundRobin,
+    /// Entrants with similar points play each other, for a fixed number of rounds
+    Swiss,
+}
+
+/// Tournament settings and entrants, see the module docs for the file format
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Tournament {
+    #[serde(default)]
+    pub format: Format,
+    #[serde(default = "default_rounds")]
+    pub rounds: usize,
+    #[serde(default = "default_games_per_pairing")]
+    pub games_per_pairing: u32,
+    #[serde(default)]
+    pub seed: u64,
+    #[serde(default = "default_max_ticks")]
+    pub max_ticks: u64,
+    /// Milliseconds an external bot gets to answer
+    #[serde(default = "default_bot_timeout")]
+    pub bot_timeout: u64,
+    /// Milliseconds an HTTP bot gets to answer
+    #[serde(default = "default_http_timeout")]
+    pub http_timeout: u64,
+    #[serde(rename = "entrant")]
+    pub entrants: Vec<Entrant>,
+}
+
+fn default_rounds() -> usize {
+    DEFAULT_SWISS_ROUNDS
+}
+
+fn default_games_per_pairing() -> u32 {
+    1
+}
+
+fn default_max_ticks() -> u64 {
+    DEFAULT_MAX_TICKS
+}
+
+fn default_bot_timeout() -> u64 {
+    DEFAULT_MOVE_TIMEOUT.as_millis() as u64
+}
+
+fn default_http_timeout() -> u64 {
+    DEFAULT_HTTP_TIMEOUT.as_millis() as u64
+}
+
+/// Single game to be played: who plays which side, on which seed
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+struct GameJob {
+    idx: usize,
+    round: usize,
+    sides: [usize; 2],
+    seed: u64,
+}
+
+/// Outcome of a single game of the tournament, entrants given by their indices
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct GameRecord {
+    /// Number of the game, counted from 1 in the order the games were scheduled
+    pub game: usize,
+    /// Round the game was played in, counted from 1
+    pub round: usize,
+    pub entrants: [usize; 2],
+    pub seed: u64,
+    pub ticks: u64,
+    pub scores: [u32; 2],
+    /// Entrant that won the game, none for a draw
+    pub winner: Option<usize>,
+    pub disqualified: Vec<usize>,
+    pub replay: Option<PathBuf>,
+}
+
+impl GameRecord {
+    /// Return the outcome of the game for its first side.
+    ///
+    pub fn outcome(&self) -> Outcome {
+        match self.winner {
+            Some(winner) if winner == self.entrants[0] => 1.,
+            Some(_) => 0.,
+            None => 0.5,
+        }
+    }
+}
+
+/// Results of a single entrant over the whole tournament
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct Standing {
+    pub name: String,
+    pub games: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Rounds without an opponent, each worth a win in points
+    pub byes: u32,
+    /// 1 for every win or bye, 0.5 for every draw
+    pub points: f64,
+    /// Food eaten in all games
+    pub food: u32,
+    pub disqualifications: u32,
+    pub elo: f64,
+    pub glicko: Glicko,
+}
+
+impl Standing {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            games: 0,
+            wins: 0,
+            draws: 0,
+            losses: 0,
+            byes: 0,
+            points: 0.,
+            food: 0,
+            disqualifications: 0,
+            elo: INITIAL_RATING,
+            glicko: Glicko::default(),
+        }
+    }
+}
+
+/// Final standings, best entrant first, together with all games played
+///
+#[derive(PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub struct TournamentResults {
+    pub entrants: Vec<String>,
+    pub standings: Vec<Standing>,
+    pub games: Vec<GameRecord>,
+}
+
+impl Tournament {
+    /// Load tournament from the TOML file under `path`.
+    ///
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TournamentError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+
+    /// Parse and validate tournament from the TOML string.
+    ///
+    pub fn from_toml(toml: &str) -> Result<Self, TournamentError> {
+        let tournament: Self = toml::from_str(toml)?;
+        tournament.validate()?;
+        Ok(tournament)
+    }
+
+    /// Check that there is someone to play and a way to play.
+    ///
+    pub fn validate(&self) -> Result<(), TournamentError> {
+        let invalid = |reason: String| Err(TournamentError::Invalid(reason));
+        if self.entrants.len() < 2 {
+            return invalid(format!(
+                "at least 2 entrants are needed, got {}",
+                self.entrants.len()
+            ));
+        }
+        let mut names = HashSet::new();
+        if let Some(entrant) = self
+            .entrants
+            .iter()
+            .find(|entrant| !names.insert(&entrant.name))
+        {
+            return invalid(format!("entrant '{}' is listed twice", entrant.name));
+        }
+        if self.games_per_pairing == 0 {
+            return invalid("games_per_pairing must be at least 1".to_string());
+        }
+        if self.format == Format::Swiss && self.rounds == 0 {
+            return invalid("rounds must be at least 1".to_string());
+        }
+        if self.max_ticks == 0 {
+            return invalid("max_ticks must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Play the whole tournament on up to `threads` threads, with games set up
+    /// by the `config` apart from the snakes. Each game is recorded into
+    /// `replay_dir`, if given.
+    ///
+    pub fn run(
+        &self,
+        config: &GameConfig,
+        threads: usize,
+        replay_dir: Option<&Path>,
+    ) -> Result<TournamentResults, TournamentError> {
+        self.play(config, threads, replay_dir, None)
+    }
+
+    /// Play the whole tournament like `run` does, streaming every game to the
+    /// spectators of the `broadcaster`. Games are played in real time then,
+    /// for the spectators to watch them live.
+    ///
+    pub fn run_broadcast(
+        &self,
+        config: &GameConfig,
+        threads: usize,
+        replay_dir: Option<&Path>,
+        broadcaster: &Broadcaster,
+    ) -> Result<TournamentResults, TournamentError> {
+        self.play(config, threads, replay_dir, Some(broadcaster))
+    }
+
+    fn play(
+        &self,
+        config: &GameConfig,
+        threads: usize,
+        replay_dir: Option<&Path>,
+        broadcaster: Option<&Broadcaster>,
+    ) -> Result<TournamentResults, TournamentError> {
+        self.validate()?;
+        let config = GameConfig {
+            players: 0,
+            bots: 2,
+            ..config.clone()
+        };
+        config.validate()?;
+        if let Some(dir) = replay_dir {
+            fs::create_dir_all(dir)?;
+        }
+
+        let mut standings: Vec<_> = self
+            .entrants
+            .iter()
+            .map(|entrant| Standing::new(&entrant.name))
+            .collect();
+        let mut games = Vec::new();
+        match self.format {
+            Format::RoundRobin => {
+                let jobs = self.round_robin_jobs();
+                games = self.play_games(&config, &jobs, threads, replay_dir, broadcaster)?;
+                for round in 1..=jobs.last().map_or(0, |job| job.round) {
+                    let round_games: Vec<_> =
+                        games.iter().filter(|game| game.round == round).collect();
+                    Self::rate_round(&mut standings, &round_games);
+                }
+            }
+            Format::Swiss => {
+                let mut played = HashSet::new();
+                for round in 1..=self.rounds {
+                    let (pairings, bye) = Self::swiss_pairings(&standings, &played);
+                    if let Some(bye) = bye {
+                        standings[bye].byes += 1;
+                        standings[bye].points += 1.;
+                    }
+                    let jobs = self.pairing_jobs(round, &pairings, games.len());
+                    let round_games =
+                        self.play_games(&config, &jobs, threads, replay_dir, broadcaster)?;
+                    Self::rate_round(&mut standings, &round_games.iter().collect::<Vec<_>>());
+                    played.extend(pairings.iter().map(|&(a, b)| (a.min(b), a.max(b))));
+                    games.extend(round_games);
+                }
+            }
+        }
+
+        let entrants = self
+            .entrants
+            .iter()
+            .map(|entrant| entrant.name.clone())
+            .collect();
+        Self::sort_standings(&mut standings);
+        Ok(TournamentResults {
+            entrants,
+            standings,
+            games,
+        })
+    }
+
+    /// Return controller driving the snake of the given entrant.
+    ///
+    fn controller(&self, entrant: &Entrant) -> Box<dyn Controller> {
+        match &entrant.kind {
+            EntrantKind::Difficulty(difficulty) => difficulty.controller(),
+            EntrantKind::Command(command) => Box::new(ExternalBot::spawn(
+                command,
+                Duration::from_millis(self.bot_timeout),
+            )),
+            EntrantKind::Url(url) => {
+                Box::new(HttpBot::new(url, Duration::from_millis(self.http_timeout)))
+            }
+        }
+    }
+
+    /// Return seed of the game under `idx`, independent of all the other games.
+    ///
+    fn game_seed(&self, idx: usize) -> u64 {
+        let mut rng = rng::from_seed(self.seed);
+        rng.set_stream(idx as u64);
+        rng.gen()
+    }
+
+    /// Turn pairings of a single round into games, swapping sides every other game.
+    ///
+    fn pairing_jobs(
+        &self,
+        round: usize,
+        pairings: &[(usize, usize)],
+        first: usize,
+    ) -> Vec<GameJob> {
+        let mut jobs = Vec::new();
+        for &(a, b) in pairings {
+            for game in 0..self.games_per_pairing {
+                let idx = first + jobs.len();
+                jobs.push(GameJob {
+                    idx,
+                    round,
+                    sides: if game % 2 == 0 { [a, b] } else { [b, a] },
+                    seed: self.game_seed(idx),
+                });
+            }
+        }
+        jobs
+    }
+
+    /// Schedule all games of the round robin with the circle method, so that
+    /// every entrant plays at most once per round.
+    ///
+    fn round_robin_jobs(&self) -> Vec<GameJob> {
+        let mut ids: Vec<Option<usize>> = (0..self.entrants.len()).map(Some).collect();
+        if ids.len() % 2 == 1 {
+            ids.push(None);
+        }
+        let len = ids.len();
+
+        let mut jobs = Vec::new();
+        for round in 0..len - 1 {
+            let pairings: Vec<_> = (0..len / 2)
+                .filter_map(|idx| match (ids[idx], ids[len - 1 - idx]) {
+                    // Entrant fixed in place would always play the same side otherwise
+                    (Some(a), Some(b)) if idx == 0 && round % 2 == 1 => Some((b, a)),
+                    (Some(a), Some(b)) => Some((a, b)),
+                    _ => None,
+                })
+                .collect();
+            jobs.extend(self.pairing_jobs(round + 1, &pairings, jobs.len()));
+            ids[1..].rotate_right(1);
+        }
+        jobs
+    }
+
+    /// Pair entrants for the next Swiss round, best ones first, each with
+    /// the best one it did not play yet. With odd number of entrants, the
+    /// lowest ranked one without a bye yet sits the round out.
+    ///
+    fn swiss_pairings(
+        standings: &[Standing],
+        played: &HashSet<(usize, usize)>,
+    ) -> (Vec<(usize, usize)>, Option<usize>) {
+        let mut pool: Vec<usize> = (0..standings.len()).collect();
+        pool.sort_by(|&a, &b| Self::compare(&standings[a], &standings[b]));
+
+        let mut bye = None;
+        if pool.len() % 2 == 1 {
+            let pos = pool
+                .iter()
+                .rposition(|&idx| standings[idx].byes == 0)
+                .unwrap_or(pool.len() - 1);
+            bye = Some(pool.remove(pos));
+        }
+
+        let mut pairings = Vec::new();
+        while pool.len() >= 2 {
+            let a = pool.remove(0);
+            let pos = pool
+                .iter()
+                .position(|&b| !played.contains(&(a.min(b), a.max(b))))
+                .unwrap_or(0);
+            pairings.push((a, pool.remove(pos)));
+        }
+        (pairings, bye)
+    }
+
+    /// Play all `jobs` on up to `threads` threads, returning the games in the order of the jobs.
+    ///
+    fn play_games(
+        &self,
+        config: &GameConfig,
+        jobs: &[GameJob],
+        threads: usize,
+        replay_dir: Option<&Path>,
+        broadcaster: Option<&Broadcaster>,
+    ) -> Result<Vec<GameRecord>, TournamentError> {
+        let next = AtomicUsize::new(0);
+        let records: Mutex<Vec<Option<io::Result<GameRecord>>>> =
+            Mutex::new(jobs.iter().map(|_| None).collect());
+        thread::scope(|scope| {
+            for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+                scope.spawn(|| loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(job) = jobs.get(idx) else {
+                        break;
+                    };
+                    let record = self.play_game(config, job, replay_dir, broadcaster);
+                    records.lock().unwrap_or_else(|err| err.into_inner())[idx] = Some(record);
+                });
+            }
+        });
+
+        records
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner())
+            .into_iter()
+            .map(|record| Ok(record.expect("every game is played")?))
+            .collect()
+    }
+
+    /// Play a single game until one of the snakes is left or the time is up,
+    /// in real time if it is streamed to the spectators of the `broadcaster`.
+    ///
+    fn play_game(
+        &self,
+        config: &GameConfig,
+        job: &GameJob,
+        replay_dir: Option<&Path>,
+        broadcaster: Option<&Broadcaster>,
+    ) -> io::Result<GameRecord> {
+        let mut sim = Simulation::new(config.clone(), job.seed);
+        let mut controllers: Vec<_> = job
+            .sides
+            .iter()
+            .map(|&entrant| self.controller(&self.entrants[entrant]))
+            .collect();
+        let mut replay = Replay::new(&sim);
+        let mut disqualified = Vec::new();
+        let feed = broadcaster.map(|broadcaster| {
+            let name = format!(
+                "Game {} (round {}): {} vs {}",
+                job.idx + 1,
+                job.round,
+                self.entrants[job.sides[0]].name,
+                self.entrants[job.sides[1]].name
+            );
+            broadcaster.start_match(&name, &sim, &[])
+        });
+        let start = Instant::now();
+
+        while sim.tick < self.max_ticks {
+            let inputs = controller::collect_inputs(&mut controllers, &sim);
+            let mut sides = Vec::new();
+            for (side, _) in controller::disqualify(&controllers, &mut sim) {
+                replay.disqualify(sim.tick, side);
+                disqualified.push(job.sides[side]);
+                sides.push(side);
+            }
+            replay.record(sim.tick, &inputs);
+            if let Some(feed) = &feed {
+                feed.tick(&inputs, &sides);
+                let due = Duration::from_secs_f32(sim.config.tick_duration() * sim.tick as f32);
+                thread::sleep(due.saturating_sub(start.elapsed()));
+            }
+            if sim.tick(&inputs) == StepEvent::Died {
+                break;
+            }
+        }
+        controller::game_over(&mut controllers, &sim);
+        replay.finish(sim.tick, sim.players[0].score);
+
+        let scores = [sim.players[0].score, sim.players[1].score];
+        let winning_side = match sim.winner() {
+            Some(side) => Some(side),
+            // Time is up, the one that ate more wins
+            None if !sim.is_over() && scores[0] != scores[1] => {
+                Some(if scores[0] > scores[1] { 0 } else { 1 })
+            }
+            None => None,
+        };
+
+        let replay_path = match replay_dir {
+            Some(dir) => {
+                let path = dir.join(format!(
+                    "{:04}_{}_vs_{}.replay",
+                    job.idx + 1,
+                    file_name_part(&self.entrants[job.sides[0]].name),
+                    file_name_part(&self.entrants[job.sides[1]].name)
+                ));
+                replay.save(&path)?;
+                Some(path)
+            }
+            None => None,
+        };
+
+        Ok(GameRecord {
+            game: job.idx + 1,
+            round: job.round,
+            entrants: job.sides,
+            seed: job.seed,
+            ticks: sim.tick,
+            scores,
+            winner: winning_side.map(|side| job.sides[side]),
+            disqualified,
+            replay: replay_path,
+        })
+    }
+
+    /// Update the standings with all games of a single round. Elo follows the
+    /// games one by one, Glicko takes the whole round as its rating period.
+    ///
+    fn rate_round(standings: &mut [Standing], games: &[&GameRecord]) {
+        let elo = Elo::default();
+        let before: Vec<Glicko> = standings.iter().map(|standing| standing.glicko).collect();
+        let mut periods: Vec<Vec<(Glicko, Outcome)>> = vec![Vec::new(); standings.len()];
+
+        for game in games {
+            let [a, b] = game.entrants;
+            let outcome = game.outcome();
+            let (elo_a, elo_b) = elo.update((standings[a].elo, standings[b].elo), outcome);
+            standings[a].elo = elo_a;
+            standings[b].elo = elo_b;
+            periods[a].push((before[b], outcome));
+            periods[b].push((before[a], 1. - outcome));
+
+            for (side, &entrant) in game.entrants.iter().enumerate() {
+                let standing = &mut standings[entrant];
+                let outcome = if side == 0 { outcome } else { 1. - outcome };
+                standing.games += 1;
+                standing.points += outcome;
+                standing.food += game.scores[side];
+                match outcome {
+                    o if o > 0.5 => standing.wins += 1,
+                    o if o < 0.5 => standing.losses += 1,
+                    _ => standing.draws += 1,
+                }
+            }
+            for &entrant in &game.disqualified {
+                standings[entrant].disqualifications += 1;
+            }
+        }
+
+        for (standing, period) in standings.iter_mut().zip(&periods) {
+            standing.glicko = standing.glicko.update(period);
+        }
+    }
+
+    /// Order standings by points, then by Elo and finally by name.
+    ///
+    fn compare(a: &Standing, b: &Standing) -> std::cmp::Ordering {
+        b.points
+            .total_cmp(&a.points)
+            .then(b.elo.total_cmp(&a.elo))
+            .then_with(|| a.name.cmp(&b.name))
+    }
+
+    fn sort_standings(standings: &mut [Standing]) {
+        standings.sort_by(Self::compare);
+    }
+}
+
+/// Return `name` with everything but letters, digits, `-` and `_` replaced, to be used in file names.
+///
+fn file_name_part(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Quote CSV field if needed.
+///
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl TournamentResults {
+    /// Return the standings as CSV, a header line followed by a line per entrant.
+    ///
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "rank,name,games,wins,draws,losses,byes,points,food,disqualifications,elo,glicko,glicko_deviation\n",
+        );
+        for (idx, s) in self.standings.iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{},{:.1},{:.1},{:.1}\n",
+                idx + 1,
+                csv_field(&s.name),
+                s.games,
+                s.wins,
+                s.draws,
+                s.losses,
+                s.byes,
+                s.points,
+                s.food,
+                s.disqualifications,
+                s.elo,
+                s.glicko.rating,
+                s.glicko.deviation
+            ));
+        }
+        csv
+    }
+
+    /// Return the standings and all games as pretty printed JSON.
+    ///
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("tournament results are serializable")
+    }
+
+    /// Save results into the file under `path`, as JSON if it ends with `.json`, as CSV otherwise.
+    ///
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let is_json = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+        fs::write(
+            path,
+            if is_json {
+                self.to_json()
+            } else {
+                self.to_csv()
+            },
+        )
+    }
+}
+
+impl fmt::Display for TournamentResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>3}  {:<16} {:>5} {:>4} {:>4} {:>4} {:>6} {:>5} {:>6} {:>6} {:>5}",
+            "#", "name", "games", "won", "draw", "lost", "points", "food", "elo", "glicko", "rd"
+        )?;
+        for (idx, s) in self.standings.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>3}. {:<16} {:>5} {:>4} {:>4} {:>4} {:>6.1} {:>5} {:>6.0} {:>6.0} {:>5.0}",
+                idx + 1,
+                s.name,
+                s.games,
+                s.wins,
+                s.draws,
+                s.losses,
+                s.points,
+                s.food,
+                s.elo,
+                s.glicko.rating,
+                s.glicko.deviation
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,41 +1,33 @@
-use ggez::{
-    graphics::{self, Rect},
-    Context,
-};
+use serde::{Deserialize, Serialize};
 
-#[cfg(feature = "debug")]
-use ggez::graphics::{Color, Mesh};
+use crate::{coords::Coords, direction::Direction, rect::Rect};
 
-use crate::game::{coords::Coords, direction::Direction};
-
-use super::{
-    consts,
-    segment::{Growable, Renderable},
-    Renderer,
-};
+use crate::segment::{Collidable, Growable};
 
 /// Curved segment of a snake, 0-90 degrees of a ring.
 ///
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Turn {
     pub percentage: f32,
     pub is_growing: bool,
     pub pos: Coords,
     pub in_dir: Direction,
     pub out_dir: Direction,
+    pub width: f32,
 }
 
 impl Turn {
     /// Create a new `Turn` that starts on the given `pos` with `in_dir`
     /// and turns towards `out_dir`.
     ///
-    pub fn new(pos: Coords, in_dir: Direction, out_dir: Direction) -> Self {
+    pub fn new(pos: Coords, in_dir: Direction, out_dir: Direction, width: f32) -> Self {
         Self {
             percentage: 0.,
             is_growing: true,
             pos,
             in_dir,
             out_dir,
+            width,
         }
     }
 }
@@ -46,12 +38,8 @@ impl Growable for Turn {
             return dist;
         }
 
-        let left = f32::clamp(
-            dist - (1. - self.percentage) * consts::SNAKE_WIDTH,
-            0.,
-            dist,
-        );
-        self.percentage = f32::clamp(self.percentage + dist / consts::SNAKE_WIDTH, 0., 1.);
+        let left = f32::clamp(dist - (1. - self.percentage) * self.width, 0., dist);
+        self.percentage = f32::clamp(self.percentage + dist / self.width, 0., 1.);
         self.is_growing = self.percentage < 1.;
 
         left
@@ -62,79 +50,37 @@ impl Growable for Turn {
             return dist;
         }
 
-        let left = f32::clamp(dist - self.percentage * consts::SNAKE_WIDTH, 0., dist);
-        self.percentage = f32::clamp(self.percentage - dist / consts::SNAKE_WIDTH, 0., 1.);
+        let left = f32::clamp(dist - self.percentage * self.width, 0., dist);
+        self.percentage = f32::clamp(self.percentage - dist / self.width, 0., 1.);
 
         left
     }
 
     fn end(&self) -> Coords {
         self.pos
-            + self.in_dir.as_coords() * consts::SNAKE_HALF_WIDTH
-            + self.out_dir.as_coords() * consts::SNAKE_HALF_WIDTH
+            + self.in_dir.as_coords() * (self.width / 2.)
+            + self.out_dir.as_coords() * (self.width / 2.)
     }
 
     fn direction(&self) -> Direction {
         self.out_dir
     }
+
+    fn length(&self) -> f32 {
+        self.percentage * self.width
+    }
 }
 
-impl Renderable for Turn {
+impl Collidable for Turn {
     fn bounding_box(&self) -> Rect {
         let (x, y) = match self.in_dir {
-            Direction::Up => (
-                self.pos.x - consts::SNAKE_HALF_WIDTH,
-                self.pos.y - (consts::SNAKE_WIDTH),
-            ),
-            Direction::Down => (self.pos.x - consts::SNAKE_HALF_WIDTH, self.pos.y),
-            Direction::Left => (
-                self.pos.x - (consts::SNAKE_WIDTH),
-                self.pos.y - consts::SNAKE_HALF_WIDTH,
-            ),
-            Direction::Right => (self.pos.x, self.pos.y - consts::SNAKE_HALF_WIDTH),
+            Direction::Up => (self.pos.x - self.width / 2., self.pos.y - self.width),
+            Direction::Down => (self.pos.x - self.width / 2., self.pos.y),
+            Direction::Left => (self.pos.x - self.width, self.pos.y - self.width / 2.),
+            Direction::Right => (self.pos.x, self.pos.y - self.width / 2.),
         };
 
-        Rect::new(x, y, consts::SNAKE_WIDTH, consts::SNAKE_WIDTH)
-    }
-
-    fn draw(&self, ctx: &mut Context) {
-        let turn_type = TurnType::from_dirs(&self.in_dir, &self.out_dir);
-        let (margin, is_reversed) = match turn_type {
-            TurnType::DownRight => (Coords { x: 1., y: -1. }, self.out_dir == Direction::Up),
-            TurnType::DownLeft => (Coords { x: -1., y: -1. }, self.out_dir == Direction::Left),
-            TurnType::UpLeft => (Coords { x: -1., y: 1. }, self.out_dir == Direction::Down),
-            TurnType::UpRight => (Coords { x: 1., y: 1. }, self.out_dir == Direction::Right),
-        };
-
-        let pos = self.pos
-            + self.in_dir.as_coords() * consts::SNAKE_HALF_WIDTH
-            + margin * consts::HALF_TURN_MARGIN;
-        if let Ok(mesh) = Renderer::create_qt_ring(
-            ctx,
-            pos,
-            consts::SNAKE_WIDTH + consts::TURN_MARGIN,
-            consts::TURN_MARGIN,
-            turn_type,
-            self.percentage,
-            is_reversed ^ self.is_growing,
-            true,
-        ) {
-            graphics::draw(ctx, &mesh, graphics::DrawParam::default())
-                .expect("Error while drawing Turn");
-        }
-
-        #[cfg(feature = "debug")]
-        {
-            let mesh = Mesh::new_rectangle(
-                ctx,
-                graphics::DrawMode::stroke(1.),
-                self.get_bbox(),
-                Color::from_rgb(255, 0, 0),
-            )
-            .unwrap();
-            graphics::draw(ctx, &mesh, graphics::DrawParam::default())
-                .expect("Error while drawing Turn border");
-        }
+        Rect::new(x, y, self.width, self.width)
     }
 }
 
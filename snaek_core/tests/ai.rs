@@ -0,0 +1,136 @@
+use snaek_core::{
+    ai::Pathfinder, Controller, Coords, Difficulty, Direction, GameConfig, GameMode, Obstacle,
+    Rect, Simulation, Spawn,
+};
+
+/// Config of a single snake heading up from the bottom of the screen
+/// towards the given obstacles, with the only food placed on `food`.
+fn config(mode: GameMode, walls: &[(f32, f32, f32, f32)], food: Coords) -> GameConfig {
+    GameConfig {
+        mode,
+        spawn: Some(Spawn {
+            x: 400.,
+            y: 600.,
+            dir: Direction::Up,
+        }),
+        obstacles: walls
+            .iter()
+            .map(|&(x, y, w, h)| Obstacle::Rect(Rect::new(x, y, w, h)))
+            .collect(),
+        food_spawns: vec![food],
+        ..GameConfig::default()
+    }
+}
+
+/// Return where the head of the snake ends.
+fn head(sim: &Simulation) -> Coords {
+    sim.players[0].snake.body.front().unwrap().end()
+}
+
+/// Lets the bot play until it dies or for `ticks` ticks, calling `check`
+/// after every one of them, and return the finished game.
+fn play(
+    config: GameConfig,
+    mut bot: Box<dyn Controller>,
+    ticks: u64,
+    mut check: impl FnMut(&Simulation),
+) -> Simulation {
+    let mut sim = Simulation::new(config, 3);
+    while sim.players[0].alive && sim.tick < ticks {
+        let inputs: Vec<_> = bot
+            .inputs(&sim, 0)
+            .into_iter()
+            .map(|dir| (0, dir))
+            .collect();
+        sim.tick(&inputs);
+        check(&sim);
+    }
+    sim
+}
+
+#[test]
+fn difficulty_parses_from_its_name() {
+    for difficulty in Difficulty::ALL {
+        assert_eq!(difficulty.name().parse(), Ok(difficulty));
+    }
+    assert_eq!(
+        "insane".parse::<Difficulty>(),
+        Err("Unknown difficulty 'insane', expected one of: easy, normal, hard".to_string())
+    );
+}
+
+#[test]
+fn pathfinder_reaches_food_behind_obstacle() {
+    // Wall right across the way up, with the food just behind it
+    let config = config(
+        GameMode::Classic,
+        &[(200., 380., 400., 20.)],
+        Coords::new(400., 350.),
+    );
+
+    let sim = play(config, Box::new(Pathfinder::new(1, false)), 1000, |_| ());
+
+    assert!(sim.food_eaten() > 0);
+}
+
+#[test]
+fn hard_bot_does_not_turn_into_pocket_shorter_than_its_body() {
+    // Pocket three cells deep opening to the left, with the food at its end
+    let walls = [
+        (420., 270., 80., 20.),
+        (420., 310., 80., 20.),
+        (480., 290., 20., 20.),
+    ];
+    let food = Coords::new(460., 300.);
+    let in_pocket = |sim: &Simulation| {
+        let head = head(sim);
+        head.x > 420. && (290. ..=310.).contains(&head.y)
+    };
+
+    let mut entered = false;
+    let sim = play(
+        config(GameMode::Classic, &walls, food),
+        Difficulty::Normal.controller(),
+        2000,
+        |sim| entered |= in_pocket(sim),
+    );
+    assert!(entered);
+    assert!(!sim.players[0].alive);
+
+    let mut entered = false;
+    let sim = play(
+        config(GameMode::Classic, &walls, food),
+        Difficulty::Hard.controller(),
+        2000,
+        |sim| entered |= in_pocket(sim),
+    );
+    assert!(!entered);
+    assert!(sim.players[0].alive);
+}
+
+#[test]
+fn bot_does_not_turn_into_wall_next_to_it() {
+    // Wall along the way up from edge to edge, with the food right behind it
+    let walls = [(420., 0., 20., 800.)];
+    let food = Coords::new(460., 300.);
+    for mode in [GameMode::Classic, GameMode::Wrap] {
+        for difficulty in Difficulty::ALL {
+            let mut last = (Direction::Up, Coords::new(400., 600.));
+            let sim = play(
+                config(mode, &walls, food),
+                difficulty.controller(),
+                400,
+                |sim| {
+                    // Snake was going up or down the wall, less than a cell away from it
+                    let (dir, head_before) = last;
+                    if dir.is_colinear(Direction::Up) && head_before.x > 390. {
+                        let snake = &sim.players[0].snake;
+                        assert_ne!(snake.dir, Direction::Right, "{:?} {}", mode, difficulty);
+                    }
+                    last = (sim.players[0].snake.dir, head(sim));
+                },
+            );
+            assert!(sim.players[0].alive, "{:?} {}", mode, difficulty);
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use snaek_core::{
+    battlesnake::{GridMove, GridPos, MockFailure},
+    controller, Controller, Direction, GameConfig, GridProjection, HttpBot, MockServer, Simulation,
+    Spawn,
+};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+fn config() -> GameConfig {
+    GameConfig {
+        snake_start_len: 4,
+        spawn: Some(Spawn {
+            x: 110.,
+            y: 200.,
+            dir: Direction::Down,
+        }),
+        ..GameConfig::default()
+    }
+}
+
+#[test]
+fn snake_is_projected_onto_grid_from_head_to_tail() {
+    let sim = Simulation::new(config(), 1);
+    let grid = GridProjection::new(&sim.config);
+    let top = grid.height - 1;
+
+    let cells = grid.snake(&sim.players[0].snake);
+
+    let expected: Vec<_> = (6..=9)
+        .rev()
+        .map(|row| GridPos { x: 5, y: top - row })
+        .collect();
+    assert_eq!(cells, expected);
+}
+
+#[test]
+fn bot_is_started_moved_and_ended() {
+    let server = MockServer::always(GridMove::Right).unwrap();
+    let mut controllers: Vec<Box<dyn Controller>> =
+        vec![Box::new(HttpBot::new(&server.url(), TIMEOUT))];
+    let mut sim = Simulation::new(config(), 1);
+
+    for _ in 0..10 {
+        let inputs = controller::collect_inputs(&mut controllers, &sim);
+        sim.tick(&inputs);
+    }
+    controller::game_over(&mut controllers, &sim);
+
+    let paths: Vec<_> = server
+        .requests()
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect();
+    assert_eq!(paths.first().unwrap(), "/start");
+    assert_eq!(paths.last().unwrap(), "/end");
+    assert!(paths.len() > 2);
+    assert!(paths[1..paths.len() - 1].iter().all(|path| path == "/move"));
+    assert_eq!(sim.players[0].snake.dir, Direction::Right);
+}
+
+#[test]
+fn bot_is_asked_again_in_next_cell() {
+    let server = MockServer::always(GridMove::Down).unwrap();
+    let mut bot = HttpBot::new(&server.url(), TIMEOUT);
+    let mut sim = Simulation::new(config(), 1);
+    let cell_ticks = (sim.config.snake_width / sim.config.tick_distance()).ceil() as u64;
+
+    for _ in 0..cell_ticks + 1 {
+        let inputs: Vec<_> = bot
+            .decide(&sim, 0)
+            .map(|dir| (0, dir))
+            .into_iter()
+            .collect();
+        sim.tick(&inputs);
+    }
+
+    let requests = server.requests();
+    let moves: Vec<_> = requests
+        .iter()
+        .filter(|(path, _)| path == "/move")
+        .collect();
+    assert_eq!(moves.len(), 2);
+    assert_eq!(moves[0].1.turn, 0);
+    assert_eq!(moves[1].1.turn, 1);
+    assert_eq!(moves[1].1.you.head.y, moves[0].1.you.head.y - 1);
+}
+
+#[test]
+fn slow_bot_is_disqualified() {
+    let server = MockServer::start(|_| Err(MockFailure::Delay(TIMEOUT * 2, GridMove::Up))).unwrap();
+    let mut controllers: Vec<Box<dyn Controller>> =
+        vec![Box::new(HttpBot::new(&server.url(), TIMEOUT))];
+    let mut sim = Simulation::new(config(), 1);
+
+    let inputs = controller::collect_inputs(&mut controllers, &sim);
+    let disqualified = controller::disqualify(&controllers, &mut sim);
+
+    assert!(inputs.is_empty());
+    assert_eq!(disqualified.len(), 1);
+    assert!(!sim.players[0].alive);
+}
+
+#[test]
+fn malformed_answers_disqualify_bot() {
+    for failure in [MockFailure::Garbage, MockFailure::Status(500)] {
+        let server = MockServer::start(move |_| Err(failure)).unwrap();
+        let mut bot = HttpBot::new(&server.url(), TIMEOUT);
+        let sim = Simulation::new(config(), 1);
+
+        assert_eq!(bot.decide(&sim, 0), None);
+        assert!(bot.disqualified().is_some(), "{:?}", failure);
+    }
+}
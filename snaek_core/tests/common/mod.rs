@@ -0,0 +1,68 @@
+//! Fixtures shared by the tests of the networked games
+#![allow(dead_code)]
+
+use std::{
+    net::SocketAddr,
+    sync::mpsc,
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use snaek_core::{GameConfig, LockstepServer, NetError};
+
+/// How many ticks the networked games are played for
+pub const TICKS: u64 = 300;
+/// Seed of the games started by `server`
+pub const SEED: u64 = 11;
+
+pub fn config(players: u8, bots: u8) -> GameConfig {
+    GameConfig {
+        players,
+        bots,
+        ..GameConfig::default()
+    }
+}
+
+/// Starts a server on loopback, returning its address and the hashes of all ticks it played.
+pub fn server(
+    config: GameConfig,
+    input_delay: u64,
+    timeout: Duration,
+) -> (SocketAddr, JoinHandle<Result<Vec<u64>, NetError>>) {
+    spawn_server(
+        config,
+        SEED,
+        input_delay,
+        move |server| server.with_timeout(timeout),
+        |server| server.hashes().to_vec(),
+    )
+}
+
+/// Starts a server on loopback with the options added by `setup`, returning
+/// its address. Server thread gives what `result` reads from the finished server.
+pub fn spawn_server<S, R, T>(
+    config: GameConfig,
+    seed: u64,
+    input_delay: u64,
+    setup: S,
+    result: R,
+) -> (SocketAddr, JoinHandle<Result<T, NetError>>)
+where
+    S: FnOnce(LockstepServer) -> LockstepServer + Send + 'static,
+    R: FnOnce(&LockstepServer) -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let handle = thread::spawn(move || {
+        let mut server = setup(LockstepServer::bind(
+            "127.0.0.1:0",
+            config,
+            seed,
+            input_delay,
+        )?);
+        sender.send(server.local_addr()?).unwrap();
+        server.run()?;
+        Ok(result(&server))
+    });
+    (receiver.recv().unwrap(), handle)
+}
@@ -0,0 +1,178 @@
+use snaek_core::{
+    env::{ObservationConfig, RewardConfig},
+    Action, Coords, Direction, Env, EnvConfig, GameConfig, GameMode, Obstacle, Rect, Spawn,
+};
+
+fn config() -> EnvConfig {
+    EnvConfig {
+        game: GameConfig {
+            snake_start_len: 4,
+            spawn: Some(Spawn {
+                x: 110.,
+                y: 200.,
+                dir: Direction::Down,
+            }),
+            food_spawns: vec![Coords::new(110., 330.)],
+            ..GameConfig::default()
+        },
+        observation: ObservationConfig {
+            segments: true,
+            ..ObservationConfig::default()
+        },
+        reward: RewardConfig {
+            survival: 0.5,
+            ..RewardConfig::default()
+        },
+        ..EnvConfig::default()
+    }
+}
+
+#[test]
+fn episodes_are_reproduced_by_seed() {
+    let config = EnvConfig {
+        game: GameConfig {
+            bots: 2,
+            ..GameConfig::default()
+        },
+        ..EnvConfig::default()
+    };
+    let mut envs = [Env::new(config.clone()).unwrap(), Env::new(config).unwrap()];
+    let observations: Vec<_> = envs.iter_mut().map(|env| env.reset(9)).collect();
+    assert_eq!(observations[0], observations[1]);
+
+    for step in 0..200 {
+        let action = Action::from_index(step / 20 % 5).unwrap();
+        let steps: Vec<_> = envs.iter_mut().map(|env| env.step(action)).collect();
+        assert_eq!(steps[0], steps[1]);
+    }
+}
+
+#[test]
+fn grid_marks_snake_and_food() {
+    let mut env = Env::new(config()).unwrap();
+    let grid = env.reset(0).grid.unwrap();
+
+    assert_eq!((grid.rows, grid.cols), (40, 40));
+    for row in 6..=9 {
+        assert_eq!(grid.get(0, row, 5), 1.);
+    }
+    assert_eq!(grid.get(0, 10, 5), 0.);
+    assert_eq!(grid.get(1, 9, 5), 1.);
+    assert_eq!(grid.get(1, 6, 5), 0.);
+    assert_eq!(grid.get(3, 16, 5), 1.);
+    assert_eq!(grid.data.iter().sum::<f32>(), 4. + 1. + 1.);
+}
+
+#[test]
+fn rays_measure_distances_from_head() {
+    let mut env = Env::new(config()).unwrap();
+    let observation = env.reset(0);
+    let rays = observation.rays.as_ref().unwrap();
+    let range = 800f32.hypot(800.);
+
+    assert_eq!(rays.len(), 8 * 3);
+    // Straight down to the bottom wall and the food
+    assert!((rays[0] - (810. - 200.) / range).abs() < 1e-4);
+    assert_eq!(rays[1], 1.);
+    assert!((rays[2] - (320. - 200.) / range).abs() < 1e-4);
+    // Back along the body, which is the head itself, to the top wall
+    assert!((rays[4 * 3] - 210. / range).abs() < 1e-4);
+    assert_eq!(rays[4 * 3 + 1], 1.);
+    assert_eq!(observation.features().len(), 5 * 40 * 40 + 8 * 3);
+}
+
+#[test]
+fn food_and_survival_are_rewarded() {
+    let mut env = Env::new(config()).unwrap();
+    env.reset(0);
+    let mut total = 0.;
+
+    let info = loop {
+        let (_, reward, done, info) = env.step(Action::Keep);
+        assert!(!done);
+        total += reward;
+        if info.food_eaten > 0 {
+            break info;
+        }
+    };
+
+    let secs = info.tick as f32 / 120.;
+    assert_eq!(info.score, 1);
+    assert!((total - (1. + 0.5 * secs)).abs() < 1e-4);
+}
+
+#[test]
+fn death_ends_episode() {
+    let mut env = Env::new(config()).unwrap();
+    env.reset(0);
+
+    let (observation, reward, info) = loop {
+        let (observation, reward, done, info) = env.step(Action::Turn(Direction::Left));
+        if done {
+            break (observation, reward, info);
+        }
+    };
+
+    assert!(info.died && !info.won && !info.truncated);
+    assert!(reward < 0.);
+    assert!(!observation.segments.unwrap().snakes[0].alive);
+    let (_, reward, done, after) = env.step(Action::Keep);
+    assert_eq!((reward, done, after.tick), (0., true, info.tick));
+}
+
+#[test]
+fn outliving_the_bots_ends_episode() {
+    let game = GameConfig {
+        players: 1,
+        bots: 1,
+        snake_start_len: 4,
+        ..GameConfig::default()
+    };
+    // Bot starts in a pocket too tight to turn around in
+    let bot = game.snake_spawns()[1];
+    let walls = [
+        (-20., -100., 10., 110.),
+        (10., -100., 10., 110.),
+        (-20., 5., 40., 10.),
+    ];
+    let config = EnvConfig {
+        game: GameConfig {
+            obstacles: walls
+                .iter()
+                .map(|&(x, y, w, h)| Obstacle::Rect(Rect::new(bot.x + x, bot.y + y, w, h)))
+                .collect(),
+            ..game
+        },
+        ..config()
+    };
+    let mut env = Env::new(config).unwrap();
+    env.reset(0);
+
+    let (reward, info) = loop {
+        let (_, reward, done, info) = env.step(Action::Keep);
+        if done {
+            break (reward, info);
+        }
+    };
+
+    assert!(info.won && !info.died && !info.truncated);
+    assert!(info.tick < 50);
+    assert!(reward > 0.);
+}
+
+#[test]
+fn long_episodes_are_truncated() {
+    let mut config = config();
+    config.game.mode = GameMode::Wrap;
+    config.max_ticks = 50;
+    let mut env = Env::new(config).unwrap();
+    env.reset(0);
+
+    let steps = std::iter::repeat_with(|| env.step(Action::Keep))
+        .take_while(|(_, _, done, _)| !done)
+        .count();
+
+    assert_eq!(steps, 50 / 4);
+    assert!(env.is_done());
+    assert!(env.step(Action::Keep).3.truncated);
+}
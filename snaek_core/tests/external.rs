@@ -0,0 +1,115 @@
+#![cfg(unix)]
+
+use std::{env, fs, path::PathBuf, time::Duration};
+
+use snaek_core::{controller, Controller, Direction, ExternalBot, GameConfig, Simulation};
+
+const TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Return the path of a file in the temporary directory, unique to this test run.
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("snaek_external_{}_{}", std::process::id(), name))
+}
+
+/// Starts the shell `script` as a bot.
+fn bot(name: &str, script: &str) -> ExternalBot {
+    let path = temp_path(&format!("{}.sh", name));
+    fs::write(&path, script).unwrap();
+    ExternalBot::spawn(&format!("sh {}", path.display()), TIMEOUT)
+}
+
+/// Plays a few ticks with the snake steered by the `bot`, returning the game
+/// and the players taken out of it, with the reasons.
+fn play(bot: ExternalBot) -> (Simulation, Vec<(usize, String)>) {
+    let mut sim = Simulation::new(GameConfig::default(), 3);
+    let mut controllers: Vec<Box<dyn Controller>> = vec![Box::new(bot)];
+    let mut disqualified = Vec::new();
+    for _ in 0..3 {
+        let inputs = controller::collect_inputs(&mut controllers, &sim);
+        disqualified.extend(controller::disqualify(&controllers, &mut sim));
+        sim.tick(&inputs);
+    }
+    (sim, disqualified)
+}
+
+#[test]
+fn bot_steers_the_snake() {
+    let mut bot = bot("steer", "while read state; do echo '\"Right\"'; done\n");
+    let sim = Simulation::new(GameConfig::default(), 3);
+
+    assert_eq!(bot.decide(&sim, 0), Some(Direction::Right));
+    assert_eq!(bot.decide(&sim, 0), Some(Direction::Right));
+    assert!(bot.disqualified().is_none());
+}
+
+#[test]
+fn slow_bot_is_disqualified() {
+    let bot = bot("slow", "read state\necho null\nread state\nsleep 5\n");
+
+    let (sim, disqualified) = play(bot);
+
+    assert_eq!(disqualified.len(), 1);
+    assert_eq!(disqualified[0].0, 0);
+    assert!(
+        disqualified[0]
+            .1
+            .starts_with("Bot did not answer within 200 ms"),
+        "{}",
+        disqualified[0].1
+    );
+    assert!(!sim.players[0].alive);
+}
+
+#[test]
+fn bot_answering_garbage_is_disqualified() {
+    // Answers with the state it got, which is not a direction
+    let bot = ExternalBot::spawn("cat", TIMEOUT);
+
+    let (sim, disqualified) = play(bot);
+
+    assert_eq!(disqualified.len(), 1);
+    assert!(
+        disqualified[0].1.starts_with("Bot answered '{\"tick\":0"),
+        "{}",
+        disqualified[0].1
+    );
+    assert!(!sim.players[0].alive);
+}
+
+#[test]
+fn bot_that_exits_is_disqualified() {
+    let bot = bot("exit", "read state\necho null\n");
+
+    let (sim, disqualified) = play(bot);
+
+    assert_eq!(disqualified.len(), 1);
+    assert_eq!(disqualified[0].1, "Bot exited");
+    assert!(!sim.players[0].alive);
+}
+
+#[test]
+fn bot_is_told_the_game_is_over() {
+    let marker = temp_path("over");
+    let script = format!(
+        "while read state; do\n\
+         case \"$state\" in *'\"over\":true'*) echo \"$state\" > {}; exit;; esac\n\
+         echo null\n\
+         done\n",
+        marker.display()
+    );
+    let mut controllers: Vec<Box<dyn Controller>> = vec![Box::new(bot("over", &script))];
+    let mut sim = Simulation::new(GameConfig::default(), 3);
+    let inputs = controller::collect_inputs(&mut controllers, &sim);
+    sim.tick(&inputs);
+
+    controller::game_over(&mut controllers, &sim);
+
+    let last = fs::read_to_string(&marker).unwrap();
+    fs::remove_file(&marker).unwrap();
+    assert!(
+        last.starts_with("{\"tick\":1,\"you\":0,\"over\":true"),
+        "{}",
+        last
+    );
+    assert!(controllers[0].disqualified().is_none());
+}
@@ -0,0 +1,103 @@
+use std::{env, fs, path::PathBuf};
+
+use snaek_core::{highscores::MAX_HIGH_SCORES, GameMode, HighScore, HighScores};
+
+/// Return the path of a file in the temporary directory, unique to this test run.
+fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "snaek_highscores_{}_{}.json",
+        std::process::id(),
+        name
+    ))
+}
+
+fn entry(mode: GameMode, score: u32) -> HighScore {
+    HighScore {
+        name: format!("Player {}", score),
+        score,
+        length: 1.,
+        secs: 1.,
+        seed: 0,
+        mode,
+        timestamp: 0,
+    }
+}
+
+fn scores(table: &HighScores, mode: GameMode) -> Vec<u32> {
+    table.for_mode(mode).map(|entry| entry.score).collect()
+}
+
+#[test]
+fn missing_file_gives_empty_table() {
+    let (table, err) = HighScores::load_or_default(temp_path("missing"));
+
+    assert!(table.entries.is_empty());
+    assert!(err.is_none());
+}
+
+#[test]
+fn corrupt_file_is_moved_aside_and_kept() {
+    let path = temp_path("corrupt");
+    let aside = path.with_extension("corrupt");
+    fs::write(&path, "not a table").unwrap();
+
+    let (mut table, err) = HighScores::load_or_default(&path);
+    assert!(table.entries.is_empty());
+    assert!(err
+        .unwrap()
+        .to_string()
+        .starts_with("Could not parse high scores"),);
+    assert!(!path.exists());
+
+    table.insert(entry(GameMode::Classic, 5));
+    table.save(&path).unwrap();
+    let saved = HighScores::load(&path);
+    let kept = fs::read_to_string(&aside);
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&aside).unwrap();
+
+    assert_eq!(saved.unwrap(), table);
+    assert_eq!(kept.unwrap(), "not a table");
+}
+
+#[test]
+fn tables_are_kept_per_mode() {
+    let mut table = HighScores::default();
+    for score in 1..=MAX_HIGH_SCORES as u32 {
+        table.insert(entry(GameMode::Classic, score * 10));
+    }
+
+    assert_eq!(table.insert(entry(GameMode::Wrap, 5)), Some(0));
+    assert_eq!(scores(&table, GameMode::Wrap), vec![5]);
+    assert_eq!(scores(&table, GameMode::Classic).len(), MAX_HIGH_SCORES);
+    assert!(!table.qualifies(GameMode::Classic, 5));
+    assert!(table.qualifies(GameMode::Wrap, 1));
+    assert!(!table.qualifies(GameMode::Wrap, 0));
+}
+
+#[test]
+fn table_is_trimmed_to_the_best_scores() {
+    let mut table = HighScores::default();
+    for score in 1..=MAX_HIGH_SCORES as u32 + 2 {
+        table.insert(entry(GameMode::Classic, score));
+    }
+
+    let kept = scores(&table, GameMode::Classic);
+    assert_eq!(kept.len(), MAX_HIGH_SCORES);
+    assert_eq!(kept.first(), Some(&(MAX_HIGH_SCORES as u32 + 2)));
+    assert_eq!(kept.last(), Some(&3));
+    assert_eq!(table.insert(entry(GameMode::Classic, 2)), None);
+    assert_eq!(scores(&table, GameMode::Classic), kept);
+}
+
+#[test]
+fn insert_returns_the_place_within_the_mode() {
+    let mut table = HighScores::default();
+
+    assert_eq!(table.insert(entry(GameMode::Classic, 30)), Some(0));
+    assert_eq!(table.insert(entry(GameMode::Classic, 10)), Some(1));
+    assert_eq!(table.insert(entry(GameMode::Classic, 20)), Some(1));
+    assert_eq!(table.insert(entry(GameMode::Wrap, 25)), Some(0));
+    assert_eq!(table.insert(entry(GameMode::Classic, 15)), Some(2));
+    assert_eq!(scores(&table, GameMode::Classic), vec![30, 20, 15, 10]);
+}
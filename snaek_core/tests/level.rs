@@ -0,0 +1,62 @@
+use snaek_core::{Direction, Goal, Level};
+
+/// Level text with the `goal` and the `map`, whose rows start at line 5.
+fn level(goal: &str, map: &str) -> String {
+    format!("snaek_level 1\nname Test\ngoal {}\nmap\n{}", goal, map)
+}
+
+/// Parses the level, expecting it to fail, and return the error message.
+fn error(text: &str) -> String {
+    match Level::parse(text) {
+        Ok(_) => panic!("level parsed:\n{}", text),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[test]
+fn valid_level_parses() {
+    let level = Level::parse(&level("score 5", "#####\n#.>*#\n#####\n")).unwrap();
+
+    assert_eq!(level.name, "Test");
+    assert_eq!(level.goal, Goal::Score(5));
+    assert_eq!(level.size, (5, 3));
+    assert_eq!(level.spawn, (2, 1, Direction::Right));
+    assert_eq!(level.food, vec![(3, 1)]);
+}
+
+#[test]
+fn map_without_head_is_refused() {
+    assert_eq!(
+        error(&level("endless", "#####\n#...#\n#####\n")),
+        "Malformed level at line 4: missing snake head on the map"
+    );
+}
+
+#[test]
+fn map_with_two_heads_is_refused() {
+    assert_eq!(
+        error(&level("endless", "#####\n#.v.#\n#^..#\n#####\n")),
+        "Malformed level at line 7: map has more than one snake head"
+    );
+}
+
+#[test]
+fn unknown_map_cell_is_refused() {
+    assert_eq!(
+        error(&level("endless", "#####\n#.v.#\n#.x.#\n#####\n")),
+        "Malformed level at line 7: unknown map cell 'x'"
+    );
+}
+
+#[test]
+fn bad_goal_is_refused() {
+    assert_eq!(
+        error(&level("score many", "#####\n#.v.#\n#####\n")),
+        "Malformed level at line 3: goal value 'many' is not a number"
+    );
+    assert_eq!(
+        error(&level("win", "#####\n#.v.#\n#####\n")),
+        "Malformed level at line 3: unknown goal 'win', expected one of: \
+         endless, score N, length N, survive SECS, food"
+    );
+}
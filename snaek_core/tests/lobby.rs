@@ -0,0 +1,297 @@
+mod common;
+
+use std::{
+    net::SocketAddr,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use snaek_core::{
+    lobby::{LobbyClient, StartedGame},
+    lockstep::DISCONNECT_TIMEOUT,
+    net, Discovery, GameConfig, GameMode, Level, Lobby, NetError, Obstacle, Rect,
+};
+
+use common::{config, spawn_server};
+
+/// Seed of the games started in the lobby
+const SEED: u64 = 5;
+const LEVEL: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../resources/levels/01_walled_garden.lvl"
+);
+
+/// Server thread, giving the hash after the last tick and the number of players in the roster
+type Server = JoinHandle<Result<(u64, usize), NetError>>;
+
+/// Starts a server with a lobby for up to `slots` players on loopback, returning
+/// its address.
+fn server(slots: u8, announce: Option<SocketAddr>) -> (SocketAddr, Server) {
+    let config = config(slots, 1);
+    let level = Level::load(LEVEL).unwrap();
+    let mut lobby = Lobby::new("Office").with_level(&level.name, level.config(&config).unwrap());
+    if let Some(addr) = announce {
+        lobby = lobby.announce_to(addr);
+    }
+    spawn_server(
+        config,
+        SEED,
+        2,
+        |server| {
+            server
+                .with_timeout(Duration::from_secs(1))
+                .with_lobby(lobby)
+        },
+        |server| (net::state_hash(server.sim()), server.roster().len()),
+    )
+}
+
+fn join(addr: SocketAddr) -> LobbyClient {
+    LobbyClient::join(addr, DISCONNECT_TIMEOUT).unwrap()
+}
+
+/// Polls all `clients` until `done` or panics after a while. Return the
+/// games of the clients, once they have all started.
+fn poll_until<F>(clients: &mut [&mut LobbyClient], done: F) -> Vec<StartedGame>
+where
+    F: Fn(&[&mut LobbyClient]) -> bool,
+{
+    let start = Instant::now();
+    let mut games: Vec<Option<StartedGame>> = clients.iter().map(|_| None).collect();
+    loop {
+        for (client, game) in clients.iter_mut().zip(&mut games) {
+            if game.is_none() {
+                *game = client.poll().unwrap();
+            }
+        }
+        if games.iter().all(Option::is_some) {
+            return games.into_iter().flatten().collect();
+        }
+        if done(clients) {
+            return Vec::new();
+        }
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+/// Plays the started games with nobody steering until they are over,
+/// returning the hash after the last tick of each.
+fn play(games: Vec<StartedGame>) -> Vec<u64> {
+    let mut games: Vec<_> = games.into_iter().map(StartedGame::into_lockstep).collect();
+    let start = Instant::now();
+    while !games.iter().all(|(_, sim)| sim.is_over()) {
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        for (client, sim) in games.iter_mut().filter(|(_, sim)| !sim.is_over()) {
+            client.step(sim).unwrap();
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    games.iter().map(|(_, sim)| net::state_hash(sim)).collect()
+}
+
+#[test]
+fn lobby_is_discovered_on_loopback() {
+    let mut discovery = Discovery::bind("127.0.0.1:0").unwrap();
+    let (addr, _server) = server(4, Some(discovery.local_addr().unwrap()));
+    let mut client = join(addr);
+
+    let start = Instant::now();
+    while !discovery
+        .games()
+        .iter()
+        .any(|game| game.advert.players == 1)
+    {
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        client.poll().unwrap();
+        discovery.poll().unwrap();
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    let game = &discovery.games()[0];
+    assert_eq!(game.addr, addr);
+    assert_eq!(game.advert.name, "Office");
+    assert_eq!(game.advert.slots, 4);
+    assert_eq!(game.advert.mode, GameMode::Classic);
+}
+
+#[test]
+fn host_sets_up_and_starts_the_game() {
+    let (addr, server) = server(4, None);
+    let mut host = join(addr);
+    let mut guest = join(addr);
+    poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[1].lobby().members.len() == 2
+    });
+    assert!(host.is_host());
+    assert!(!guest.is_host());
+    assert_eq!(host.lobby().levels, ["Walled garden"]);
+
+    host.set_name("Alice");
+    host.set_color(2);
+    host.set_ready(true);
+    host.choose(GameMode::Wrap, Some(0));
+    guest.set_name("  Bob\n");
+    guest.set_ready(true);
+    poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[1].lobby().all_ready() && clients[1].lobby().mode == GameMode::Wrap
+    });
+    assert_eq!(guest.lobby().members[0].name, "Alice");
+    assert_eq!(guest.lobby().members[0].color, 2);
+    assert_eq!(host.lobby().members[1].name, "Bob");
+    assert_eq!(host.lobby().level, Some(0));
+
+    host.start();
+    let games = poll_until(&mut [&mut host, &mut guest], |_| false);
+    let names: Vec<_> = games[1]
+        .roster()
+        .iter()
+        .map(|member| &member.name)
+        .collect();
+    assert_eq!(names, ["Alice", "Bob"]);
+    assert_eq!(games[0].player(), 0);
+    assert_eq!(games[1].player(), 1);
+    let config = &games[0].sim().config;
+    assert_eq!(config.mode, GameMode::Wrap);
+    assert_eq!((config.players, config.bots), (2, 1));
+    assert!(!config.obstacles.is_empty());
+
+    let hashes = play(games);
+    let (hash, roster) = server.join().unwrap().unwrap();
+    assert_eq!(hashes, [hash, hash]);
+    assert_eq!(roster, 2);
+}
+
+#[test]
+fn game_starts_once_everybody_is_ready() {
+    let (addr, server) = server(2, None);
+    let mut host = join(addr);
+    let mut guest = join(addr);
+    host.set_ready(true);
+    host.start();
+    // Only the host can start the game
+    guest.start();
+    let games = poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[0].lobby().members.len() == 2 && clients[0].lobby().members[0].ready
+    });
+    assert!(games.is_empty());
+    thread::sleep(Duration::from_millis(300));
+    assert!(host.poll().unwrap().is_none());
+
+    guest.set_ready(true);
+    let games = poll_until(&mut [&mut host, &mut guest], |_| false);
+    assert_eq!(games.len(), 2);
+    play(games);
+    server.join().unwrap().unwrap();
+}
+
+#[test]
+fn full_lobby_turns_players_away() {
+    let (addr, _server) = server(1, None);
+    let _host = join(addr);
+
+    let result = LobbyClient::join(addr, Duration::from_secs(1));
+
+    assert!(matches!(result, Err(NetError::Rejected(_))));
+}
+
+#[test]
+fn next_player_hosts_once_the_host_leaves() {
+    let (addr, _server) = server(3, None);
+    let host = join(addr);
+    let mut guest = join(addr);
+    poll_until(&mut [&mut guest], |clients| {
+        clients[0].lobby().members.len() == 2
+    });
+    assert!(!guest.is_host());
+
+    drop(host);
+    poll_until(&mut [&mut guest], |clients| {
+        clients[0].lobby().members.len() == 1
+    });
+    assert!(guest.is_host());
+    assert_eq!(guest.member().name, "Player 2");
+}
+
+#[test]
+fn player_cannot_take_the_colour_of_another() {
+    let (addr, _server) = server(2, None);
+    let mut host = join(addr);
+    let mut guest = join(addr);
+    poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[1].lobby().members.len() == 2
+    });
+    let colors = |client: &LobbyClient| -> Vec<u8> {
+        client
+            .lobby()
+            .members
+            .iter()
+            .map(|member| member.color)
+            .collect()
+    };
+    assert_eq!(colors(&guest), vec![0, 1]);
+
+    guest.set_name("Guest");
+    guest.set_color(0);
+    poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[1].lobby().problem.is_some()
+    });
+
+    assert_eq!(
+        guest.lobby().problem.as_deref(),
+        Some("Guest can't take the colour of Player 1")
+    );
+    assert_eq!(colors(&guest), vec![0, 1]);
+    poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[1].lobby().members[1].name == "Guest"
+    });
+    assert_eq!(guest.member().color, 1);
+}
+
+#[test]
+fn game_does_not_start_with_snakes_spawning_in_a_wall() {
+    let config = config(3, 1);
+    // Clear of the four snakes the level is checked for, but in the way
+    // of the middle one of three
+    let wall = Obstacle::Rect(Rect::new(190., 350., 20., 100.));
+    let level = GameConfig {
+        obstacles: vec![wall],
+        ..config.clone()
+    };
+    level.validate().unwrap();
+    let lobby = Lobby::new("Office").with_level("Pillar", level);
+    let (addr, server) = spawn_server(
+        config,
+        SEED,
+        2,
+        |server| {
+            server
+                .with_timeout(Duration::from_secs(1))
+                .with_lobby(lobby)
+        },
+        |server| server.sim().config.snake_count(),
+    );
+    let mut host = join(addr);
+    let mut guest = join(addr);
+    host.set_ready(true);
+    host.choose(GameMode::Classic, Some(0));
+    guest.set_ready(true);
+    poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[0].lobby().all_ready() && clients[0].lobby().members.len() == 2
+    });
+
+    host.start();
+    let games = poll_until(&mut [&mut host, &mut guest], |clients| {
+        clients[1].lobby().problem.is_some()
+    });
+    assert!(games.is_empty());
+    let problem = guest.lobby().problem.clone().unwrap();
+    assert!(problem.starts_with("The game can't start:"), "{}", problem);
+
+    // With the guest gone the snakes spawn clear of the wall
+    drop(guest);
+    let games = poll_until(&mut [&mut host], |_| false);
+    assert_eq!(games[0].sim().config.snake_count(), 2);
+    play(games);
+    assert_eq!(server.join().unwrap().unwrap(), 2);
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use std::{
+    net::{SocketAddr, UdpSocket},
+    thread,
+    time::{Duration, Instant},
+};
+
+use snaek_core::{
+    conditioner::{LinkConditioner, NetConditions},
+    lockstep::DISCONNECT_TIMEOUT,
+    net::{self, Message, PROTOCOL_VERSION},
+    Coords, Difficulty, Direction, LockstepClient, NetError, Simulation,
+};
+
+use common::{config, server, TICKS};
+
+/// Plays up to `TICKS` ticks with a bot steering the client's snake,
+/// returning the hash after each of them.
+fn play(addr: SocketAddr) -> Result<Vec<u64>, NetError> {
+    let (mut client, mut sim) = LockstepClient::connect(addr, DISCONNECT_TIMEOUT)?;
+    let mut bot = Difficulty::Easy.controller();
+    let mut hashes = Vec::new();
+
+    while sim.tick < TICKS && !sim.is_over() {
+        for dir in bot.inputs(&sim, client.player()) {
+            client.push_input(dir);
+        }
+        client.wait_step(&mut sim)?;
+        hashes.push(net::state_hash(&sim));
+    }
+    Ok(hashes)
+}
+
+#[test]
+fn clients_stay_in_sync_on_loopback() {
+    let (addr, server) = server(config(2, 1), 3, DISCONNECT_TIMEOUT);
+
+    let clients: Vec<_> = (0..2).map(|_| thread::spawn(move || play(addr))).collect();
+    let hashes: Vec<_> = clients
+        .into_iter()
+        .map(|client| client.join().unwrap().unwrap())
+        .collect();
+    let server_hashes = server.join().unwrap().unwrap();
+
+    assert_eq!(hashes[0], hashes[1]);
+    assert!(!hashes[0].is_empty());
+    assert_eq!(hashes[0][..], server_hashes[..hashes[0].len()]);
+}
+
+#[test]
+fn inputs_are_delayed() {
+    let (addr, _server) = server(config(1, 0), 10, DISCONNECT_TIMEOUT);
+    let (mut client, mut sim) = LockstepClient::connect(addr, DISCONNECT_TIMEOUT).unwrap();
+    assert_eq!(client.input_delay(), 10);
+
+    client.push_input(Direction::Right);
+    for _ in 0..10 {
+        client.wait_step(&mut sim).unwrap();
+        assert!(sim.players[0].inputs.is_empty());
+    }
+    client.wait_step(&mut sim).unwrap();
+    assert!(sim.players[0].snake.dir == Direction::Right || !sim.players[0].inputs.is_empty());
+}
+
+#[test]
+fn silent_player_is_dropped() {
+    let (addr, server) = server(config(2, 0), 2, Duration::from_millis(300));
+    // Second player joins by hand and never says a word again
+    let silent = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let join = Message::Join {
+        version: PROTOCOL_VERSION,
+    };
+    let client = thread::spawn(move || {
+        let (mut client, mut sim) = LockstepClient::connect(addr, DISCONNECT_TIMEOUT)?;
+        while !sim.is_over() {
+            client.wait_step(&mut sim)?;
+        }
+        Ok::<_, NetError>(sim.players[1 - client.player()].alive)
+    });
+    join.send(&silent, Some(addr)).unwrap();
+
+    assert!(!client.join().unwrap().unwrap());
+    assert!(server.join().unwrap().is_ok());
+}
+
+#[test]
+fn desync_is_detected() {
+    let (addr, server) = server(config(1, 0), 2, DISCONNECT_TIMEOUT);
+    let (mut client, mut sim) = LockstepClient::connect(addr, DISCONNECT_TIMEOUT).unwrap();
+
+    for _ in 0..5 {
+        client.wait_step(&mut sim).unwrap();
+    }
+    sim.players[0]
+        .snake
+        .body
+        .front_mut()
+        .unwrap()
+        .translate(Coords::new(1., 0.));
+    let err = (0..100)
+        .map(|_| client.wait_step(&mut sim))
+        .find_map(Result::err)
+        .unwrap();
+
+    assert!(
+        matches!(err, NetError::Desync { tick: 5, player: 0 }),
+        "{}",
+        err
+    );
+    assert!(matches!(
+        server.join().unwrap(),
+        Err(NetError::Desync { tick: 5, .. })
+    ));
+}
+
+#[test]
+fn desync_is_detected_when_datagrams_get_lost() {
+    let conditions = NetConditions {
+        loss: 0.5,
+        ..NetConditions::default()
+    };
+    for seed in 0..6 {
+        let (addr, server) = server(config(1, 0), 2, DISCONNECT_TIMEOUT);
+        let conditioner = LinkConditioner::spawn(addr, conditions, seed).unwrap();
+        let (mut client, mut sim) =
+            LockstepClient::connect(conditioner.local_addr(), DISCONNECT_TIMEOUT).unwrap();
+
+        for _ in 0..5 {
+            client.wait_step(&mut sim).unwrap();
+        }
+        sim.players[0]
+            .snake
+            .body
+            .front_mut()
+            .unwrap()
+            .translate(Coords::new(1., 0.));
+        // Hash of the tick that went out of sync is sent until the server
+        // checks it, rather than just once
+        let start = Instant::now();
+        while !server.is_finished() && client.step(&mut sim).is_ok() {
+            assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        let result = server.join().unwrap();
+        assert!(
+            matches!(result, Err(NetError::Desync { tick: 5, .. })),
+            "{:?}",
+            result
+        );
+    }
+}
+
+#[test]
+fn full_game_rejects_new_players() {
+    let (addr, _server) = server(config(1, 0), 2, DISCONNECT_TIMEOUT);
+    let (_client, _sim): (_, Simulation) =
+        LockstepClient::connect(addr, DISCONNECT_TIMEOUT).unwrap();
+
+    let result = LockstepClient::connect(addr, DISCONNECT_TIMEOUT);
+
+    assert!(matches!(result, Err(NetError::Rejected(_))));
+}
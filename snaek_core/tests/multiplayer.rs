@@ -0,0 +1,106 @@
+use snaek_core::{
+    controller, Controller, Coords, Direction, GameConfig, Scripted, Simulation, StepEvent,
+};
+
+/// Arena for two snakes, starting next to each other heading down,
+/// long enough to be in each other's way when they turn.
+fn config() -> GameConfig {
+    GameConfig {
+        players: 2,
+        screen_width: 400.,
+        snake_start_len: 8,
+        food_spawns: vec![Coords::new(30., 30.); 4],
+        ..GameConfig::default()
+    }
+}
+
+/// Plays the game with snakes turning as scripted, until it is over.
+/// Return the tick on which it ended.
+fn play(sim: &mut Simulation, scripts: Vec<Vec<(u64, Direction)>>) -> u64 {
+    let mut controllers: Vec<Box<dyn Controller>> = scripts
+        .into_iter()
+        .map(|moves| Box::new(Scripted::new(moves)) as Box<dyn Controller>)
+        .collect();
+    loop {
+        let inputs = controller::collect_inputs(&mut controllers, sim);
+        let event = sim.tick(&inputs);
+        assert_eq!(event == StepEvent::Died, sim.is_over());
+        if sim.is_over() {
+            return sim.tick;
+        }
+        assert!(sim.tick < 1000, "timed out");
+    }
+}
+
+#[test]
+fn snakes_meeting_head_on_both_die() {
+    let mut sim = Simulation::new(config(), 1);
+
+    play(
+        &mut sim,
+        vec![vec![(0, Direction::Right)], vec![(0, Direction::Left)]],
+    );
+
+    assert!(sim.players.iter().all(|player| !player.alive));
+    assert_eq!(sim.winner(), None);
+    // Each of them got about halfway to the other one
+    let heads: Vec<_> = sim
+        .players
+        .iter()
+        .map(|player| player.snake.body.front().unwrap().end().x)
+        .collect();
+    assert!(
+        heads.iter().all(|head| (head - 200.).abs() < 5.),
+        "{:?}",
+        heads
+    );
+}
+
+#[test]
+fn head_running_into_a_body_kills_its_snake_only() {
+    let mut sim = Simulation::new(config(), 1);
+
+    play(&mut sim, vec![vec![], vec![(0, Direction::Left)]]);
+
+    assert!(sim.players[0].alive);
+    assert!(!sim.players[1].alive);
+    assert_eq!(sim.winner(), Some(0));
+    let head = sim.players[1].snake.body.front().unwrap().end();
+    assert!(head.x > sim.players[0].snake.body.front().unwrap().end().x);
+}
+
+#[test]
+fn round_is_over_once_at_most_one_snake_is_alive() {
+    let config = GameConfig {
+        players: 3,
+        ..config()
+    };
+    let mut sim = Simulation::new(config, 1);
+    assert!(!sim.is_over());
+
+    sim.disqualify(1);
+    assert_eq!(sim.tick(&[]), StepEvent::Moved);
+    assert!(!sim.is_over());
+    assert_eq!(sim.winner(), None);
+
+    sim.disqualify(0);
+    assert_eq!(sim.tick(&[]), StepEvent::Died);
+    assert!(sim.is_over());
+    assert_eq!(sim.winner(), Some(2));
+}
+
+#[test]
+fn single_snake_plays_until_it_dies() {
+    let config = GameConfig {
+        players: 1,
+        ..config()
+    };
+    let mut sim = Simulation::new(config, 1);
+
+    let tick = play(&mut sim, vec![vec![]]);
+
+    // Straight down into the wall
+    assert!(tick > 1);
+    assert!(!sim.players[0].alive);
+    assert_eq!(sim.winner(), None);
+}
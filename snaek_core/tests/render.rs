@@ -0,0 +1,75 @@
+use snaek_core::{
+    render::{self, turn_contains},
+    Coords, Direction, Food, GameConfig, GameMode, Rect, Simulation, Turn,
+};
+
+const BACKGROUND: [u8; 3] = [0, 0, 0];
+const FOOD: [u8; 3] = [255, 0, 0];
+
+/// Turn from heading right to heading down, starting at the origin, so its
+/// quarter ring lies around (0, 10), right of it and above it.
+fn turn(percentage: f32, is_growing: bool) -> Turn {
+    Turn {
+        percentage,
+        is_growing,
+        ..Turn::new(Coords::new(0., 0.), Direction::Right, Direction::Down, 20.)
+    }
+}
+
+#[test]
+fn growing_turn_covers_the_start_of_its_ring() {
+    let turn = turn(0.5, true);
+
+    assert!(turn_contains(&turn, Coords::new(2., 5.)));
+    assert!(!turn_contains(&turn, Coords::new(15., 8.)));
+}
+
+#[test]
+fn shrinking_turn_covers_the_end_of_its_ring() {
+    let turn = turn(0.5, false);
+
+    assert!(!turn_contains(&turn, Coords::new(2., 5.)));
+    assert!(turn_contains(&turn, Coords::new(15., 8.)));
+}
+
+#[test]
+fn turn_covers_its_quarter_ring_only() {
+    let turn = turn(1., false);
+
+    assert!(turn_contains(&turn, Coords::new(10., 0.)));
+    // Beyond the outer edge of the ring, behind it and past its end
+    assert!(!turn_contains(&turn, Coords::new(15., -10.)));
+    assert!(!turn_contains(&turn, Coords::new(-2., 5.)));
+    assert!(!turn_contains(&turn, Coords::new(10., 12.)));
+}
+
+/// Renders the arena with the food sticking out of its right edge.
+fn render_food_on_the_edge(mode: GameMode) -> render::Image {
+    let config = GameConfig {
+        mode,
+        ..GameConfig::default()
+    };
+    let mut sim = Simulation::new(config, 1);
+    let width = sim.config.screen_width;
+    sim.food = Some(Food {
+        bbox: Rect::new(width - 5., 100., 10., 10.),
+    });
+    render::render(&sim, 1.)
+}
+
+#[test]
+fn shapes_wrap_around_the_edges() {
+    let image = render_food_on_the_edge(GameMode::Wrap);
+
+    assert_eq!(image.pixel(image.width - 2, 105), FOOD);
+    assert_eq!(image.pixel(2, 105), FOOD);
+    assert_eq!(image.pixel(7, 105), BACKGROUND);
+}
+
+#[test]
+fn shapes_are_cut_off_by_the_edges_of_the_walled_arena() {
+    let image = render_food_on_the_edge(GameMode::Classic);
+
+    assert_eq!(image.pixel(image.width - 2, 105), FOOD);
+    assert_eq!(image.pixel(2, 105), BACKGROUND);
+}
@@ -0,0 +1,67 @@
+use std::{env, fs};
+
+use snaek_core::{
+    ai::{self, Greedy},
+    controller::{self, Controller},
+    net, GameConfig, Replay, ReplayError, Scripted, Simulation, StepEvent,
+};
+
+/// Plays a game of a bot chasing the food against the computer, with a
+/// third snake disqualified on the way, recording it as it goes.
+fn record_game() -> (Replay, Simulation) {
+    let config = GameConfig {
+        players: 2,
+        bots: 1,
+        ..GameConfig::default()
+    };
+    let mut controllers: Vec<Box<dyn Controller>> = vec![
+        Box::new(Greedy::new(1)),
+        Box::new(Scripted::default().disqualify_at(100)),
+    ];
+    controllers.extend(ai::bots(&config));
+    let mut sim = Simulation::new(config, 21);
+    let mut replay = Replay::new(&sim);
+
+    while sim.tick < 3000 {
+        let inputs = controller::collect_inputs(&mut controllers, &sim);
+        for (player, _) in controller::disqualify(&controllers, &mut sim) {
+            replay.disqualify(sim.tick, player);
+        }
+        replay.record(sim.tick, &inputs);
+        if sim.tick(&inputs) == StepEvent::Died {
+            break;
+        }
+    }
+    replay.finish(sim.tick, sim.players[0].score);
+    (replay, sim)
+}
+
+#[test]
+fn loaded_replay_reproduces_the_recorded_game() {
+    let (replay, recorded) = record_game();
+    assert!(!replay.inputs.is_empty());
+    assert_eq!(replay.disqualified, [(100, 1)]);
+    assert!(recorded.players[0].score > 0);
+    let path = env::temp_dir().join(format!("snaek_replay_{}.txt", std::process::id()));
+
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path);
+    fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap();
+    let sim = loaded.play_headless();
+
+    assert_eq!(loaded, replay);
+    let end = loaded.end.unwrap();
+    assert_eq!(sim.tick, end.tick);
+    assert_eq!(sim.players[0].score, end.score);
+    assert_eq!(net::state_hash(&sim), net::state_hash(&recorded));
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let replay = "snaek_replay 2\nseed 1\ninput 15 0 Left\n";
+
+    let result = Replay::read_from(replay.as_bytes());
+
+    assert!(matches!(result, Err(ReplayError::UnsupportedVersion(2))));
+}
@@ -0,0 +1,143 @@
+mod common;
+
+use std::{
+    net::UdpSocket,
+    thread,
+    time::{Duration, Instant},
+};
+
+use snaek_core::{
+    conditioner::{LinkConditioner, NetConditions},
+    lockstep::DISCONNECT_TIMEOUT,
+    net, Difficulty, Direction, LockstepClient, NetError, RollbackClient, Simulation, StepEvent,
+};
+
+use common::{config, server, TICKS};
+
+const SERVER_TIMEOUT: Duration = Duration::from_secs(1);
+
+fn bad_network() -> NetConditions {
+    NetConditions {
+        latency: Duration::from_millis(20),
+        jitter: Duration::from_millis(20),
+        loss: 0.1,
+    }
+}
+
+#[test]
+fn snapshot_restores_the_whole_game() {
+    let mut sim = Simulation::new(config(2, 0), 3);
+    for _ in 0..40 {
+        sim.tick(&[]);
+    }
+    let snapshot = sim.snapshot();
+    let hash = net::state_hash(&sim);
+
+    let inputs = [(0, Direction::Down), (1, Direction::Up)];
+    for tick in 0..60 {
+        sim.tick(if tick == 5 { &inputs } else { &[] });
+    }
+    let played = net::state_hash(&sim);
+    sim.restore(&snapshot);
+    assert_eq!(net::state_hash(&sim), hash);
+
+    for tick in 0..60 {
+        sim.tick(if tick == 5 { &inputs } else { &[] });
+    }
+    assert_eq!(net::state_hash(&sim), played);
+}
+
+#[test]
+fn rollback_client_does_not_wait_for_the_server() {
+    let (addr, _server) = server(config(1, 0), 2, SERVER_TIMEOUT);
+    let conditioner = LinkConditioner::spawn(
+        addr,
+        NetConditions {
+            latency: Duration::from_millis(100),
+            ..NetConditions::default()
+        },
+        1,
+    )
+    .unwrap();
+    let (client, mut sim) =
+        RollbackClient::connect(conditioner.local_addr(), DISCONNECT_TIMEOUT).unwrap();
+    let mut client = client.with_max_prediction(10);
+
+    for _ in 0..20 {
+        client.step(&mut sim).unwrap();
+    }
+
+    assert_eq!(sim.tick, 10);
+    assert_eq!(client.confirmed(&sim), 0);
+}
+
+#[test]
+fn rollback_and_lockstep_clients_stay_in_sync_on_a_bad_network() {
+    let (addr, server) = server(config(2, 1), 6, SERVER_TIMEOUT);
+    let conditioner = LinkConditioner::spawn(addr, bad_network(), 2).unwrap();
+    let proxy = conditioner.local_addr();
+
+    let lockstep = thread::spawn(move || {
+        let (mut client, mut sim) = LockstepClient::connect(proxy, DISCONNECT_TIMEOUT)?;
+        let mut bot = Difficulty::Easy.controller();
+        while sim.tick < TICKS && !sim.is_over() {
+            for dir in bot.inputs(&sim, client.player()) {
+                client.push_input(dir);
+            }
+            client.wait_step(&mut sim)?;
+        }
+        Ok::<_, NetError>(())
+    });
+    let rollback = thread::spawn(move || {
+        let (mut client, mut sim) = RollbackClient::connect(proxy, DISCONNECT_TIMEOUT)?;
+        let mut bot = Difficulty::Easy.controller();
+        while client.confirmed(&sim) < TICKS {
+            for dir in bot.inputs(&sim, client.player()) {
+                client.push_input(dir);
+            }
+            if client.step(&mut sim)? == Some(StepEvent::Died) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        Ok::<_, NetError>(client.rollbacks())
+    });
+
+    lockstep.join().unwrap().unwrap();
+    let rollbacks = rollback.join().unwrap().unwrap();
+    server.join().unwrap().unwrap();
+    assert!(rollbacks > 0);
+}
+
+#[test]
+fn conditioner_delays_datagrams() {
+    let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let conditions = NetConditions {
+        latency: Duration::from_millis(50),
+        ..NetConditions::default()
+    };
+    let conditioner = LinkConditioner::spawn(server.local_addr().unwrap(), conditions, 3).unwrap();
+    let client = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    let start = Instant::now();
+    client.send_to(b"ping", conditioner.local_addr()).unwrap();
+    let mut buf = [0; 4];
+    let (len, _) = server.recv_from(&mut buf).unwrap();
+
+    assert_eq!(&buf[..len], b"ping");
+    assert!(start.elapsed() >= conditions.latency);
+}
+
+#[test]
+fn conditioner_loses_datagrams() {
+    let (addr, _server) = server(config(1, 0), 2, SERVER_TIMEOUT);
+    let conditions = NetConditions {
+        loss: 1.,
+        ..NetConditions::default()
+    };
+    let conditioner = LinkConditioner::spawn(addr, conditions, 4).unwrap();
+
+    let result = RollbackClient::connect(conditioner.local_addr(), Duration::from_millis(300));
+
+    assert!(matches!(result, Err(NetError::Timeout)));
+}
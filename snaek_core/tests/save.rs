@@ -0,0 +1,200 @@
+use snaek_core::{
+    ai::Greedy,
+    controller::{self, Controller},
+    lobby::Member,
+    net, CampaignProgress, Direction, GameConfig, GameMode, Level, Replay, SaveError, SavedGame,
+    SegmentKind, Simulation,
+};
+
+const LEVEL: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../resources/levels/01_walled_garden.lvl"
+);
+
+fn config(mode: GameMode) -> GameConfig {
+    GameConfig {
+        mode,
+        players: 2,
+        ..GameConfig::default()
+    }
+}
+
+/// Bots chasing the food, so the food keeps being eaten and placed anew.
+fn bots() -> Vec<Box<dyn Controller>> {
+    (0..2)
+        .map(|_| Box::new(Greedy::new(1)) as Box<dyn Controller>)
+        .collect()
+}
+
+/// Plays the game for a while, leaving it in the middle of a turn
+/// with inputs still waiting to be processed.
+fn game_in_progress(config: GameConfig) -> Simulation {
+    let mut sim = Simulation::new(config, 11);
+    let mut bots = bots();
+    while sim.food_eaten() < 2 || sim.players[0].snake.body.len() < 3 {
+        let inputs = controller::collect_inputs(&mut bots, &sim);
+        sim.tick(&inputs);
+        assert!(!sim.is_over() && sim.tick < 10_000);
+    }
+    sim.push_input(0, Direction::Left);
+    sim.push_input(0, Direction::Right);
+    assert!(sim.players.iter().any(|player| player
+        .snake
+        .body
+        .iter()
+        .any(|segment| matches!(segment.kind(), SegmentKind::Turn(_)))));
+    sim
+}
+
+/// Plays both games on with the same inputs, checking they stay identical.
+fn assert_same_continuation(mut sim: Simulation, mut restored: Simulation) {
+    assert_eq!(net::state_hash(&restored), net::state_hash(&sim));
+    let mut bots = bots();
+    let eaten = sim.food_eaten();
+    for _ in 0..1000 {
+        let inputs = controller::collect_inputs(&mut bots, &sim);
+        sim.tick(&inputs);
+        restored.tick(&inputs);
+        assert_eq!(net::state_hash(&restored), net::state_hash(&sim));
+    }
+    assert!(sim.food_eaten() > eaten);
+    assert_eq!(restored.food_eaten(), sim.food_eaten());
+    assert_eq!(restored.to_json(), sim.to_json());
+}
+
+#[test]
+fn json_round_trip_continues_identically() {
+    let sim = game_in_progress(config(GameMode::Wrap));
+
+    let restored = Simulation::from_json(&sim.to_json()).unwrap();
+
+    assert_eq!(restored.config, sim.config);
+    assert_eq!(restored.seed, sim.seed);
+    assert_eq!(restored.players[0].inputs, sim.players[0].inputs);
+    assert_same_continuation(sim, restored);
+}
+
+#[test]
+fn binary_round_trip_continues_identically() {
+    let level = Level::load(LEVEL).unwrap();
+    let config = level.config(&config(GameMode::Classic)).unwrap();
+    let sim = game_in_progress(config);
+
+    let restored = Simulation::from_bytes(&sim.to_bytes()).unwrap();
+
+    assert_eq!(restored.config, sim.config);
+    assert!(!restored.config.obstacles.is_empty());
+    assert_same_continuation(sim, restored);
+}
+
+#[test]
+fn serialized_state_is_stable() {
+    let sim = game_in_progress(config(GameMode::Wrap));
+    let json = sim.to_json();
+    let bytes = sim.to_bytes();
+
+    assert_eq!(Simulation::from_json(&json).unwrap().to_json(), json);
+    assert_eq!(Simulation::from_bytes(&bytes).unwrap().to_bytes(), bytes);
+    assert!(bytes.len() < json.len() / 3);
+}
+
+#[test]
+fn other_versions_are_refused() {
+    let sim = game_in_progress(config(GameMode::Classic));
+    let mut json: serde_json::Value = serde_json::from_str(&sim.to_json()).unwrap();
+    json["version"] = 2.into();
+    let mut bytes = sim.to_bytes();
+    bytes[b"snaek_save".len()] = 2;
+
+    let from_json = Simulation::from_json(&json.to_string());
+    let from_bytes = Simulation::from_bytes(&bytes);
+
+    assert!(matches!(from_json, Err(SaveError::UnsupportedVersion(2))));
+    assert!(matches!(from_bytes, Err(SaveError::UnsupportedVersion(2))));
+}
+
+#[test]
+fn malformed_states_are_refused() {
+    let sim = game_in_progress(config(GameMode::Classic));
+    let mut json: serde_json::Value = serde_json::from_str(&sim.to_json()).unwrap();
+    json["players"][0]["snake"]["body"] = serde_json::json!([]);
+    let bytes = sim.to_bytes();
+
+    let headless = Simulation::from_json(&json.to_string());
+    let truncated = Simulation::from_bytes(&bytes[..bytes.len() / 2]);
+    let not_a_save = Simulation::from_bytes(sim.to_json().as_bytes());
+
+    assert!(matches!(headless, Err(SaveError::Json(_))));
+    assert!(matches!(truncated, Err(SaveError::Binary(_))));
+    assert!(matches!(not_a_save, Err(SaveError::NotASave)));
+    assert!(matches!(
+        Simulation::from_json("{\"version\": 1}"),
+        Err(SaveError::Json(_))
+    ));
+}
+
+#[test]
+fn states_not_matching_their_config_are_refused() {
+    let sim = game_in_progress(config(GameMode::Classic));
+    let json: serde_json::Value = serde_json::from_str(&sim.to_json()).unwrap();
+    let mut missing_player = json.clone();
+    missing_player["players"].as_array_mut().unwrap().pop();
+    let mut wider_snake = json.clone();
+    wider_snake["players"][1]["snake"]["width"] = 30.into();
+    let mut wrapping_snake = json;
+    wrapping_snake["players"][0]["prev_snake"]["wrap"] = serde_json::json!({"x": 800., "y": 800.});
+
+    for state in [missing_player, wider_snake, wrapping_snake] {
+        let result = Simulation::from_json(&state.to_string());
+        assert!(
+            matches!(result, Err(SaveError::Mismatch(_))),
+            "{:?}",
+            result.err()
+        );
+    }
+}
+
+#[test]
+fn saved_game_round_trip() {
+    let level = Level::load(LEVEL).unwrap();
+    let level_config = level.config(&config(GameMode::Classic)).unwrap();
+    let sim = game_in_progress(level_config.clone());
+    let mut progress = CampaignProgress::default();
+    progress.complete("walled_garden", 12, 34.5);
+    let mut recording = Replay::new(&Simulation::new(level_config.clone(), 11));
+    recording.record(3, &[(1, Direction::Up)]);
+    let member = Member {
+        name: "Alice".to_string(),
+        color: 2,
+        ready: true,
+    };
+    let game = SavedGame {
+        fixed_seed: Some(11),
+        level: Some(level),
+        campaign_level: None,
+        progress,
+        base_config: config(GameMode::Wrap),
+        roster: vec![member],
+        round_wins: vec![2],
+        recording: Some(recording),
+        playback: None,
+        sim,
+    };
+
+    let restored = SavedGame::from_bytes(&game.to_bytes()).unwrap();
+
+    assert_eq!(restored.fixed_seed, game.fixed_seed);
+    assert_eq!(restored.level, game.level);
+    assert_eq!(restored.progress, game.progress);
+    assert_eq!(restored.base_config, game.base_config);
+    assert_eq!(restored.roster, game.roster);
+    assert_eq!(restored.round_wins, game.round_wins);
+    assert_eq!(restored.recording, game.recording);
+    assert_eq!(restored.playback, None);
+    assert_eq!(restored.to_bytes(), game.to_bytes());
+    assert_same_continuation(game.sim, restored.sim);
+    assert!(matches!(
+        SavedGame::from_bytes(&Simulation::new(level_config, 1).to_bytes()),
+        Err(SaveError::NotASave)
+    ));
+}
@@ -0,0 +1,231 @@
+use snaek_core::{
+    ai::Greedy, consts, controller::Controller, net, Coords, Direction, FixedTimestep, Food,
+    GameConfig, GameMode, Obstacle, Rect, SegmentKind, Simulation, Spawn, StepEvent,
+};
+
+fn config() -> GameConfig {
+    GameConfig {
+        players: 2,
+        ..GameConfig::default()
+    }
+}
+
+/// Inputs fed on the given tick, every player turning now and then.
+fn inputs(tick: u64) -> Vec<(usize, Direction)> {
+    const TURNS: [Direction; 4] = [
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+        Direction::Up,
+    ];
+    (0..2)
+        .filter(|player| (tick + 11 * player).is_multiple_of(40))
+        .map(|player| (player as usize, TURNS[(tick / 40 + player) as usize % 4]))
+        .collect()
+}
+
+/// Plays the game driven by frames of the given lengths, returning
+/// the hash after every tick.
+fn play_frames(frames: impl IntoIterator<Item = f32>) -> Vec<u64> {
+    let mut sim = Simulation::new(config(), 5);
+    let mut timestep = FixedTimestep::new(sim.config.ticks_per_sec);
+    let mut hashes = Vec::new();
+    for frame in frames {
+        for _ in 0..timestep.advance(frame) {
+            sim.tick(&inputs(sim.tick));
+            hashes.push(net::state_hash(&sim));
+        }
+    }
+    hashes
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_game() {
+    let mut sims = [Simulation::new(config(), 42), Simulation::new(config(), 42)];
+
+    for _ in 0..500 {
+        let inputs = inputs(sims[0].tick);
+        for sim in &mut sims {
+            sim.tick(&inputs);
+        }
+        assert_eq!(net::state_hash(&sims[0]), net::state_hash(&sims[1]));
+        assert_eq!(sims[0].food, sims[1].food);
+    }
+}
+
+/// Lets a bot eat its way through the first `count` food of the game
+/// with the given seed, returning where each of them was placed.
+fn food_sequence(seed: u64, count: usize) -> Vec<Option<Food>> {
+    let mut sim = Simulation::new(GameConfig::default(), seed);
+    let mut bot = Greedy::new(1);
+    let mut foods = vec![sim.food];
+    while foods.len() < count {
+        let inputs: Vec<_> = bot
+            .inputs(&sim, 0)
+            .into_iter()
+            .map(|dir| (0, dir))
+            .collect();
+        match sim.tick(&inputs) {
+            StepEvent::FoodEaten => foods.push(sim.food),
+            StepEvent::Died => panic!("bot died after {} food", foods.len() - 1),
+            StepEvent::Moved => assert!(sim.tick < 20_000, "timed out"),
+        }
+    }
+    foods
+}
+
+#[test]
+fn seed_fixes_the_food_sequence() {
+    let foods = food_sequence(7, 6);
+
+    assert_eq!(food_sequence(7, 6), foods);
+    let other = food_sequence(8, 6);
+    assert!(other.iter().zip(&foods).all(|(other, food)| other != food));
+}
+
+#[test]
+fn timestep_does_not_depend_on_frame_times() {
+    let splits: [&[f32]; 3] = [&[0.105], &[0.05, 0.055], &[0.035, 0.035, 0.035]];
+
+    let results: Vec<_> = splits
+        .iter()
+        .map(|frames| {
+            let mut timestep = FixedTimestep::new(100);
+            let ticks: u32 = frames.iter().map(|&frame| timestep.advance(frame)).sum();
+            (ticks, timestep.alpha())
+        })
+        .collect();
+
+    for &(ticks, alpha) in &results {
+        assert_eq!(ticks, 10);
+        assert!((alpha - 0.5).abs() < 1e-3, "alpha {}", alpha);
+    }
+}
+
+#[test]
+fn long_frames_are_capped() {
+    let mut timestep = FixedTimestep::new(8);
+
+    assert_eq!(timestep.advance(10.), (consts::MAX_FRAME_TIME * 8.) as u32);
+    assert_eq!(timestep.advance(0.), 0);
+    assert_eq!(timestep.alpha(), 0.);
+}
+
+#[test]
+fn game_does_not_depend_on_framerate() {
+    let at_30_fps = play_frames(vec![1. / 30.; 150]);
+    let at_144_fps = play_frames(vec![1. / 144.; 720]);
+    let uneven = play_frames((0..400).map(|frame| [0.004, 0.031, 0.0125][frame % 3]));
+
+    let ticks = at_30_fps.len().min(at_144_fps.len()).min(uneven.len());
+    assert!(ticks > 550);
+    assert_eq!(at_30_fps[..ticks], at_144_fps[..ticks]);
+    assert_eq!(at_30_fps[..ticks], uneven[..ticks]);
+}
+
+#[test]
+fn head_stays_in_wrapped_arena_when_turning_at_the_edge() {
+    let turns = [
+        (Direction::Right, Direction::Down),
+        (Direction::Down, Direction::Left),
+        (Direction::Left, Direction::Up),
+        (Direction::Up, Direction::Right),
+    ];
+    for (dir, turn) in turns {
+        // Head reaches the edge on tick 160, turning starts a few pixels before it
+        for turn_tick in [154, 157, 159] {
+            let config = GameConfig {
+                mode: GameMode::Wrap,
+                spawn: Some(Spawn {
+                    x: 400.,
+                    y: 400.,
+                    dir,
+                }),
+                food_spawns: vec![Coords::new(100., 100.); 2],
+                ..GameConfig::default()
+            };
+            let size = config.screen_size();
+            let mut sim = Simulation::new(config, 1);
+
+            while sim.tick < 200 {
+                let inputs = match sim.tick == turn_tick {
+                    true => vec![(0, turn)],
+                    false => Vec::new(),
+                };
+                sim.tick(&inputs);
+
+                let snake = &sim.players[0].snake;
+                let head = snake.body.front().unwrap().end();
+                assert!(
+                    (0. ..=size.x).contains(&head.x) && (0. ..=size.y).contains(&head.y),
+                    "head {:?} left the arena on tick {}",
+                    head,
+                    sim.tick
+                );
+                assert!(sim.players[0].alive);
+            }
+            let snake = &sim.players[0].snake;
+            assert_eq!(snake.dir, turn);
+            assert!(snake.length() > 0.);
+            assert!(matches!(
+                snake.body.front().unwrap().kind(),
+                SegmentKind::Line(line) if line.dir == turn
+            ));
+        }
+    }
+}
+
+/// Small arena walled all over, but for the snake and the free cell
+/// of the size of the food in its top left corner.
+fn walled_arena() -> GameConfig {
+    let walls = [
+        (0., 0., 200., 20.),
+        (0., 20., 20., 20.),
+        (40., 20., 160., 20.),
+        (0., 40., 90., 160.),
+        (110., 40., 90., 160.),
+        (90., 40., 20., 50.),
+        (90., 150., 20., 50.),
+    ];
+    GameConfig {
+        screen_width: 200.,
+        screen_height: 200.,
+        snake_start_len: 2,
+        spawn: Some(Spawn {
+            x: 100.,
+            y: 100.,
+            dir: Direction::Up,
+        }),
+        obstacles: walls
+            .iter()
+            .map(|&(x, y, w, h)| Obstacle::Rect(Rect::new(x, y, w, h)))
+            .collect(),
+        ..GameConfig::default()
+    }
+}
+
+#[test]
+fn food_finds_the_only_free_cell() {
+    for seed in 0..5 {
+        let sim = Simulation::new(walled_arena(), seed);
+
+        let food = sim.food.expect("food was not placed");
+        assert!((food.bbox.x - 20.).abs() < 0.1 && (food.bbox.y - 20.).abs() < 0.1);
+    }
+}
+
+#[test]
+fn arena_without_room_has_no_food() {
+    let mut config = walled_arena();
+    config
+        .obstacles
+        .push(Obstacle::Rect(Rect::new(20., 20., 20., 20.)));
+    let mut sim = Simulation::new(config, 3);
+    assert_eq!(sim.food, None);
+
+    for _ in 0..10 {
+        sim.tick(&[]);
+        assert_eq!(sim.food, None);
+    }
+    assert_eq!(sim.food_eaten(), 0);
+}
@@ -0,0 +1,242 @@
+mod common;
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use snaek_core::{
+    ai, controller,
+    lobby::Member,
+    lockstep::DISCONNECT_TIMEOUT,
+    net,
+    spectate::MatchFeed,
+    tournament::{Entrant, EntrantKind},
+    Broadcaster, Difficulty, GameConfig, LockstepClient, Simulation, Spectator, Tournament,
+};
+
+use common::{config, spawn_server};
+
+/// Polls the spectator until `done` or panics after a while.
+fn wait_until<F>(spectator: &mut Spectator, done: F)
+where
+    F: Fn(&Spectator) -> bool,
+{
+    let start = Instant::now();
+    while !done(spectator) {
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        spectator.receive().unwrap();
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn bots_game(seed: u64) -> Simulation {
+    Simulation::new(config(0, 2), seed)
+}
+
+/// Plays up to `ticks` ticks with bots, streaming them through the `feed`.
+fn play(sim: &mut Simulation, feed: &MatchFeed, ticks: u64) {
+    let mut controllers = ai::bots(&sim.config);
+    for _ in 0..ticks {
+        if sim.is_over() {
+            break;
+        }
+        let inputs = controller::collect_inputs(&mut controllers, sim);
+        feed.tick(&inputs, &[]);
+        sim.tick(&inputs);
+    }
+}
+
+/// Plays back the watched match as it arrives, until `done` or panics after a while.
+fn play_back_until<F>(spectator: &mut Spectator, done: F)
+where
+    F: Fn(&Spectator) -> bool,
+{
+    let start = Instant::now();
+    loop {
+        spectator.receive().unwrap();
+        while spectator.advance().is_some() {}
+        if done(spectator) {
+            return;
+        }
+        assert!(start.elapsed() < Duration::from_secs(10), "timed out");
+        thread::sleep(Duration::from_millis(5));
+    }
+}
+
+fn played_ticks(spectator: &Spectator) -> u64 {
+    spectator.sim().map_or(0, |sim| sim.tick)
+}
+
+#[test]
+fn spectator_sees_the_same_game() {
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", Duration::ZERO).unwrap();
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+    let mut sim = bots_game(1);
+    let feed = broadcaster.start_match("Bots", &sim, &[]);
+
+    wait_until(&mut spectator, |spectator| !spectator.matches().is_empty());
+    assert_eq!(spectator.matches()[0].name, "Bots");
+    spectator.watch(feed.id()).unwrap();
+    play(&mut sim, &feed, 300);
+    drop(feed);
+    play_back_until(&mut spectator, Spectator::is_over);
+
+    let watched = spectator.sim().unwrap();
+    assert_eq!(watched.tick, sim.tick);
+    assert_eq!(net::state_hash(watched), net::state_hash(&sim));
+}
+
+#[test]
+fn spectator_gets_the_players_seated_in_the_lobby() {
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", Duration::ZERO).unwrap();
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+    let sim = bots_game(6);
+    let roster: Vec<_> = ["Ann", "Bob"]
+        .iter()
+        .zip(&[3, 1])
+        .map(|(name, &color)| Member {
+            name: name.to_string(),
+            color,
+            ready: true,
+        })
+        .collect();
+    let feed = broadcaster.start_match("Lobby", &sim, &roster);
+    assert!(spectator.roster().is_empty());
+
+    spectator.watch(feed.id()).unwrap();
+    wait_until(&mut spectator, |spectator| spectator.sim().is_some());
+
+    assert_eq!(spectator.roster(), roster.as_slice());
+}
+
+#[test]
+fn late_spectator_catches_up_at_once() {
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", Duration::ZERO).unwrap();
+    let mut sim = bots_game(2);
+    let feed = broadcaster.start_match("Bots", &sim, &[]);
+    play(&mut sim, &feed, 100);
+
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+    spectator.watch(feed.id()).unwrap();
+    wait_until(&mut spectator, |spectator| played_ticks(spectator) == 100);
+    assert_eq!(spectator.advance(), None);
+
+    play(&mut sim, &feed, 10);
+    play_back_until(&mut spectator, |spectator| played_ticks(spectator) == 110);
+    assert_eq!(
+        net::state_hash(spectator.sim().unwrap()),
+        net::state_hash(&sim)
+    );
+}
+
+#[test]
+fn deltas_are_delayed() {
+    let delay = Duration::from_millis(300);
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", delay).unwrap();
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+    let mut sim = bots_game(3);
+    let feed = broadcaster.start_match("Bots", &sim, &[]);
+    spectator.watch(feed.id()).unwrap();
+    let start = Instant::now();
+    play(&mut sim, &feed, 10);
+
+    play_back_until(&mut spectator, |spectator| played_ticks(spectator) == 10);
+
+    assert!(start.elapsed() >= delay);
+}
+
+#[test]
+fn spectator_switches_matches() {
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", Duration::ZERO).unwrap();
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+    let mut first = bots_game(4);
+    let mut second = bots_game(5);
+    let first_feed = broadcaster.start_match("First", &first, &[]);
+    let second_feed = broadcaster.start_match("Second", &second, &[]);
+    play(&mut first, &first_feed, 20);
+    play(&mut second, &second_feed, 30);
+
+    spectator.watch(first_feed.id()).unwrap();
+    wait_until(&mut spectator, |spectator| played_ticks(spectator) == 20);
+    assert_eq!(spectator.watching().unwrap().1, "First");
+
+    spectator.watch(second_feed.id()).unwrap();
+    wait_until(&mut spectator, |spectator| played_ticks(spectator) == 30);
+    assert_eq!(spectator.watching().unwrap().1, "Second");
+    assert_eq!(
+        net::state_hash(spectator.sim().unwrap()),
+        net::state_hash(&second)
+    );
+}
+
+#[test]
+fn online_game_is_streamed() {
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", Duration::ZERO).unwrap();
+    let streamed = broadcaster.clone();
+    let (addr, server) = spawn_server(
+        config(1, 1),
+        6,
+        2,
+        move |server| server.with_broadcast(&streamed, "Online"),
+        |server| net::state_hash(server.sim()),
+    );
+
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+    wait_until(&mut spectator, |spectator| !spectator.matches().is_empty());
+    spectator.watch(spectator.matches()[0].id).unwrap();
+    {
+        let (mut client, mut sim) = LockstepClient::connect(addr, DISCONNECT_TIMEOUT).unwrap();
+        while sim.tick < 100 && !sim.is_over() {
+            client.wait_step(&mut sim).unwrap();
+        }
+    }
+    let hash = server.join().unwrap().unwrap();
+
+    play_back_until(&mut spectator, Spectator::is_over);
+    assert_eq!(net::state_hash(spectator.sim().unwrap()), hash);
+}
+
+#[test]
+fn tournament_games_are_streamed() {
+    let broadcaster = Broadcaster::bind("127.0.0.1:0", Duration::ZERO).unwrap();
+    let tournament = Tournament {
+        max_ticks: 30,
+        entrants: vec![
+            Entrant {
+                name: "easy".to_string(),
+                kind: EntrantKind::Difficulty(Difficulty::Easy),
+            },
+            Entrant {
+                name: "hard".to_string(),
+                kind: EntrantKind::Difficulty(Difficulty::Hard),
+            },
+        ],
+        ..Tournament::from_toml(
+            "[[entrant]]\nname = \"a\"\ndifficulty = \"easy\"\n\
+             [[entrant]]\nname = \"b\"\ndifficulty = \"easy\"",
+        )
+        .unwrap()
+    };
+    let mut spectator = Spectator::connect(broadcaster.local_addr()).unwrap();
+
+    let start = Instant::now();
+    let results = tournament
+        .run_broadcast(&GameConfig::default(), 1, None, &broadcaster)
+        .unwrap();
+    wait_until(&mut spectator, |spectator| {
+        spectator.matches().first().is_some_and(|info| info.over)
+    });
+    assert_eq!(spectator.matches().len(), 1);
+
+    let game = &spectator.matches()[0];
+    assert_eq!(game.name, "Game 1 (round 1): easy vs hard");
+    // Played in real time for the spectators
+    let ticks = results.games[0].ticks;
+    let ticks_per_sec = GameConfig::default().ticks_per_sec as f32;
+    assert!(start.elapsed().as_secs_f32() >= (ticks - 1) as f32 / ticks_per_sec);
+
+    spectator.watch(game.id).unwrap();
+    play_back_until(&mut spectator, Spectator::is_over);
+    assert_eq!(played_ticks(&spectator), ticks);
+}
@@ -0,0 +1,134 @@
+use std::collections::HashSet;
+
+use snaek_core::{
+    rating::INITIAL_RATING,
+    tournament::{Entrant, EntrantKind, Format},
+    Difficulty, GameConfig, Tournament,
+};
+
+fn tournament(format: Format, entrants: usize) -> Tournament {
+    let difficulties = Difficulty::ALL.iter().cycle();
+    Tournament {
+        format,
+        rounds: 3,
+        games_per_pairing: 2,
+        seed: 7,
+        max_ticks: 1500,
+        bot_timeout: 100,
+        http_timeout: 100,
+        entrants: difficulties
+            .take(entrants)
+            .enumerate()
+            .map(|(idx, &difficulty)| Entrant {
+                name: format!("bot{}", idx),
+                kind: EntrantKind::Difficulty(difficulty),
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn tournament_is_parsed_from_toml() {
+    let tournament = Tournament::from_toml(
+        r#"
+        format = "swiss"
+        seed = 3
+
+        [[entrant]]
+        name = "hard"
+        difficulty = "hard"
+
+        [[entrant]]
+        name = "script"
+        command = "python3 bot.py"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(tournament.format, Format::Swiss);
+    assert_eq!(tournament.games_per_pairing, 1);
+    assert_eq!(
+        tournament.entrants[1].kind,
+        EntrantKind::Command("python3 bot.py".to_string())
+    );
+    assert!(Tournament::from_toml("[[entrant]]\nname = \"alone\"\ndifficulty = \"easy\"").is_err());
+}
+
+#[test]
+fn round_robin_plays_every_pairing_on_both_sides() {
+    let results = tournament(Format::RoundRobin, 5)
+        .run(&GameConfig::default(), 4, None)
+        .unwrap();
+
+    assert_eq!(results.games.len(), 5 * 4);
+    let sides: HashSet<_> = results.games.iter().map(|game| game.entrants).collect();
+    assert_eq!(sides.len(), 5 * 4);
+    for game in &results.games {
+        let round = results
+            .games
+            .iter()
+            .filter(|other| other.round == game.round);
+        // Both entrants play only their two games against each other in the round
+        assert!(
+            round
+                .flat_map(|other| other.entrants)
+                .filter(|entrant| game.entrants.contains(entrant))
+                .count()
+                == 2 * 2
+        );
+    }
+}
+
+#[test]
+fn results_do_not_depend_on_threads() {
+    let tournament = tournament(Format::RoundRobin, 3);
+    let config = GameConfig::default();
+
+    let single = tournament.run(&config, 1, None).unwrap();
+    let parallel = tournament.run(&config, 8, None).unwrap();
+
+    assert_eq!(single, parallel);
+}
+
+#[test]
+fn swiss_avoids_rematches_and_gives_single_byes() {
+    let results = tournament(Format::Swiss, 5)
+        .run(&GameConfig::default(), 4, None)
+        .unwrap();
+
+    let mut pairings = HashSet::new();
+    for round in 1..=3 {
+        let round_pairings: HashSet<_> = results
+            .games
+            .iter()
+            .filter(|game| game.round == round)
+            .map(|game| {
+                (
+                    game.entrants[0].min(game.entrants[1]),
+                    game.entrants[0].max(game.entrants[1]),
+                )
+            })
+            .collect();
+        assert_eq!(round_pairings.len(), 2);
+        assert!(pairings.is_disjoint(&round_pairings));
+        pairings.extend(round_pairings);
+    }
+    assert!(results.standings.iter().all(|standing| standing.byes <= 1));
+    assert_eq!(results.standings.iter().map(|s| s.byes).sum::<u32>(), 3);
+}
+
+#[test]
+fn elo_is_zero_sum() {
+    let results = tournament(Format::RoundRobin, 4)
+        .run(&GameConfig::default(), 4, None)
+        .unwrap();
+
+    let total: f64 = results.standings.iter().map(|standing| standing.elo).sum();
+    assert!((total - 4. * INITIAL_RATING).abs() < 1e-6);
+    let points: f64 = results
+        .standings
+        .iter()
+        .map(|standing| standing.points)
+        .sum();
+    assert_eq!(points, results.games.len() as f64);
+}
@@ -0,0 +1,41 @@
+#!/usr/bin/env python3
+"""Plays a batch of episodes with random actions and reports the mean reward.
+
+Build the module first: `maturin develop --release -m snaek_py/Cargo.toml`.
+"""
+import numpy as np
+
+import snaek
+
+CONFIG = """
+ticks_per_step = 8
+
+[game]
+bots = 1
+
+[reward]
+survival = 0.1
+"""
+
+
+def main():
+    envs = snaek.VecEnv(16, CONFIG)
+    rng = np.random.default_rng(0)
+    observation = envs.reset(seed=0)
+    print("grid", observation["grid"].shape, "rays", observation["rays"].shape)
+
+    returns, episodes = np.zeros(len(envs)), []
+    for _ in range(2000):
+        actions = rng.integers(envs.action_count, size=len(envs))
+        observation, rewards, dones, _ = envs.step(actions)
+        returns += rewards
+        episodes.extend(returns[dones])
+        returns[dones] = 0.
+
+    print(f"{len(episodes)} episodes, mean reward {np.mean(episodes):.2f}")
+    frame = envs.render_rgb_array(0, scale=0.5)
+    print("frame", frame.shape, frame.dtype)
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,303 @@
+//! # snaek
+//! Python bindings of the snaek_rust reinforcement-learning environment.
+//!
+//! `Env` wraps a single `snaek_core::Env`, `VecEnv` steps a batch of them
+//! at once, resetting every finished episode on its own. Observations are
+//! dicts of NumPy arrays: `grid` of shape (channels, rows, cols) and `rays`
+//! of shape (rays, 3), with a leading batch dimension in `VecEnv`, plus
+//! the raw `segments` as plain Python objects when turned on.
+//!
+//! Both take the environment config as TOML text, the same as
+//! `snaek_core::EnvConfig`. Build the module with maturin:
+//! `maturin develop --release -m snaek_py/Cargo.toml`.
+use std::convert::TryFrom;
+
+use numpy::{PyArray1, PyArray3, PyArrayMethods, PyReadonlyArray1};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyDict, PyList},
+};
+use snaek_core::{
+    env::{StepInfo, GRID_CHANNELS, RAY_VALUES},
+    render, Action, Env, EnvConfig, Observation,
+};
+
+fn value_error<E: std::fmt::Display>(err: E) -> PyErr {
+    PyValueError::new_err(err.to_string())
+}
+
+/// Return the environment parsed from the TOML `config`, the default one if missing.
+///
+fn env(config: Option<&str>) -> PyResult<Env> {
+    let config = match config {
+        Some(toml) => toml::from_str(toml)
+            .map_err(|err| value_error(format!("Could not parse config: {}", err)))?,
+        None => EnvConfig::default(),
+    };
+    Env::new(config).map_err(value_error)
+}
+
+fn action(idx: usize) -> PyResult<Action> {
+    Action::from_index(idx).ok_or_else(|| {
+        value_error(format!(
+            "action must be below {}, got {}",
+            Action::ALL.len(),
+            idx
+        ))
+    })
+}
+
+/// Return the arrays of the given observations stacked together,
+/// with a leading batch dimension if `batched`.
+///
+fn observations<'py>(
+    py: Python<'py>,
+    observations: &[Observation],
+    batched: bool,
+) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    let stacked = |mut shape: Vec<usize>, data: Vec<f32>| {
+        if batched {
+            shape.insert(0, observations.len());
+        }
+        PyArray1::from_vec(py, data).reshape(shape)
+    };
+
+    if let Some(grid) = &observations[0].grid {
+        let data = observations
+            .iter()
+            .flat_map(|observation| observation.grid.as_ref().unwrap().data.iter().copied())
+            .collect();
+        dict.set_item(
+            "grid",
+            stacked(vec![GRID_CHANNELS, grid.rows, grid.cols], data)?,
+        )?;
+    }
+    if let Some(rays) = &observations[0].rays {
+        let data = observations
+            .iter()
+            .flat_map(|observation| observation.rays.iter().flatten().copied())
+            .collect();
+        dict.set_item(
+            "rays",
+            stacked(vec![rays.len() / RAY_VALUES, RAY_VALUES], data)?,
+        )?;
+    }
+    if observations[0].segments.is_some() {
+        let json = py.import("json")?;
+        let segments = observations
+            .iter()
+            .map(|observation| {
+                let text = serde_json::to_string(&observation.segments).map_err(value_error)?;
+                json.call_method1("loads", (text,))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        match batched {
+            true => dict.set_item("segments", segments)?,
+            false => dict.set_item("segments", &segments[0])?,
+        }
+    }
+    Ok(dict)
+}
+
+fn info<'py>(py: Python<'py>, info: &StepInfo) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("tick", info.tick)?;
+    dict.set_item("score", info.score)?;
+    dict.set_item("food_eaten", info.food_eaten)?;
+    dict.set_item("died", info.died)?;
+    dict.set_item("won", info.won)?;
+    dict.set_item("truncated", info.truncated)?;
+    Ok(dict)
+}
+
+fn render_rgb_array<'py>(
+    py: Python<'py>,
+    env: &Env,
+    scale: f32,
+) -> PyResult<Bound<'py, PyArray3<u8>>> {
+    if !scale.is_finite() || scale <= 0. {
+        return Err(value_error(format!(
+            "scale must be positive, got {}",
+            scale
+        )));
+    }
+    let image = render::render(env.sim(), scale);
+    PyArray1::from_vec(py, image.data).reshape([image.height, image.width, 3])
+}
+
+/// Single environment, with the agent steering the first snake
+///
+#[pyclass(name = "Env", unsendable)]
+struct PyEnv {
+    env: Env,
+}
+
+#[pymethods]
+impl PyEnv {
+    #[new]
+    #[pyo3(signature = (config=None))]
+    fn new(config: Option<&str>) -> PyResult<Self> {
+        Ok(Self { env: env(config)? })
+    }
+
+    /// Number of discrete actions: keep going, up, down, left and right
+    #[getter]
+    fn action_count(&self) -> usize {
+        self.env.action_count()
+    }
+
+    #[pyo3(signature = (seed=0))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: u64) -> PyResult<Bound<'py, PyDict>> {
+        let observation = self.env.reset(seed);
+        observations(py, &[observation], false)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        action: usize,
+    ) -> PyResult<(Bound<'py, PyDict>, f32, bool, Bound<'py, PyDict>)> {
+        let (observation, reward, done, step_info) = self.env.step(self::action(action)?);
+        Ok((
+            observations(py, &[observation], false)?,
+            reward,
+            done,
+            info(py, &step_info)?,
+        ))
+    }
+
+    /// Return the arena as an array of shape (height, width, 3)
+    #[pyo3(signature = (scale=1.0))]
+    fn render_rgb_array<'py>(
+        &self,
+        py: Python<'py>,
+        scale: f32,
+    ) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        render_rgb_array(py, &self.env, scale)
+    }
+}
+
+/// Batch of environments stepped together
+///
+/// Episodes that end are reset right away with the next unused seed,
+/// their last observation is kept in the info as `final_observation`.
+///
+#[pyclass(name = "VecEnv", unsendable)]
+struct PyVecEnv {
+    envs: Vec<Env>,
+    next_seed: u64,
+}
+
+#[pymethods]
+impl PyVecEnv {
+    #[new]
+    #[pyo3(signature = (count, config=None))]
+    fn new(count: usize, config: Option<&str>) -> PyResult<Self> {
+        if count == 0 {
+            return Err(value_error("there must be at least one environment"));
+        }
+        Ok(Self {
+            envs: (0..count).map(|_| env(config)).collect::<PyResult<_>>()?,
+            next_seed: count as u64,
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.envs.len()
+    }
+
+    #[getter]
+    fn action_count(&self) -> usize {
+        self.envs[0].action_count()
+    }
+
+    /// Resets every environment, the n-th one with `seed + n`
+    #[pyo3(signature = (seed=0))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: u64) -> PyResult<Bound<'py, PyDict>> {
+        let batch: Vec<_> = (seed..)
+            .zip(self.envs.iter_mut())
+            .map(|(seed, env)| env.reset(seed))
+            .collect();
+        self.next_seed = seed + self.envs.len() as u64;
+        observations(py, &batch, true)
+    }
+
+    /// Steps every environment with its action, given as a list or a NumPy array
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(
+        &mut self,
+        py: Python<'py>,
+        actions: &Bound<'py, PyAny>,
+    ) -> PyResult<(
+        Bound<'py, PyDict>,
+        Bound<'py, PyArray1<f32>>,
+        Bound<'py, PyArray1<bool>>,
+        Bound<'py, PyList>,
+    )> {
+        let actions: Vec<i64> = match actions.extract::<PyReadonlyArray1<'py, i64>>() {
+            Ok(array) => array.as_slice()?.to_vec(),
+            Err(_) => actions.extract()?,
+        };
+        if actions.len() != self.envs.len() {
+            return Err(value_error(format!(
+                "expected {} actions, got {}",
+                self.envs.len(),
+                actions.len()
+            )));
+        }
+
+        let (mut batch, mut rewards, mut dones) = (Vec::new(), Vec::new(), Vec::new());
+        let infos = PyList::empty(py);
+        for (env, &idx) in self.envs.iter_mut().zip(&actions) {
+            let action = usize::try_from(idx).map_err(value_error).and_then(action)?;
+            let (observation, reward, done, step_info) = env.step(action);
+            let info = info(py, &step_info)?;
+            let observation = match done {
+                true => {
+                    let last = observations(py, &[observation], false)?;
+                    info.set_item("final_observation", last)?;
+                    self.next_seed += 1;
+                    env.reset(self.next_seed - 1)
+                }
+                false => observation,
+            };
+            batch.push(observation);
+            rewards.push(reward);
+            dones.push(done);
+            infos.append(info)?;
+        }
+
+        Ok((
+            observations(py, &batch, true)?,
+            PyArray1::from_vec(py, rewards),
+            PyArray1::from_vec(py, dones),
+            infos,
+        ))
+    }
+
+    /// Return the arena of the environment with the given index
+    /// as an array of shape (height, width, 3)
+    #[pyo3(signature = (index=0, scale=1.0))]
+    fn render_rgb_array<'py>(
+        &self,
+        py: Python<'py>,
+        index: usize,
+        scale: f32,
+    ) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        let env = self
+            .envs
+            .get(index)
+            .ok_or_else(|| value_error(format!("no environment with index {}", index)))?;
+        render_rgb_array(py, env, scale)
+    }
+}
+
+#[pymodule]
+fn snaek(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyEnv>()?;
+    module.add_class::<PyVecEnv>()?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! # snaek_server
+//! Dedicated server of online games of snaek_rust.
+//!
+//! Waits until every player joined with `snaek_rust --connect ADDR`, then
+//! relays their inputs in lockstep and steers the bots until the game is over.
+//! With `--lobby` the game is advertised on the local network instead, for
+//! the players to join with `snaek_rust --lobby` and set it up together.
+use std::{net::SocketAddr, path::PathBuf, process, thread, time::Duration};
+
+use clap::Parser;
+use snaek_core::{
+    lockstep::{DEFAULT_INPUT_DELAY, DISCONNECT_TIMEOUT},
+    net::DEFAULT_PORT,
+    Broadcaster, Difficulty, GameConfig, GameMode, Level, Lobby, LockstepServer,
+};
+
+/// How long the broadcast stays up after the game, for the spectators to get its end
+const LINGER: Duration = Duration::from_secs(1);
+
+/// Dedicated server of online snaek_rust games
+///
+#[derive(Parser, Debug)]
+#[command(name = "snaek_server", version, about)]
+struct Args {
+    /// Address to listen on
+    #[arg(long, value_name = "ADDR", default_value_t = format!("0.0.0.0:{}", DEFAULT_PORT))]
+    bind: String,
+
+    /// TOML file with the game config
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Number of players joining over the network, the most of them with `--lobby`
+    #[arg(long, value_name = "N", default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..))]
+    players: u8,
+
+    /// Number of computer controlled snakes, overrides the config
+    #[arg(long, value_name = "N")]
+    bots: Option<u8>,
+
+    /// How well the computer controlled snakes play, overrides the config
+    #[arg(long, value_name = "NAME")]
+    difficulty: Option<Difficulty>,
+
+    /// Game mode, overrides the config
+    #[arg(long, value_name = "NAME")]
+    mode: Option<GameMode>,
+
+    /// Seed of the game, random if not given
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Ticks the inputs of the players are delayed by to hide the latency
+    #[arg(long, value_name = "TICKS", default_value_t = DEFAULT_INPUT_DELAY)]
+    input_delay: u64,
+
+    /// Seconds a player may stay silent before being dropped from the game
+    #[arg(long, value_name = "SECS", default_value_t = DISCONNECT_TIMEOUT.as_secs())]
+    timeout: u64,
+
+    /// Address to stream the game to spectators on
+    #[arg(long, value_name = "ADDR")]
+    broadcast: Option<String>,
+
+    /// Seconds the spectators are kept behind the players
+    #[arg(long, value_name = "SECS", default_value_t = 0, requires = "broadcast")]
+    broadcast_delay: u64,
+
+    /// Advertise the game on the local network under the given name and let the
+    /// players in through a lobby, where the first of them sets up and starts it
+    #[arg(long, value_name = "NAME")]
+    lobby: Option<String>,
+
+    /// Level file the host of the lobby can pick, can be repeated
+    #[arg(long, value_name = "FILE", requires = "lobby")]
+    level: Vec<PathBuf>,
+
+    /// Address to advertise the lobby to [default: broadcast on the local network]
+    #[arg(long, value_name = "ADDR", requires = "lobby")]
+    announce: Option<SocketAddr>,
+}
+
+impl Args {
+    /// Build `GameConfig` from the config file and command line overrides.
+    ///
+    fn game_config(&self) -> Result<GameConfig, String> {
+        let mut config = match &self.config {
+            Some(path) => {
+                GameConfig::load(path).map_err(|err| format!("{}: {}", path.display(), err))?
+            }
+            None => GameConfig::default(),
+        };
+        config.players = self.players;
+        if let Some(bots) = self.bots {
+            config.bots = bots;
+        }
+        if let Some(difficulty) = self.difficulty {
+            config.bot_difficulty = difficulty;
+        }
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        config.validate().map_err(|err| err.to_string())?;
+        Ok(config)
+    }
+
+    /// Build the lobby with the levels from the level files, if there is any lobby.
+    ///
+    fn lobby(&self, base: &GameConfig) -> Result<Option<Lobby>, String> {
+        let name = match &self.lobby {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        let mut lobby = Lobby::new(name);
+        for path in &self.level {
+            let level = Level::load(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+            let config = level
+                .config(base)
+                .map_err(|err| format!("{}: {}", path.display(), err))?;
+            lobby = lobby.with_level(&level.name, config);
+        }
+        if let Some(addr) = self.announce {
+            lobby = lobby.announce_to(addr);
+        }
+        Ok(Some(lobby))
+    }
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let config = args.game_config()?;
+    let lobby = args.lobby(&config)?;
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let mut server = LockstepServer::bind(&args.bind, config, seed, args.input_delay)
+        .map_err(|err| format!("{}: {}", args.bind, err))?
+        .with_timeout(Duration::from_secs(args.timeout));
+    let addr = server.local_addr().map_err(|err| err.to_string())?;
+    if let Some(lobby) = lobby {
+        server = server.with_lobby(lobby);
+    }
+    let broadcast_delay = Duration::from_secs(args.broadcast_delay);
+    if let Some(broadcast) = &args.broadcast {
+        let broadcaster = Broadcaster::bind(broadcast, broadcast_delay)
+            .map_err(|err| format!("{}: {}", broadcast, err))?;
+        server = server.with_broadcast(&broadcaster, &format!("Online game on {}", addr));
+        println!("Streaming the game to spectators on {}", broadcast);
+    }
+
+    match &args.lobby {
+        Some(name) => println!(
+            "Lobby '{}' for up to {} players open on {}",
+            name, args.players, addr
+        ),
+        None => println!("Waiting for {} players on {}", args.players, addr),
+    }
+    let result = server.run();
+    if args.broadcast.is_some() {
+        // Spectators are behind, let them see the game to the end
+        thread::sleep(broadcast_delay + LINGER);
+    }
+    result.map_err(|err| err.to_string())?;
+
+    let sim = server.sim();
+    let scores: Vec<_> = sim
+        .players
+        .iter()
+        .map(|player| player.score.to_string())
+        .collect();
+    for (player, member) in server.roster().iter().enumerate() {
+        println!("Snake {}: {}", player + 1, member.name);
+    }
+    println!(
+        "Ticks: {}, score: {}, seed: {}",
+        sim.tick,
+        scores.join(" "),
+        sim.seed
+    );
+    if let Some(winner) = sim.winner() {
+        println!("Snake {} wins!", winner + 1);
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(err) = run(&Args::parse()) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
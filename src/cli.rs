@@ -0,0 +1,318 @@
+//! Command line interface of the game binary
+use std::path::{Path, PathBuf};
+
+use clap::{ArgGroup, Parser};
+use ggez::{GameError, GameResult};
+use snaek_core::{
+    battlesnake::DEFAULT_HTTP_TIMEOUT, external::DEFAULT_MOVE_TIMEOUT, net::DEFAULT_PORT,
+    spectate::DEFAULT_SPECTATOR_PORT, Difficulty, GameConfig, GameMode, Level, Replay,
+};
+
+use crate::game::SNAKE_COLOR_NAMES;
+
+/// Config file that is loaded when no `--config` is given, if it exists.
+const DEFAULT_CONFIG_PATH: &str = "./config.toml";
+
+/// Little redefinition of a standard game of snake
+///
+#[derive(Parser, Debug)]
+#[command(name = "snaek_rust", version, about)]
+#[command(group(ArgGroup::new("online").args(["connect", "lobby"]).multiple(true)))]
+pub struct Cli {
+    /// TOML file with the game config [default: ./config.toml, if it exists]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Seed used for every game, random for each game if not given
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Directory with game resources
+    #[arg(long, value_name = "DIR", default_value = "./resources")]
+    pub resources: PathBuf,
+
+    /// Arena width in pixels, overrides the config
+    #[arg(long)]
+    pub width: Option<f32>,
+
+    /// Arena height in pixels, overrides the config
+    #[arg(long)]
+    pub height: Option<f32>,
+
+    /// Run in a fullscreen window, scaling the arena to fit
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Play back the replay file instead of taking keyboard input
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "config", "seed", "width", "height", "mode", "level", "players", "bots", "difficulty",
+            "external_bot", "http_bot"
+        ]
+    )]
+    pub replay: Option<PathBuf>,
+
+    /// Level file to play, its map replaces the configured arena
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["width", "height"])]
+    pub level: Option<PathBuf>,
+
+    /// Record games into the replay file, overwritten after each game
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// File the game in progress is saved into on quitting, and resumed from
+    /// on the next start
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["replay", "headless", "tournament", "connect", "lobby", "spectate"]
+    )]
+    pub save: Option<PathBuf>,
+
+    /// Game mode, overrides the config
+    #[arg(long, value_name = "NAME")]
+    pub mode: Option<GameMode>,
+
+    /// Number of local players sharing the keyboard, overrides the config
+    #[arg(long, value_name = "N", conflicts_with = "level")]
+    pub players: Option<u8>,
+
+    /// Number of computer controlled snakes, overrides the config
+    #[arg(long, value_name = "N", conflicts_with = "level")]
+    pub bots: Option<u8>,
+
+    /// How well the computer controlled snakes play, overrides the config
+    #[arg(long, value_name = "NAME")]
+    pub difficulty: Option<Difficulty>,
+
+    /// Command starting an external bot that talks JSON lines over stdin/stdout,
+    /// can be repeated. External bots take the first AI snakes, adding more if needed
+    #[arg(
+        long,
+        value_name = "CMD",
+        requires = "headless",
+        conflicts_with = "level"
+    )]
+    pub external_bot: Vec<String>,
+
+    /// Milliseconds an external bot gets to answer before it is disqualified
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_MOVE_TIMEOUT.as_millis() as u64)]
+    pub bot_timeout: u64,
+
+    /// Base url of a bot server speaking the Battlesnake-like HTTP API, can be repeated.
+    /// HTTP bots take the AI snakes following the external ones, adding more if needed
+    #[arg(
+        long,
+        value_name = "URL",
+        requires = "headless",
+        conflicts_with = "level"
+    )]
+    pub http_bot: Vec<String>,
+
+    /// Milliseconds an HTTP bot gets to answer before it is disqualified
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_HTTP_TIMEOUT.as_millis() as u64)]
+    pub http_timeout: u64,
+
+    /// Tournament file listing the bots to play against each other without a window
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["replay", "level", "headless", "record", "players", "bots"]
+    )]
+    pub tournament: Option<PathBuf>,
+
+    /// Number of threads tournament games are played on [default: number of CPUs]
+    #[arg(long, value_name = "N", requires = "tournament")]
+    pub threads: Option<usize>,
+
+    /// File the tournament results are saved into, as JSON for `.json` files and
+    /// as CSV otherwise, can be repeated
+    #[arg(long, value_name = "FILE", requires = "tournament")]
+    pub results: Vec<PathBuf>,
+
+    /// Directory every tournament game is recorded into, as a replay file
+    #[arg(long, value_name = "DIR", requires = "tournament")]
+    pub replay_dir: Option<PathBuf>,
+
+    /// Address of an online game server to join, the game config comes from the server.
+    /// In the headless mode the snake is steered by a bot of the `--difficulty`
+    #[arg(
+        long,
+        value_name = "ADDR",
+        conflicts_with_all = [
+            "config", "seed", "width", "height", "mode", "replay", "level", "record", "players",
+            "bots", "external_bot", "http_bot", "tournament"
+        ]
+    )]
+    pub connect: Option<String>,
+
+    /// Join the lobby of the server given with `--connect`, or of a game found on
+    /// the local network, and set the game up there together with the other players
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "config", "seed", "width", "height", "mode", "replay", "level", "record", "players",
+            "bots", "external_bot", "http_bot", "tournament", "headless"
+        ]
+    )]
+    pub lobby: bool,
+
+    /// Name shown to the other players in the lobby
+    #[arg(long, value_name = "NAME", requires = "lobby")]
+    pub name: Option<String>,
+
+    /// Color of the snake, picked in the lobby
+    #[arg(long, value_name = "COLOR", requires = "lobby", value_parser = SNAKE_COLOR_NAMES)]
+    pub color: Option<String>,
+
+    /// Wait for the inputs of the other players before every online tick, instead
+    /// of predicting them and rolling back the ticks that were mispredicted.
+    /// The headless mode always does
+    #[arg(long, requires = "online")]
+    pub lockstep: bool,
+
+    /// Address of a broadcaster to watch the matches of, the arrow keys switch between them
+    #[arg(
+        long,
+        value_name = "ADDR",
+        conflicts_with_all = [
+            "replay", "level", "record", "external_bot", "http_bot", "tournament", "connect",
+            "lobby", "headless"
+        ]
+    )]
+    pub spectate: Option<String>,
+
+    /// Address to stream the tournament games to spectators on, they are played
+    /// in real time then
+    #[arg(long, value_name = "ADDR", requires = "tournament")]
+    pub broadcast: Option<String>,
+
+    /// Seconds the spectators are kept behind the games
+    #[arg(long, value_name = "SECS", default_value_t = 0, requires = "broadcast")]
+    pub broadcast_delay: u64,
+
+    /// Run the simulation without a window
+    #[arg(long, requires = "ticks")]
+    pub headless: bool,
+
+    /// Number of ticks to simulate in the headless mode
+    #[arg(long, value_name = "N", requires = "headless")]
+    pub ticks: Option<u64>,
+}
+
+impl Cli {
+    /// Return the number of bots given with `--external-bot` and `--http-bot`.
+    ///
+    pub fn remote_bot_count(&self) -> usize {
+        self.external_bot.len() + self.http_bot.len()
+    }
+
+    /// Return the address given with `--connect`, with the default port if it has none.
+    ///
+    pub fn server_addr(&self) -> Option<String> {
+        self.connect
+            .as_ref()
+            .map(|addr| with_default_port(addr, DEFAULT_PORT))
+    }
+
+    /// Return the address given with `--spectate`, with the default port if it has none.
+    ///
+    pub fn spectator_addr(&self) -> Option<String> {
+        self.spectate
+            .as_ref()
+            .map(|addr| with_default_port(addr, DEFAULT_SPECTATOR_PORT))
+    }
+
+    /// Return the address given with `--broadcast`, with the default port if it has none.
+    ///
+    pub fn broadcast_addr(&self) -> Option<String> {
+        self.broadcast
+            .as_ref()
+            .map(|addr| with_default_port(addr, DEFAULT_SPECTATOR_PORT))
+    }
+
+    /// Load the replay given with `--replay`, if any.
+    ///
+    pub fn load_replay(&self) -> GameResult<Option<Replay>> {
+        match &self.replay {
+            Some(path) => Replay::load(path).map(Some).map_err(|err| {
+                GameError::ResourceLoadError(format!("{}: {}", path.display(), err))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Load the level given with `--level`, if any.
+    ///
+    pub fn load_level(&self) -> GameResult<Option<Level>> {
+        match &self.level {
+            Some(path) => Level::load(path).map(Some).map_err(|err| {
+                GameError::ResourceLoadError(format!("{}: {}", path.display(), err))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Build `GameConfig` from the config file, the level and command line overrides.
+    /// When playing back a replay, its own config is used as is.
+    ///
+    pub fn game_config(
+        &self,
+        replay: Option<&Replay>,
+        level: Option<&Level>,
+    ) -> GameResult<GameConfig> {
+        if let Some(replay) = replay {
+            return Ok(replay.config.clone());
+        }
+
+        let path = match &self.config {
+            Some(path) => Some(path.as_path()),
+            None => Some(Path::new(DEFAULT_CONFIG_PATH)).filter(|path| path.exists()),
+        };
+        let mut config = match path {
+            Some(path) => GameConfig::load(path)
+                .map_err(|err| GameError::ConfigError(format!("{}: {}", path.display(), err)))?,
+            None => GameConfig::default(),
+        };
+        if let Some(level) = level {
+            config = level
+                .config(&config)
+                .map_err(|err| GameError::ConfigError(format!("{}: {}", level.name, err)))?;
+        }
+
+        if let Some(mode) = self.mode {
+            config.mode = mode;
+        }
+        if let Some(players) = self.players {
+            config.players = players;
+        }
+        if let Some(bots) = self.bots {
+            config.bots = bots;
+        }
+        config.bots = config.bots.max(self.remote_bot_count() as u8);
+        if let Some(difficulty) = self.difficulty {
+            config.bot_difficulty = difficulty;
+        }
+        if let Some(width) = self.width {
+            config.screen_width = width;
+        }
+        if let Some(height) = self.height {
+            config.screen_height = height;
+        }
+        config
+            .validate()
+            .map_err(|err| GameError::ConfigError(err.to_string()))?;
+        Ok(config)
+    }
+}
+
+/// Return `addr` with the given `port` appended, unless it has one.
+///
+fn with_default_port(addr: &str, port: u16) -> String {
+    match addr.contains(':') {
+        true => addr.to_string(),
+        false => format!("{}:{}", addr, port),
+    }
+}
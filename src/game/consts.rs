@@ -1,25 +1,34 @@
 //! Module that gathers all more or less modifiable parameters
-use crate::game::coords::Coords;
+use ggez::event::KeyCode;
+use snaek_core::consts::SNAKE_COLORS;
 
 pub const GAME_ID: &str = "snaek_rust";
 pub const GAME_AUTHOR: &str = "mk.kulagowski";
+/// Name of the high score file in the user data directory
+pub const HIGH_SCORES_FILE: &str = "highscores.json";
+/// Name of the campaign progress file in the user data directory
+pub const CAMPAIGN_PROGRESS_FILE: &str = "campaign.json";
+/// Campaign file, relative to the resources directory
+pub const CAMPAIGN_FILE: &str = "levels/campaign.txt";
+/// Rounds a player has to win to win the whole match, when there are more players
+pub const ROUNDS_TO_WIN: u32 = 3;
 
-pub const SCREEN_SIZE: Coords = Coords { x: 800.0, y: 800.0 };
-pub const SNAKE_WIDTH: f32 = 20.;
-pub const SNAKE_START_LEN: u8 = 8;
-pub const SPEED: f32 = SNAKE_WIDTH * 15.;
-
-pub const WALL_MARGIN: f32 = SNAKE_WIDTH * 0.5;
-pub const COLLISION_PIXELS_MARGIN: f32 = 1.;
-pub const FOOD_SIZE: f32 = SNAKE_WIDTH;
-pub const FOOD_HALF_SIZE: f32 = FOOD_SIZE / 2.;
-pub const SNAKE_HALF_WIDTH: f32 = SNAKE_WIDTH / 2.;
-pub const SNAKE_START_HEIGHT: f32 = SNAKE_WIDTH * SNAKE_START_LEN as f32;
-pub const TURN_MARGIN: f32 = SNAKE_WIDTH * 0.15;
-pub const HALF_TURN_MARGIN: f32 = TURN_MARGIN / 2.;
-pub const SECS_PER_INPUT_UPDATE: f32 = (SNAKE_WIDTH + TURN_MARGIN) / SPEED;
-
-pub const PREGAME_TXT: &str = "Press SPACE to start the game";
+/// Keys steering the snake of each player, in the up, down, left, right order
+pub const KEY_MAPS: [[KeyCode; 4]; 4] = [
+    [KeyCode::W, KeyCode::S, KeyCode::A, KeyCode::D],
+    [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right],
+    [KeyCode::I, KeyCode::K, KeyCode::J, KeyCode::L],
+    [
+        KeyCode::Numpad8,
+        KeyCode::Numpad5,
+        KeyCode::Numpad4,
+        KeyCode::Numpad6,
+    ],
+];
+/// Names of the snake colors, for picking one in the lobby
+pub const SNAKE_COLOR_NAMES: [&str; SNAKE_COLORS.len()] = ["yellow", "cyan", "magenta", "orange"];
+/// Names of the key maps of the players, shown in the menu
+pub const KEY_MAP_NAMES: [&str; 4] = ["WASD", "arrows", "IJKL", "numpad"];
 
 /// Cannot use const value here, as macro requires literals
 #[macro_export]
@@ -1,85 +1,196 @@
 #[macro_use]
 mod consts;
-mod coords;
-mod direction;
-mod food;
-mod line;
 mod renderer;
 mod resourceloader;
-mod segment;
-mod snake;
+mod scene;
+mod spectator;
 mod state;
-mod turn;
 
 use ggez::{
     event::{EventHandler, KeyCode, KeyMods},
     graphics, Context, GameResult,
 };
-use state::GameState;
+use scene::{high_score_lines, MenuAction, Scene};
+use snaek_core::{Coords, InputQueue};
 use std::time::Instant;
 
 pub use crate::game::consts::*;
+pub use crate::game::spectator::SpectatorView;
 pub use crate::game::state::GameData;
 
-use self::{coords::Coords, direction::Direction, renderer::Renderer};
+use self::renderer::Renderer;
 
 impl EventHandler for GameData {
     fn update(&mut self, _ctx: &mut Context) -> GameResult<()> {
         let prev_time = std::mem::replace(&mut self.delta_time, Instant::now());
         let time_delta = self.delta_time.duration_since(prev_time).as_secs_f32();
 
-        match self.state {
-            GameState::PreGame => {}
-            GameState::Game => {
-                self.update_input(time_delta);
-                self.update_snake(time_delta);
-            }
+        if self.scene() == &Scene::Playing {
+            self.update_sim(time_delta);
         }
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
-        Renderer::draw_bg(ctx, &self.resources.bg_image);
-        Renderer::draw_snake(ctx, &self.snake);
-        Renderer::draw_food(ctx, &self.food, &self.resources.food_image);
+        let config = &self.sim.config;
+        Renderer::draw_bg(ctx, &self.resources.bg_image, config);
+        Renderer::draw_obstacles(ctx, &config.obstacles, &self.resources.wall_image);
+        let alpha = self.timestep.alpha();
+        for (idx, player) in self.sim.players.iter().enumerate() {
+            // Dead snakes of a match are out of the game, the only one is kept on screen
+            if player.alive || self.sim.players.len() == 1 {
+                let color = GameData::player_color(&self.roster, idx);
+                Renderer::draw_snake(ctx, &player.interpolated_snake(alpha), config, color);
+            }
+        }
+        if let Some(food) = &self.sim.food {
+            Renderer::draw_food(ctx, food, &self.resources.food_image);
+        }
 
-        match self.state {
-            GameState::PreGame => {
-                let (xdim, ydim) = self.pregame_txt.dimensions(ctx);
-                let (xdim, ydim) = (xdim as f32, ydim as f32);
-                Renderer::draw_text_with_outline(
-                    ctx,
-                    &self.pregame_txt,
-                    Coords::new(
-                        consts::SCREEN_SIZE.x / 2. - xdim / 2.,
-                        consts::SCREEN_SIZE.y / 2. - ydim / 2.,
-                    ),
-                );
+        let (menu, info) = match self.scene() {
+            Scene::Playing => {
+                Renderer::draw_text_with_outline(ctx, &self.score_txt, Coords::new(10., 10.));
+                (None, Vec::new())
+            }
+            Scene::MainMenu(menu) => {
+                let info = match &self.level {
+                    Some(level) => vec![level.name.clone(), level.goal.to_string()],
+                    None => Vec::new(),
+                };
+                (Some(menu), info)
+            }
+            Scene::Settings(menu) => {
+                let players = usize::from(config.players);
+                let info = match players {
+                    1 => Vec::new(),
+                    _ => KEY_MAP_NAMES[..players]
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, keys)| format!("Player {}: {}", idx + 1, keys))
+                        .collect(),
+                };
+                (Some(menu), info)
             }
-            GameState::Game => {
+            Scene::Paused(menu) => {
                 Renderer::draw_text_with_outline(ctx, &self.score_txt, Coords::new(10., 10.));
+                (Some(menu), Vec::new())
             }
+            Scene::GameOver(summary, menu) => (Some(menu), summary.lines()),
+            Scene::EnterName(summary, name, menu) => {
+                let mut info = summary.lines();
+                info.push(format!("Name: {}_", name));
+                (Some(menu), info)
+            }
+            Scene::HighScores(mode, menu) => {
+                (Some(menu), high_score_lines(&self.high_scores, *mode))
+            }
+            Scene::LevelSelect(menu) => {
+                let goal = match menu.current() {
+                    MenuAction::PlayLevel(idx) => {
+                        format!("Goal: {}", self.campaign.levels[idx].level.goal)
+                    }
+                    _ => String::new(),
+                };
+                (Some(menu), vec![goal])
+            }
+            Scene::RoundOver(_, menu) | Scene::MatchOver(_, menu) => {
+                (Some(menu), self.round_lines())
+            }
+            Scene::LevelComplete(summary, menu) => {
+                let mut info = summary.lines();
+                let next = self
+                    .campaign_level
+                    .and_then(|idx| self.campaign.levels.get(idx + 1));
+                if let Some(next) = next {
+                    info.push(format!("Next: {} - {}", next.level.name, next.level.goal));
+                }
+                (Some(menu), info)
+            }
+        };
+        if let Some(menu) = menu {
+            let labels = self.menu_labels(menu);
+            Renderer::draw_menu(ctx, menu, &info, &labels, config, self.resources.font);
         }
 
         graphics::present(ctx)
     }
 
-    fn key_down_event(&mut self, _ctx: &mut Context, keycode: KeyCode, _km: KeyMods, _rpt: bool) {
-        if let Some(dir) = match keycode {
-            KeyCode::W => Some(Direction::Up),
-            KeyCode::S => Some(Direction::Down),
-            KeyCode::A => Some(Direction::Left),
-            KeyCode::D => Some(Direction::Right),
-            _ => None,
-        } {
-            if self.inputs.is_empty() || self.inputs.back().unwrap() != &dir {
-                self.inputs.push_front(dir);
+    fn quit_event(&mut self, _ctx: &mut Context) -> bool {
+        self.save_on_quit();
+        false
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _km: KeyMods, _rpt: bool) {
+        if self.scene() == &Scene::Playing {
+            if let Some((player, dir)) = self.key_direction(keycode) {
+                if let Some(queue) = self.key_queues.get(player) {
+                    queue.push(dir);
+                }
+            } else if keycode == KeyCode::Space {
+                self.key_queues.iter().for_each(InputQueue::clear);
+            } else if keycode == KeyCode::Escape {
+                self.pause();
+            }
+            return;
+        }
+
+        if let Scene::EnterName(..) = self.scene() {
+            // Letters are typed into the name, see `text_input_event`
+            let action = match keycode {
+                KeyCode::Back => {
+                    self.erase_name_char();
+                    None
+                }
+                KeyCode::Up | KeyCode::Down => {
+                    let step = if keycode == KeyCode::Up { -1 } else { 1 };
+                    if let Some(menu) = self.scene_mut().menu_mut() {
+                        menu.move_selection(step);
+                    }
+                    None
+                }
+                KeyCode::Return => self.scene_mut().menu_mut().map(|menu| menu.current()),
+                KeyCode::Escape => self.scene().escape_action(),
+                _ => None,
+            };
+            if let Some(action) = action {
+                self.do_menu_action(ctx, action, 1);
+            }
+            return;
+        }
+
+        let menu = match self.scene_mut().menu_mut() {
+            Some(menu) => menu,
+            None => return,
+        };
+        let setting = |step| Some((menu.current(), step)).filter(|(action, _)| action.is_setting());
+        let action = match keycode {
+            KeyCode::W | KeyCode::Up => {
+                menu.move_selection(-1);
+                None
             }
-        } else if keycode == KeyCode::Space {
-            if self.state == GameState::PreGame {
-                self.state = GameState::Game;
+            KeyCode::S | KeyCode::Down => {
+                menu.move_selection(1);
+                None
             }
-            self.inputs.clear();
+            KeyCode::A | KeyCode::Left => setting(-1),
+            KeyCode::D | KeyCode::Right => setting(1),
+            KeyCode::Return | KeyCode::Space => Some((menu.current(), 1)),
+            KeyCode::Escape => self.scene().escape_action().map(|action| (action, 1)),
+            _ => None,
+        };
+
+        if let Some((action, step)) = action {
+            self.do_menu_action(ctx, action, step);
+        }
+    }
+
+    fn text_input_event(&mut self, _ctx: &mut Context, ch: char) {
+        self.type_name_char(ch);
+    }
+
+    fn focus_event(&mut self, _ctx: &mut Context, gained: bool) {
+        if !gained {
+            self.pause();
         }
     }
 }
@@ -1,11 +1,14 @@
-use crate::game::{consts, food::Food, turn::TurnType};
-use crate::game::{coords::Coords, snake::Snake};
+use crate::game::scene::Menu;
 use ggez::{
-    graphics::{self, Color, FillOptions, Mesh, MeshBuilder, Text},
+    graphics::{self, Color, FillOptions, Font, Mesh, MeshBuilder, Text, TextFragment},
     Context, GameError,
 };
 use graphics::Image;
 use itertools as it;
+use snaek_core::{
+    Collidable, Coords, Direction, Food, GameConfig, Line, Obstacle, Rect, SegmentKind, Snake,
+    Turn, TurnType,
+};
 
 /// Helper struct for various drawing functions.
 /// It helps to draw each type of object in a proper manner.
@@ -15,16 +18,16 @@ impl Renderer {
     /// Draws a tiled background. Given image is scaled down to 50%
     /// and tiled as needed, depending on the screen size.
     ///
-    pub fn draw_bg(ctx: &mut Context, img: &Image) {
+    pub fn draw_bg(ctx: &mut Context, img: &Image, config: &GameConfig) {
         let scale = 0.5;
         let (x_step, y_step) = {
             let dims = img.dimensions();
             ((dims.w * scale) as usize, (dims.h * scale) as usize)
         };
-        (0..consts::SCREEN_SIZE.y as i32)
+        (0..config.screen_height as i32)
             .step_by(x_step)
             .for_each(|yy| {
-                (0..consts::SCREEN_SIZE.x as i32)
+                (0..config.screen_width as i32)
                     .step_by(y_step)
                     .map(|xx| (xx as f32, yy as f32))
                     .for_each(|(x, y)| {
@@ -43,8 +46,8 @@ impl Renderer {
     /// Draws a sprite on the position from the `Food` instance
     ///
     pub fn draw_food(ctx: &mut Context, food: &Food, img: &Image) {
-        let scalex = consts::FOOD_SIZE / img.dimensions().w;
-        let scaley = consts::FOOD_SIZE / img.dimensions().h;
+        let scalex = food.bbox.w / img.dimensions().w;
+        let scaley = food.bbox.h / img.dimensions().h;
         graphics::draw(
             ctx,
             img,
@@ -59,7 +62,7 @@ impl Renderer {
             let mesh = Mesh::new_rectangle(
                 ctx,
                 graphics::DrawMode::stroke(1.),
-                food.bbox,
+                to_ggez_rect(food.bbox),
                 Color::from_rgb(255, 0, 0),
             )
             .unwrap();
@@ -68,11 +71,116 @@ impl Renderer {
         }
     }
 
-    /// Draws whole `LineSnake` structure
+    /// Draws obstacles with the given image tiled over them,
+    /// scaled down to 50% like the background.
     ///
-    pub fn draw_snake(ctx: &mut Context, snake: &Snake) {
-        for segment in &snake.body {
-            segment.draw(ctx);
+    pub fn draw_obstacles(ctx: &mut Context, obstacles: &[Obstacle], img: &Image) {
+        let scale = 0.5;
+        let dims = img.dimensions();
+        for obstacle in obstacles {
+            let bbox = obstacle.bounding_box();
+            let src =
+                graphics::Rect::new(0., 0., bbox.w / (dims.w * scale), bbox.h / (dims.h * scale));
+            graphics::draw(
+                ctx,
+                img,
+                graphics::DrawParam::new()
+                    .src(src)
+                    .dest(Coords::new(bbox.x, bbox.y))
+                    .scale([scale, scale]),
+            )
+            .expect("Error while drawing Obstacle");
+        }
+    }
+
+    /// Draws whole `LineSnake` structure in the given color.
+    /// In the wrap-around arena segments sticking out of the screen
+    /// are drawn again on its opposite side.
+    ///
+    pub fn draw_snake(ctx: &mut Context, snake: &Snake, config: &GameConfig, color: Color) {
+        let screen = Rect::new(0., 0., config.screen_width, config.screen_height);
+        for offset in snake.wrap_offsets() {
+            for segment in &snake.body {
+                let mut segment = segment.clone();
+                segment.translate(offset);
+                if !segment.bounding_box().overlaps(&screen) {
+                    continue;
+                }
+                match segment.kind() {
+                    SegmentKind::Line(line) => Self::draw_line(ctx, line, color),
+                    SegmentKind::Turn(turn) => Self::draw_turn(ctx, turn, config, color),
+                }
+            }
+        }
+    }
+
+    /// Draws straight segment of the snake
+    ///
+    pub fn draw_line(ctx: &mut Context, line: &Line, color: Color) {
+        let mesh = Mesh::new_rectangle(
+            ctx,
+            graphics::DrawMode::fill(),
+            to_ggez_rect(line.bounding_box()),
+            color,
+        )
+        .unwrap();
+        graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+            .expect("Error while drawing Line");
+
+        #[cfg(feature = "debug")]
+        {
+            let mesh = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(1.),
+                to_ggez_rect(line.bounding_box()),
+                Color::from_rgb(255, 0, 0),
+            )
+            .unwrap();
+            graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+                .expect("Error while drawing Line border");
+        }
+    }
+
+    /// Draws curved segment of the snake
+    ///
+    pub fn draw_turn(ctx: &mut Context, turn: &Turn, config: &GameConfig, color: Color) {
+        let turn_type = TurnType::from_dirs(&turn.in_dir, &turn.out_dir);
+        let (margin, is_reversed) = match turn_type {
+            TurnType::DownRight => (Coords { x: 1., y: -1. }, turn.out_dir == Direction::Up),
+            TurnType::DownLeft => (Coords { x: -1., y: -1. }, turn.out_dir == Direction::Left),
+            TurnType::UpLeft => (Coords { x: -1., y: 1. }, turn.out_dir == Direction::Down),
+            TurnType::UpRight => (Coords { x: 1., y: 1. }, turn.out_dir == Direction::Right),
+        };
+
+        let pos = turn.pos
+            + turn.in_dir.as_coords() * (turn.width / 2.)
+            + margin * (config.turn_margin / 2.);
+        if let Ok(mesh) = Self::create_qt_ring(
+            ctx,
+            pos,
+            turn.width + config.turn_margin,
+            config.turn_margin,
+            turn_type,
+            turn.percentage,
+            is_reversed ^ turn.is_growing,
+            true,
+            color,
+        ) {
+            graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+                .expect("Error while drawing Turn");
+        }
+
+        #[cfg(feature = "debug")]
+        {
+            let mesh = Mesh::new_rectangle(
+                ctx,
+                graphics::DrawMode::stroke(1.),
+                to_ggez_rect(turn.bounding_box()),
+                Color::from_rgb(255, 0, 0),
+            )
+            .unwrap();
+            graphics::draw(ctx, &mesh, graphics::DrawParam::default())
+                .expect("Error while drawing Turn border");
         }
     }
 
@@ -85,6 +193,24 @@ impl Renderer {
     /// - `pos`: position of the top left corner of the text
     ///
     pub fn draw_text_with_outline(ctx: &mut Context, txt: &Text, pos: Coords) {
+        Self::draw_colored_text_with_outline(ctx, txt, pos, graphics::WHITE);
+    }
+
+    /// Draws given text in the given color with a black outline
+    ///
+    /// # Parameters
+    ///
+    /// - `ctx`: game context
+    /// - `txt`: the text itself
+    /// - `pos`: position of the top left corner of the text
+    /// - `color`: color of the text fill
+    ///
+    pub fn draw_colored_text_with_outline(
+        ctx: &mut Context,
+        txt: &Text,
+        pos: Coords,
+        color: Color,
+    ) {
         const WIDTH: f32 = 2.;
         [-WIDTH, 0., WIDTH].iter().for_each(|x| {
             [-WIDTH, 0., WIDTH].iter().for_each(|y| {
@@ -99,7 +225,58 @@ impl Renderer {
         });
 
         let params = graphics::DrawParam::default().dest(pos);
-        graphics::draw(ctx, txt, params.color(graphics::WHITE)).expect("Error while drawing score");
+        graphics::draw(ctx, txt, params.color(color)).expect("Error while drawing score");
+    }
+
+    /// Draws a menu in the middle of the screen: its title, given `info` lines
+    /// and `labels` of all of its items, with the selected one highlighted
+    ///
+    pub fn draw_menu(
+        ctx: &mut Context,
+        menu: &Menu,
+        info: &[String],
+        labels: &[String],
+        config: &GameConfig,
+        font: Font,
+    ) {
+        const SPACING: f32 = 8.;
+        let create_txt = |line: &str, scale: f32| {
+            Text::new(
+                TextFragment::new(line)
+                    .scale(graphics::Scale::uniform(scale))
+                    .font(font),
+            )
+        };
+
+        let mut lines = vec![(create_txt(&menu.title, 64.), graphics::WHITE)];
+        lines.extend(
+            info.iter()
+                .map(|line| (create_txt(line, 28.), graphics::WHITE)),
+        );
+        lines.extend(labels.iter().enumerate().map(|(idx, label)| {
+            let color = if idx == menu.selected {
+                Color::from_rgb(255, 255, 0)
+            } else {
+                graphics::WHITE
+            };
+            (create_txt(label, 36.), color)
+        }));
+
+        let dims: Vec<_> = lines
+            .iter()
+            .map(|(txt, _)| {
+                let (w, h) = txt.dimensions(ctx);
+                (w as f32, h as f32)
+            })
+            .collect();
+        let total_height: f32 = dims.iter().map(|(_, h)| h + SPACING).sum();
+
+        let mut y = config.screen_height / 2. - total_height / 2.;
+        for ((txt, color), (w, h)) in lines.iter().zip(dims) {
+            let pos = Coords::new(config.screen_width / 2. - w / 2., y);
+            Self::draw_colored_text_with_outline(ctx, txt, pos, *color);
+            y += h + SPACING;
+        }
     }
 
     /// Draws given text in a black color
@@ -146,6 +323,7 @@ impl Renderer {
     /// - `progress`: how much of a quater should be created, between 0 and 1
     /// - `reversed`: determines from which end the progress is
     /// - `is_head`: whether the segment drawn is a head and we want to draw eyes
+    /// - `color`: color of the ring
     ///
     /// # Returns
     ///
@@ -154,6 +332,7 @@ impl Renderer {
     /// - `Ok`: A `Mesh` that is ready to be drawn
     /// - `Err`: Something went wrong I guess...or You gave a negative radius
     ///
+    #[allow(clippy::too_many_arguments)]
     pub fn create_qt_ring(
         ctx: &mut Context,
         pos: Coords,
@@ -163,6 +342,7 @@ impl Renderer {
         progress: f32,
         reversed: bool,
         is_head: bool,
+        color: Color,
     ) -> Result<Mesh, GameError> {
         if r1 <= 0. || r2 < 0. {
             return Result::Err(GameError::ConfigError(
@@ -183,9 +363,9 @@ impl Renderer {
         let polys = it::chain(outers, it::rev(inners)).collect();
 
         if is_head {
-            create_head(polys, ctx)
+            create_head(polys, ctx, color)
         } else {
-            create_body(polys, ctx)
+            create_body(polys, ctx, color)
         }
     }
 
@@ -199,18 +379,24 @@ impl Renderer {
     }
 }
 
-fn create_body(polys: Vec<Coords>, ctx: &mut Context) -> Result<Mesh, ggez::GameError> {
+/// Convert engine-agnostic `Rect` into the ggez one
+///
+fn to_ggez_rect(rect: Rect) -> graphics::Rect {
+    graphics::Rect::new(rect.x, rect.y, rect.w, rect.h)
+}
+
+fn create_body(polys: Vec<Coords>, ctx: &mut Context, color: Color) -> Result<Mesh, GameError> {
     MeshBuilder::new()
         .polygon(
             graphics::DrawMode::Fill(FillOptions::default()),
             &polys,
-            Color::from_rgb(255, 255, 0),
+            color,
         )
         .unwrap()
         .build(ctx)
 }
 
-fn create_head(polys: Vec<Coords>, ctx: &mut Context) -> Result<Mesh, ggez::GameError> {
+fn create_head(polys: Vec<Coords>, ctx: &mut Context, color: Color) -> Result<Mesh, GameError> {
     let pt1 = polys.first().unwrap();
     let pt2 = polys.last().unwrap();
 
@@ -225,7 +411,7 @@ fn create_head(polys: Vec<Coords>, ctx: &mut Context) -> Result<Mesh, ggez::Game
         .polygon(
             graphics::DrawMode::Fill(FillOptions::default()),
             &polys,
-            Color::from_rgb(255, 255, 0),
+            color,
         )
         .unwrap()
         .circle(
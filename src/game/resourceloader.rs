@@ -1,5 +1,5 @@
 use ggez::{
-    graphics::{Font, Image},
+    graphics::{Font, Image, WrapMode},
     Context,
 };
 
@@ -8,6 +8,7 @@ use ggez::{
 pub struct ResourceLoader {
     pub bg_image: Image,
     pub food_image: Image,
+    pub wall_image: Image,
     pub font: Font,
 }
 
@@ -15,9 +16,12 @@ impl ResourceLoader {
     /// Load all resources and create new instance with them.
     ///
     pub fn new(ctx: &mut Context) -> Self {
+        let mut wall_image = Image::new(ctx, "/wall.png").unwrap();
+        wall_image.set_wrap(WrapMode::Tile, WrapMode::Tile);
         Self {
             bg_image: Image::new(ctx, "/grass.png").unwrap(),
             food_image: Image::new(ctx, "/ball.png").unwrap(),
+            wall_image,
             font: Font::new(ctx, "/Roboto-Black.ttf").unwrap(),
         }
     }
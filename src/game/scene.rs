@@ -0,0 +1,363 @@
+//! Module with the scenes the game can be in
+use snaek_core::{consts::MAX_PLAYERS, Difficulty, GameConfig, GameMode, HighScores};
+
+/// Action triggered by choosing an entry of a `Menu`
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub enum MenuAction {
+    NewGame,
+    NextRound,
+    Resume,
+    Settings,
+    HighScores,
+    Campaign,
+    PlayLevel(usize),
+    NextLevel,
+    MainMenu,
+    Quit,
+    Back,
+    ChangeMode,
+    ChangeSpeed,
+    ChangeStartLen,
+    ChangePlayers,
+    ChangeBots,
+    ChangeDifficulty,
+    /// Switches the mode whose high scores are shown, without changing the settings
+    ViewMode,
+    SaveScore,
+    SkipScore,
+}
+
+impl MenuAction {
+    /// Check if this action changes a setting, i.e. can be used with left/right keys.
+    ///
+    pub fn is_setting(&self) -> bool {
+        matches!(
+            self,
+            MenuAction::ChangeMode
+                | MenuAction::ChangeSpeed
+                | MenuAction::ChangeStartLen
+                | MenuAction::ChangePlayers
+                | MenuAction::ChangeBots
+                | MenuAction::ChangeDifficulty
+                | MenuAction::ViewMode
+        )
+    }
+
+    /// Return text shown for this action in a menu.
+    ///
+    pub fn label(&self, config: &GameConfig) -> String {
+        match self {
+            MenuAction::NewGame => "New game".to_string(),
+            MenuAction::NextRound => "Next round".to_string(),
+            MenuAction::Resume => "Resume".to_string(),
+            MenuAction::Settings => "Settings".to_string(),
+            MenuAction::HighScores => "High scores".to_string(),
+            MenuAction::Campaign => "Campaign".to_string(),
+            MenuAction::PlayLevel(idx) => format!("Level {}", idx + 1),
+            MenuAction::NextLevel => "Next level".to_string(),
+            MenuAction::MainMenu => "Main menu".to_string(),
+            MenuAction::Quit => "Quit".to_string(),
+            MenuAction::Back => "Back".to_string(),
+            MenuAction::ChangeMode => format!("< Mode: {} >", config.mode),
+            MenuAction::ChangeSpeed => format!("< Speed: {} >", config.speed),
+            MenuAction::ChangeStartLen => format!("< Start length: {} >", config.snake_start_len),
+            MenuAction::ChangePlayers => format!("< Players: {} >", config.players),
+            MenuAction::ChangeBots => format!("< AI snakes: {} >", config.bots),
+            MenuAction::ChangeDifficulty => format!("< AI: {} >", config.bot_difficulty),
+            MenuAction::ViewMode => "< Mode >".to_string(),
+            MenuAction::SaveScore => "Save score".to_string(),
+            MenuAction::SkipScore => "Skip".to_string(),
+        }
+    }
+
+    /// Apply a settings change in the given direction (`1` or `-1`) to the `config`.
+    /// Changes that would make the config invalid are rejected.
+    ///
+    pub fn change_setting(&self, config: &mut GameConfig, step: i32) {
+        let mut changed = config.clone();
+        match self {
+            MenuAction::ChangeMode => changed.mode = cycle(&GameMode::ALL, config.mode, step),
+            MenuAction::ChangeSpeed => changed.speed += step as f32 * changed.snake_width,
+            MenuAction::ChangeStartLen => {
+                changed.snake_start_len = (changed.snake_start_len as i32 + step).max(0) as u8
+            }
+            MenuAction::ChangePlayers => {
+                let max = (MAX_PLAYERS - changed.bots).max(1) as i32;
+                let players = changed.players as i32 + step;
+                changed.players = (players - 1).rem_euclid(max) as u8 + 1;
+            }
+            MenuAction::ChangeBots => {
+                let max = (MAX_PLAYERS - changed.players) as i32;
+                let bots = changed.bots as i32 + step;
+                changed.bots = bots.rem_euclid(max + 1) as u8;
+            }
+            MenuAction::ChangeDifficulty => {
+                changed.bot_difficulty = cycle(&Difficulty::ALL, config.bot_difficulty, step)
+            }
+            _ => return,
+        }
+
+        if changed.validate().is_ok() {
+            *config = changed;
+        }
+    }
+}
+
+/// Return the value `step` places away from `current` in `all`, wrapping around.
+///
+pub fn cycle<T: PartialEq + Copy>(all: &[T], current: T, step: i32) -> T {
+    let idx = all.iter().position(|&value| value == current).unwrap_or(0);
+    all[(idx as i32 + step).rem_euclid(all.len() as i32) as usize]
+}
+
+/// List of actions with one of them selected
+///
+#[derive(PartialEq, Clone, Debug)]
+pub struct Menu {
+    pub title: String,
+    pub items: Vec<MenuAction>,
+    pub selected: usize,
+}
+
+impl Menu {
+    /// Create new `Menu` with the first item selected.
+    ///
+    pub fn new(title: &str, items: Vec<MenuAction>) -> Self {
+        Self {
+            title: title.to_string(),
+            items,
+            selected: 0,
+        }
+    }
+
+    /// Move selection by `step` items, wrapping around.
+    ///
+    pub fn move_selection(&mut self, step: i32) {
+        let len = self.items.len() as i32;
+        self.selected = (self.selected as i32 + step).rem_euclid(len) as usize;
+    }
+
+    /// Return currently selected action.
+    ///
+    pub fn current(&self) -> MenuAction {
+        self.items[self.selected]
+    }
+}
+
+/// Summary of a finished game
+///
+#[derive(PartialEq, Clone, Copy, Debug)]
+pub struct GameSummary {
+    pub score: u32,
+    pub length: f32,
+    pub secs: f32,
+    pub seed: u64,
+    pub mode: GameMode,
+}
+
+impl GameSummary {
+    /// Return lines of text describing the finished game.
+    ///
+    pub fn lines(&self) -> Vec<String> {
+        let secs = self.secs as u32;
+        vec![
+            format!("Score: {}", self.score),
+            format!("Length: {:.0}", self.length),
+            format!("Time: {}:{:02}", secs / 60, secs % 60),
+            format!("Seed: {}", self.seed),
+        ]
+    }
+}
+
+/// Return name of the snake under `idx`, telling the players and the bots apart.
+///
+pub fn player_name(config: &GameConfig, idx: usize) -> String {
+    if config.is_bot(idx) {
+        format!("AI {}", idx - config.players as usize + 1)
+    } else {
+        format!("Player {}", idx + 1)
+    }
+}
+
+/// Return lines of text listing the high scores of the given `mode`.
+///
+pub fn high_score_lines(scores: &HighScores, mode: GameMode) -> Vec<String> {
+    let lines: Vec<_> = scores
+        .for_mode(mode)
+        .enumerate()
+        .map(|(idx, entry)| {
+            let secs = entry.secs as u32;
+            format!(
+                "{:>2}. {:<16} {:>5} {:>4.0} {:>3}:{:02} {}",
+                idx + 1,
+                entry.name,
+                entry.score,
+                entry.length,
+                secs / 60,
+                secs % 60,
+                entry.date()
+            )
+        })
+        .collect();
+
+    if lines.is_empty() {
+        vec!["No high scores yet".to_string()]
+    } else {
+        lines
+    }
+}
+
+/// Enumeration of the scenes kept on the scene stack.
+/// Only the top one receives input, the ones below are still drawn.
+///
+#[derive(PartialEq, Clone, Debug)]
+pub enum Scene {
+    MainMenu(Menu),
+    Playing,
+    Paused(Menu),
+    GameOver(GameSummary, Menu),
+    Settings(Menu),
+    /// Name being typed in for a run that made it into the high score table
+    EnterName(GameSummary, String, Menu),
+    /// High score table of the given mode, picked independently of the settings
+    HighScores(GameMode, Menu),
+    LevelSelect(Menu),
+    LevelComplete(GameSummary, Menu),
+    /// Round of a match with more players is over, won by the given player if any
+    RoundOver(Option<usize>, Menu),
+    /// The given player won enough rounds to win the whole match
+    MatchOver(usize, Menu),
+}
+
+impl Scene {
+    pub fn main_menu() -> Self {
+        Scene::MainMenu(Menu::new(
+            "snaek_rust",
+            vec![
+                MenuAction::NewGame,
+                MenuAction::Campaign,
+                MenuAction::HighScores,
+                MenuAction::Settings,
+                MenuAction::Quit,
+            ],
+        ))
+    }
+
+    pub fn paused() -> Self {
+        Scene::Paused(Menu::new(
+            "Paused",
+            vec![MenuAction::Resume, MenuAction::MainMenu, MenuAction::Quit],
+        ))
+    }
+
+    pub fn game_over(summary: GameSummary) -> Self {
+        Scene::GameOver(
+            summary,
+            Menu::new(
+                "Game over",
+                vec![MenuAction::NewGame, MenuAction::MainMenu, MenuAction::Quit],
+            ),
+        )
+    }
+
+    pub fn level_complete(summary: GameSummary, items: Vec<MenuAction>) -> Self {
+        Scene::LevelComplete(summary, Menu::new("Level complete", items))
+    }
+
+    pub fn round_over(winner: Option<usize>, config: &GameConfig) -> Self {
+        let title = match winner {
+            Some(winner) => format!("{} wins the round", player_name(config, winner)),
+            None => "Draw".to_string(),
+        };
+        Scene::RoundOver(
+            winner,
+            Menu::new(&title, vec![MenuAction::NextRound, MenuAction::MainMenu]),
+        )
+    }
+
+    pub fn match_over(winner: usize, config: &GameConfig) -> Self {
+        Scene::MatchOver(
+            winner,
+            Menu::new(
+                &format!("{} wins!", player_name(config, winner)),
+                vec![MenuAction::NewGame, MenuAction::MainMenu, MenuAction::Quit],
+            ),
+        )
+    }
+
+    pub fn level_select(level_count: usize) -> Self {
+        let mut items: Vec<_> = (0..level_count).map(MenuAction::PlayLevel).collect();
+        items.push(MenuAction::Back);
+        Scene::LevelSelect(Menu::new("Campaign", items))
+    }
+
+    pub fn settings() -> Self {
+        Scene::Settings(Menu::new(
+            "Settings",
+            vec![
+                MenuAction::ChangeMode,
+                MenuAction::ChangeSpeed,
+                MenuAction::ChangeStartLen,
+                MenuAction::ChangePlayers,
+                MenuAction::ChangeBots,
+                MenuAction::ChangeDifficulty,
+                MenuAction::Back,
+            ],
+        ))
+    }
+
+    pub fn enter_name(summary: GameSummary) -> Self {
+        Scene::EnterName(
+            summary,
+            String::new(),
+            Menu::new(
+                "New high score!",
+                vec![MenuAction::SaveScore, MenuAction::SkipScore],
+            ),
+        )
+    }
+
+    pub fn high_scores(mode: GameMode) -> Self {
+        Scene::HighScores(
+            mode,
+            Menu::new("High scores", vec![MenuAction::ViewMode, MenuAction::Back]),
+        )
+    }
+
+    /// Return the menu shown by this scene, if any.
+    ///
+    pub fn menu_mut(&mut self) -> Option<&mut Menu> {
+        match self {
+            Scene::MainMenu(menu)
+            | Scene::Paused(menu)
+            | Scene::GameOver(_, menu)
+            | Scene::Settings(menu)
+            | Scene::EnterName(_, _, menu)
+            | Scene::HighScores(_, menu)
+            | Scene::LevelSelect(menu)
+            | Scene::LevelComplete(_, menu)
+            | Scene::RoundOver(_, menu)
+            | Scene::MatchOver(_, menu) => Some(menu),
+            Scene::Playing => None,
+        }
+    }
+
+    /// Action taken when Esc is pressed in this menu scene.
+    ///
+    pub fn escape_action(&self) -> Option<MenuAction> {
+        match self {
+            Scene::MainMenu(_) => Some(MenuAction::Quit),
+            Scene::Playing => None,
+            Scene::Paused(_) => Some(MenuAction::Resume),
+            Scene::GameOver(..) => Some(MenuAction::MainMenu),
+            Scene::Settings(_) | Scene::HighScores(..) | Scene::LevelSelect(_) => {
+                Some(MenuAction::Back)
+            }
+            Scene::LevelComplete(..) | Scene::RoundOver(..) | Scene::MatchOver(..) => {
+                Some(MenuAction::MainMenu)
+            }
+            Scene::EnterName(..) => Some(MenuAction::SkipScore),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::time::Instant;
+
+use ggez::{
+    event::{self, EventHandler, KeyCode, KeyMods},
+    graphics::{self, Font, Text, TextFragment},
+    Context, GameResult,
+};
+use snaek_core::{Coords, FixedTimestep, Spectator};
+
+use crate::{
+    game::{renderer::Renderer, resourceloader::ResourceLoader, GameData},
+    online,
+};
+
+/// Window watching the matches streamed by a broadcaster, playing them back
+/// at their own pace. Left and right arrows switch between the matches.
+///
+pub struct SpectatorView {
+    spectator: Spectator,
+    resources: ResourceLoader,
+    timestep: FixedTimestep,
+    delta_time: Instant,
+    /// Match asked for, which may not have started streaming yet
+    requested: Option<u64>,
+    /// Match, its score, whether it is over and the number of matches shown by the texts
+    shown: Option<(u64, u32, bool, usize)>,
+    score_txt: Text,
+    info_txt: Text,
+}
+
+impl SpectatorView {
+    /// Creates new `SpectatorView` instance, which starts with the first
+    /// match still being played once the broadcaster lists it.
+    ///
+    pub fn new(ctx: &mut Context, spectator: Spectator) -> Self {
+        graphics::set_default_filter(ctx, graphics::FilterMode::Nearest);
+        let resources = ResourceLoader::new(ctx);
+        let info_txt = Self::text("Waiting for matches...", resources.font);
+        Self {
+            spectator,
+            resources,
+            timestep: FixedTimestep::new(1),
+            delta_time: Instant::now(),
+            requested: None,
+            shown: None,
+            score_txt: Text::default(),
+            info_txt,
+        }
+    }
+
+    fn text(text: &str, font: Font) -> Text {
+        Text::new(
+            TextFragment::new(text)
+                .scale(graphics::Scale::uniform(24.))
+                .font(font),
+        )
+    }
+
+    /// Switches `step` matches further down the list, wrapping around.
+    ///
+    fn switch(&mut self, step: isize) -> GameResult {
+        let matches = self.spectator.matches();
+        if matches.is_empty() {
+            return Ok(());
+        }
+        let current = self
+            .requested
+            .and_then(|id| matches.iter().position(|info| info.id == id))
+            .unwrap_or(0);
+        let next = (current as isize + step).rem_euclid(matches.len() as isize) as usize;
+        let id = matches[next].id;
+        self.requested = Some(id);
+        self.spectator.watch(id).map_err(online::game_error)
+    }
+
+    /// Rebuilds the texts and the view once a new match shows up or the score changes.
+    ///
+    fn refresh(&mut self, ctx: &mut Context) -> GameResult {
+        let (id, name) = match self.spectator.watching() {
+            Some(watching) => watching,
+            None => return Ok(()),
+        };
+        let sim = self
+            .spectator
+            .sim()
+            .expect("watched match has a simulation");
+        let matches = self.spectator.matches();
+        let state = (
+            id,
+            sim.food_eaten(),
+            self.spectator.is_over(),
+            matches.len(),
+        );
+        if self.shown == Some(state) {
+            return Ok(());
+        }
+
+        if self.shown.map(|(shown, ..)| shown) != Some(id) {
+            let config = &sim.config;
+            self.timestep = FixedTimestep::new(config.ticks_per_sec);
+            graphics::set_drawable_size(ctx, config.screen_width, config.screen_height)?;
+            graphics::set_screen_coordinates(
+                ctx,
+                graphics::Rect::new(0., 0., config.screen_width, config.screen_height),
+            )?;
+        }
+        let position = matches.iter().position(|info| info.id == id).unwrap_or(0);
+        let mut info = format!("{}/{}: {}", position + 1, matches.len().max(1), name);
+        if state.2 {
+            info.push_str(" - over");
+        }
+        self.info_txt = Self::text(&info, self.resources.font);
+        self.score_txt =
+            GameData::create_score_txt(sim, self.spectator.roster(), self.resources.font);
+        self.shown = Some(state);
+        Ok(())
+    }
+}
+
+impl EventHandler for SpectatorView {
+    fn update(&mut self, ctx: &mut Context) -> GameResult<()> {
+        let prev_time = std::mem::replace(&mut self.delta_time, Instant::now());
+        let time_delta = self.delta_time.duration_since(prev_time).as_secs_f32();
+
+        if let Err(err) = self.spectator.receive() {
+            eprintln!("{}", err);
+            event::quit(ctx);
+            return Ok(());
+        }
+        if self.requested.is_none() {
+            let first = self
+                .spectator
+                .matches()
+                .iter()
+                .find(|info| !info.over)
+                .or_else(|| self.spectator.matches().first())
+                .map(|info| info.id);
+            if let Some(id) = first {
+                self.requested = Some(id);
+                self.spectator.watch(id).map_err(online::game_error)?;
+            }
+        }
+
+        for _ in 0..self.timestep.advance(time_delta) {
+            if self.spectator.advance().is_none() {
+                break;
+            }
+        }
+        self.refresh(ctx)
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult<()> {
+        graphics::clear(ctx, graphics::BLACK);
+        if let Some(sim) = self.spectator.sim() {
+            let config = &sim.config;
+            Renderer::draw_bg(ctx, &self.resources.bg_image, config);
+            Renderer::draw_obstacles(ctx, &config.obstacles, &self.resources.wall_image);
+            let alpha = self.timestep.alpha();
+            for (idx, player) in sim.players.iter().enumerate() {
+                if player.alive || sim.players.len() == 1 {
+                    let color = GameData::player_color(self.spectator.roster(), idx);
+                    Renderer::draw_snake(ctx, &player.interpolated_snake(alpha), config, color);
+                }
+            }
+            if let Some(food) = &sim.food {
+                Renderer::draw_food(ctx, food, &self.resources.food_image);
+            }
+            Renderer::draw_text_with_outline(ctx, &self.score_txt, Coords::new(10., 10.));
+        }
+        Renderer::draw_text_with_outline(ctx, &self.info_txt, Coords::new(10., 40.));
+        graphics::present(ctx)
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _km: KeyMods, _rpt: bool) {
+        let switched = match keycode {
+            KeyCode::Left | KeyCode::A => self.switch(-1),
+            KeyCode::Right | KeyCode::D => self.switch(1),
+            KeyCode::Escape => {
+                event::quit(ctx);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(err) = switched {
+            eprintln!("{}", err);
+            event::quit(ctx);
+        }
+    }
+}
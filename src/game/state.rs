@@ -1,128 +1,792 @@
-use std::{collections::VecDeque, time::Instant};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
 use ggez::{
-    graphics::{self, Font, Text, TextFragment},
+    event::{self, KeyCode},
+    filesystem,
+    graphics::{self, Color, Font, Text, TextFragment},
     Context,
 };
-use itertools::{self as it, Itertools};
-
-use crate::game::snake::Snake;
-use crate::game::{consts, direction::Direction, food::Food, resourceloader::ResourceLoader};
+use snaek_core::{
+    ai,
+    consts::{MAX_NAME_LEN, SNAKE_COLORS},
+    controller,
+    lobby::Member,
+    rng, Campaign, CampaignProgress, Controller, Direction, FixedTimestep, GameConfig, GameMode,
+    HighScore, HighScores, InputQueue, Level, LevelStatus, Replay, SavedGame, Scripted, Simulation,
+    StepEvent,
+};
 
-#[derive(PartialEq, Clone, Copy, Debug)]
-pub enum GameState {
-    PreGame,
-    Game,
-}
+use crate::{
+    game::{
+        consts::{CAMPAIGN_PROGRESS_FILE, HIGH_SCORES_FILE, KEY_MAPS, ROUNDS_TO_WIN},
+        resourceloader::ResourceLoader,
+        scene::{self, player_name, GameSummary, Menu, MenuAction, Scene},
+    },
+    online::OnlineClient,
+};
 
 /// Structure for holding game data, managing player input
-/// and updating objects.
+/// and feeding it into the simulation.
 ///
 pub struct GameData {
-    pub snake: Snake,
-    pub food: Food,
+    pub sim: Simulation,
+    pub fixed_seed: Option<u64>,
+    pub record_path: Option<PathBuf>,
+    /// File the game in progress is saved into on quitting
+    pub save_path: Option<PathBuf>,
+    pub recording: Option<Replay>,
+    pub playback: Option<Replay>,
+    pub level: Option<Level>,
+    pub campaign: Campaign,
+    pub progress: CampaignProgress,
+    pub progress_path: PathBuf,
+    /// Index of the campaign level being played
+    pub campaign_level: Option<usize>,
+    /// Config used outside of the campaign, restored when leaving it
+    pub base_config: GameConfig,
+    pub timestep: FixedTimestep,
     pub delta_time: std::time::Instant,
-    pub inputs: VecDeque<Direction>,
-    pub input_timer: f32,
-    pub score: u32,
+    /// Controllers of all snakes, in the order of the snakes
+    pub controllers: Vec<Box<dyn Controller>>,
+    /// Directions typed in by each player, shared with their controllers
+    pub key_queues: Vec<InputQueue>,
+    /// Connection to the server of the online game being played
+    pub online: Option<OnlineClient>,
+    /// Names and colors the players of the online game picked in the lobby
+    pub roster: Vec<Member>,
     pub score_txt: Text,
-    pub pregame_txt: Text,
-    pub state: GameState,
+    /// Rounds won by each player in the current match, when there are more players
+    pub round_wins: Vec<u32>,
+    pub scenes: Vec<Scene>,
+    pub high_scores: HighScores,
+    pub high_scores_path: PathBuf,
     pub resources: ResourceLoader,
 }
 
 impl GameData {
-    /// Creates new `GameData` instance. Loads game resources.
+    /// Creates new `GameData` instance. Loads game resources
+    /// and high scores and campaign progress kept in the user data directory.
     ///
-    /// Snake is created on the middle of the screen.
+    /// When `fixed_seed` is given, every game is played with it,
+    /// otherwise each game gets a fresh random seed.
     ///
-    pub fn new(ctx: &mut Context) -> Self {
+    pub fn new(ctx: &mut Context, config: GameConfig, fixed_seed: Option<u64>) -> Self {
         graphics::set_default_filter(ctx, graphics::FilterMode::Nearest);
         let resources = ResourceLoader::new(ctx);
+        let sim = Simulation::new(config, fixed_seed.unwrap_or_else(rng::random_seed));
+        let high_scores_path = filesystem::user_data_dir(ctx).join(HIGH_SCORES_FILE);
+        let progress_path = filesystem::user_data_dir(ctx).join(CAMPAIGN_PROGRESS_FILE);
         Self {
-            snake: Snake::new(consts::SCREEN_SIZE.x / 2.0, consts::SCREEN_SIZE.y / 2.0),
+            fixed_seed,
+            record_path: None,
+            save_path: None,
+            recording: None,
+            playback: None,
+            level: None,
+            campaign: Campaign::default(),
+            progress: loaded(
+                &progress_path,
+                CampaignProgress::load_or_default(&progress_path),
+            ),
+            progress_path,
+            campaign_level: None,
+            base_config: sim.config.clone(),
+            timestep: FixedTimestep::new(sim.config.ticks_per_sec),
             delta_time: Instant::now(),
-            food: Food::random(),
-            inputs: VecDeque::new(),
-            input_timer: 0.0,
-            score: 0,
-            score_txt: Self::create_score_txt(0, resources.font),
-            pregame_txt: Self::create_pregame_txt(resources.font),
-            state: GameState::PreGame,
+            controllers: Vec::new(),
+            key_queues: Vec::new(),
+            online: None,
+            roster: Vec::new(),
+            score_txt: Self::create_score_txt(&sim, &[], resources.font),
+            round_wins: Vec::new(),
+            sim,
+            scenes: vec![Scene::main_menu()],
+            high_scores: loaded(
+                &high_scores_path,
+                HighScores::load_or_default(&high_scores_path),
+            ),
+            high_scores_path,
             resources,
         }
     }
 
+    /// Return seed of the current game.
+    ///
+    pub fn seed(&self) -> u64 {
+        self.sim.seed
+    }
+
+    /// Record every game played and save it under `path` once it is over.
+    ///
+    pub fn record_to(&mut self, path: PathBuf) {
+        self.record_path = Some(path);
+    }
+
+    /// Save the game in progress under `path` on quitting, and resume
+    /// the one saved there before, if there is any.
+    ///
+    pub fn save_to(&mut self, ctx: &mut Context, path: PathBuf) {
+        if path.exists() {
+            let saved = match fs::read(&path) {
+                Ok(bytes) => SavedGame::from_bytes(&bytes).map_err(|err| err.to_string()),
+                Err(err) => Err(err.to_string()),
+            };
+            match saved {
+                Ok(saved) => self.resume(ctx, saved),
+                Err(err) => eprintln!("{}: {}", path.display(), err),
+            }
+        }
+        self.save_path = Some(path);
+    }
+
+    /// Return what is needed to resume the game being played.
+    ///
+    pub fn saved_game(&self) -> SavedGame {
+        SavedGame {
+            sim: self.sim.clone(),
+            fixed_seed: self.fixed_seed,
+            level: self.level.clone(),
+            campaign_level: self.campaign_level,
+            progress: self.progress.clone(),
+            base_config: self.base_config.clone(),
+            roster: self.roster.clone(),
+            round_wins: self.round_wins.clone(),
+            recording: self.recording.clone(),
+            playback: self.playback.clone(),
+        }
+    }
+
+    /// Resume the `saved` game, paused.
+    ///
+    pub fn resume(&mut self, ctx: &mut Context, saved: SavedGame) {
+        self.set_config(ctx, saved.sim.config.clone());
+        self.sim = saved.sim;
+        self.fixed_seed = saved.fixed_seed;
+        self.level = saved.level;
+        self.campaign_level = saved
+            .campaign_level
+            .filter(|&idx| idx < self.campaign.levels.len());
+        self.progress = saved.progress;
+        self.base_config = saved.base_config;
+        self.roster = saved.roster;
+        self.round_wins = saved.round_wins;
+        self.recording = saved.recording;
+        self.playback = saved.playback;
+        match &self.playback {
+            Some(replay) => self.controllers = Scripted::all_from_replay(replay),
+            None => self.create_controllers(),
+        }
+        self.score_txt = Self::create_score_txt(&self.sim, &self.roster, self.resources.font);
+        self.scenes = vec![Scene::Playing, Scene::paused()];
+    }
+
+    /// Saves the game if it is still being played and there is a file
+    /// to save it into, or removes the game saved there before.
+    ///
+    pub fn save_on_quit(&self) {
+        if let Some(path) = &self.save_path {
+            let in_progress =
+                self.scenes[0] == Scene::Playing && self.online.is_none() && !self.sim.is_over();
+            let saved = match in_progress {
+                true => fs::write(path, self.saved_game().to_bytes()),
+                false if path.exists() => fs::remove_file(path),
+                false => Ok(()),
+            };
+            if let Err(err) = saved {
+                eprintln!("Could not save the game into {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    /// Play back given `replay` instead of taking directions from the keyboard.
+    ///
+    pub fn play_replay(&mut self, replay: Replay) {
+        self.sim = replay.simulation();
+        self.timestep = FixedTimestep::new(self.sim.config.ticks_per_sec);
+        self.controllers = Scripted::all_from_replay(&replay);
+        self.playback = Some(replay);
+    }
+
+    /// Play the online game of the `client`, starting right away, with the players
+    /// named and colored as in the `roster` of the lobby, if there was one.
+    /// Keys of every key map steer the client's snake.
+    ///
+    pub fn play_online(&mut self, client: OnlineClient, sim: Simulation, roster: Vec<Member>) {
+        self.sim = sim;
+        self.roster = roster;
+        self.timestep = FixedTimestep::new(self.sim.config.ticks_per_sec);
+        self.key_queues = vec![InputQueue::new()];
+        self.controllers = Vec::new();
+        self.round_wins = vec![0; self.sim.players.len()];
+        self.score_txt = Self::create_score_txt(&self.sim, &self.roster, self.resources.font);
+        self.scenes = vec![Scene::Playing];
+        self.online = Some(client);
+    }
+
+    /// Play given `level`, which ends once its goal is reached.
+    /// Config of the simulation is expected to be created from it.
+    ///
+    pub fn play_level(&mut self, level: Level) {
+        self.level = Some(level);
+    }
+
+    /// Load campaign from the campaign file under `path`.
+    /// The game can be still played without it, if it is missing or broken.
+    ///
+    pub fn load_campaign(&mut self, path: &Path) {
+        match Campaign::load(path) {
+            Ok(campaign) => self.campaign = campaign,
+            Err(err) => eprintln!("{}: {}", path.display(), err),
+        }
+    }
+
+    /// Return the level being played, either one of the campaign or the one given upfront.
+    ///
+    pub fn current_level(&self) -> Option<&Level> {
+        match self.campaign_level {
+            Some(idx) => Some(&self.campaign.levels[idx].level),
+            None => self.level.as_ref(),
+        }
+    }
+
+    /// Return the scene on top of the stack, the one receiving input.
+    ///
+    pub fn scene(&self) -> &Scene {
+        self.scenes.last().expect("Scene stack is never empty")
+    }
+
+    /// Return the scene on top of the stack, the one receiving input.
+    ///
+    pub fn scene_mut(&mut self) -> &mut Scene {
+        self.scenes.last_mut().expect("Scene stack is never empty")
+    }
+
+    /// Pauses the game, if it is being played. Online games go on
+    /// for the other players, so they cannot be paused.
+    ///
+    pub fn pause(&mut self) {
+        if self.scene() == &Scene::Playing && self.online.is_none() {
+            self.scenes.push(Scene::paused());
+        }
+    }
+
+    /// Return the color of the snake of the given player, the one
+    /// picked in the lobby if the player is in the `roster`.
+    ///
+    pub fn player_color(roster: &[Member], player: usize) -> Color {
+        let color = roster
+            .get(player)
+            .map_or(player, |member| member.color as usize);
+        let (r, g, b) = SNAKE_COLORS[color % SNAKE_COLORS.len()];
+        Color::from_rgb(r, g, b)
+    }
+
+    /// Return the player and the direction steered with the given key.
+    /// Single player can use any of the key maps, bots cannot be steered.
+    ///
+    pub fn key_direction(&self, keycode: KeyCode) -> Option<(usize, Direction)> {
+        let dirs = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        let (player, dir) = KEY_MAPS.iter().enumerate().find_map(|(player, keys)| {
+            let idx = keys.iter().position(|&key| key == keycode)?;
+            Some((player, dirs[idx]))
+        })?;
+        if self.online.is_some() {
+            return Some((0, dir));
+        }
+        match self.sim.config.players as usize {
+            1 => Some((0, dir)),
+            len if player < len => Some((player, dir)),
+            _ => None,
+        }
+    }
+
+    /// Starts a fresh game, a whole new match when there are more players.
+    ///
+    pub fn new_game(&mut self) {
+        self.round_wins = vec![0; self.sim.players.len()];
+        self.start_round();
+    }
+
+    /// Starts a fresh round of the match, or the game if there is a single player,
+    /// and begins recording it, if requested.
+    ///
+    pub fn start_round(&mut self) {
+        self.reset();
+        self.scenes = vec![Scene::Playing];
+        if self.record_path.is_some() {
+            self.recording = Some(Replay::new(&self.sim));
+        }
+    }
+
+    /// Performs given menu `action`. Settings are changed in the `step` direction.
+    ///
+    pub fn do_menu_action(&mut self, ctx: &mut Context, action: MenuAction, step: i32) {
+        match action {
+            MenuAction::NewGame => self.new_game(),
+            MenuAction::NextRound => self.start_round(),
+            MenuAction::Resume | MenuAction::Back => {
+                self.scenes.pop();
+            }
+            MenuAction::Settings => self.scenes.push(Scene::settings()),
+            MenuAction::HighScores => self.scenes.push(Scene::high_scores(self.sim.config.mode)),
+            MenuAction::MainMenu => {
+                self.recording = None;
+                self.leave_campaign(ctx);
+                self.reset();
+                self.scenes = vec![Scene::main_menu()];
+            }
+            MenuAction::Campaign => {
+                if self.playback.is_none() {
+                    self.scenes
+                        .push(Scene::level_select(self.campaign.levels.len()));
+                }
+            }
+            MenuAction::PlayLevel(idx) => {
+                if self.progress.status(&self.campaign, idx) != LevelStatus::Locked {
+                    self.start_campaign_level(ctx, idx);
+                }
+            }
+            MenuAction::NextLevel => {
+                if let Some(idx) = self.campaign_level {
+                    self.start_campaign_level(ctx, idx + 1);
+                }
+            }
+            MenuAction::Quit => {
+                self.save_on_quit();
+                event::quit(ctx);
+            }
+            MenuAction::ChangeMode
+            | MenuAction::ChangeSpeed
+            | MenuAction::ChangeStartLen
+            | MenuAction::ChangePlayers
+            | MenuAction::ChangeBots
+            | MenuAction::ChangeDifficulty => {
+                if self.playback.is_none() {
+                    action.change_setting(&mut self.sim.config, step);
+                    self.reset();
+                }
+            }
+            MenuAction::ViewMode => {
+                if let Scene::HighScores(mode, _) = self.scene_mut() {
+                    *mode = scene::cycle(&GameMode::ALL, *mode, step);
+                }
+            }
+            MenuAction::SaveScore => {
+                if let Scene::EnterName(summary, name, _) = self.scene() {
+                    let summary = *summary;
+                    let name = match name.trim() {
+                        "" => "Player".to_string(),
+                        name => name.to_string(),
+                    };
+                    self.save_high_score(summary, name);
+                    self.scenes.pop();
+                    self.scenes.push(Scene::game_over(summary));
+                }
+            }
+            MenuAction::SkipScore => {
+                if let Scene::EnterName(summary, ..) = self.scene() {
+                    let summary = *summary;
+                    self.scenes.pop();
+                    self.scenes.push(Scene::game_over(summary));
+                }
+            }
+        }
+    }
+
+    /// Return labels of the `menu` items, describing campaign levels with their progress.
+    ///
+    pub fn menu_labels(&self, menu: &Menu) -> Vec<String> {
+        menu.items
+            .iter()
+            .map(|item| match *item {
+                MenuAction::PlayLevel(idx) => {
+                    let level = &self.campaign.levels[idx];
+                    let status = match self.progress.status(&self.campaign, idx) {
+                        LevelStatus::Locked => "locked".to_string(),
+                        LevelStatus::Unlocked => "new".to_string(),
+                        LevelStatus::Completed => {
+                            format!("best {}", self.progress.completed[&level.id].best_score)
+                        }
+                    };
+                    format!("{}. {} ({})", idx + 1, level.level.name, status)
+                }
+                MenuAction::ViewMode => match self.scene() {
+                    Scene::HighScores(mode, _) => format!("< Mode: {} >", mode),
+                    _ => item.label(&self.sim.config),
+                },
+                _ => item.label(&self.sim.config),
+            })
+            .collect()
+    }
+
+    fn start_campaign_level(&mut self, ctx: &mut Context, idx: usize) {
+        let level = match self.campaign.levels.get(idx) {
+            Some(level) => level,
+            None => return,
+        };
+        if self.campaign_level.is_none() {
+            self.base_config = self.sim.config.clone();
+        }
+        match level.level.config(&self.base_config) {
+            Ok(config) => {
+                self.campaign_level = Some(idx);
+                self.set_config(ctx, config);
+                self.new_game();
+            }
+            Err(err) => eprintln!("{}: {}", level.id, err),
+        }
+    }
+
+    fn leave_campaign(&mut self, ctx: &mut Context) {
+        if self.campaign_level.take().is_some() {
+            self.set_config(ctx, self.base_config.clone());
+        }
+    }
+
+    /// Switches to the given `config`, resizing the arena to fit the window.
+    ///
+    fn set_config(&mut self, ctx: &mut Context, config: GameConfig) {
+        let screen = graphics::Rect::new(0., 0., config.screen_width, config.screen_height);
+        if let Err(err) = graphics::set_screen_coordinates(ctx, screen) {
+            eprintln!("Could not resize the arena: {}", err);
+        }
+        self.timestep = FixedTimestep::new(config.ticks_per_sec);
+        self.sim.config = config;
+    }
+
+    fn complete_level(&mut self) {
+        println!("Level complete! Score: {}", self.sim.players[0].score);
+        self.finish_replays();
+        let summary = self.summary();
+        let items = match self.campaign_level {
+            Some(idx) => {
+                let id = &self.campaign.levels[idx].id;
+                self.progress.complete(id, summary.score, summary.secs);
+                if let Err(err) = self.progress.save(&self.progress_path) {
+                    eprintln!(
+                        "Could not save campaign progress to {}: {}",
+                        self.progress_path.display(),
+                        err
+                    );
+                }
+                if idx + 1 < self.campaign.levels.len() {
+                    vec![
+                        MenuAction::NextLevel,
+                        MenuAction::Campaign,
+                        MenuAction::MainMenu,
+                    ]
+                } else {
+                    vec![MenuAction::Campaign, MenuAction::MainMenu]
+                }
+            }
+            None => vec![MenuAction::NewGame, MenuAction::MainMenu, MenuAction::Quit],
+        };
+        self.scenes.push(Scene::level_complete(summary, items));
+    }
+
+    /// Appends `ch` to the name being typed in, if it is allowed in names.
+    ///
+    pub fn type_name_char(&mut self, ch: char) {
+        if let Scene::EnterName(_, name, _) = self.scene_mut() {
+            let allowed = ch.is_alphanumeric() || ch == '-' || ch == '_' || ch == ' ';
+            if allowed && name.chars().count() < MAX_NAME_LEN {
+                name.push(ch);
+            }
+        }
+    }
+
+    /// Removes the last character of the name being typed in.
+    ///
+    pub fn erase_name_char(&mut self) {
+        if let Scene::EnterName(_, name, _) = self.scene_mut() {
+            name.pop();
+        }
+    }
+
+    fn save_high_score(&mut self, summary: GameSummary, name: String) {
+        self.high_scores.insert(HighScore {
+            name,
+            score: summary.score,
+            length: summary.length,
+            secs: summary.secs,
+            seed: summary.seed,
+            mode: summary.mode,
+            timestamp: HighScore::now(),
+        });
+        if let Err(err) = self.high_scores.save(&self.high_scores_path) {
+            eprintln!(
+                "Could not save high scores to {}: {}",
+                self.high_scores_path.display(),
+                err
+            );
+        }
+    }
+
     fn reset(&mut self) {
-        self.snake = Snake::new(consts::SCREEN_SIZE.x / 2.0, consts::SCREEN_SIZE.y / 2.0);
-        self.food = Food::random();
-        while self.snake.collide(&self.food.bbox) {
-            self.food = Food::random();
+        match &self.playback {
+            Some(replay) => {
+                self.sim = replay.simulation();
+                self.controllers = Scripted::all_from_replay(replay);
+            }
+            None => {
+                self.sim
+                    .reset(self.fixed_seed.unwrap_or_else(rng::random_seed));
+                self.create_controllers();
+            }
         }
-        self.inputs.clear();
-        self.score = 0;
-        self.score_txt = Self::create_score_txt(0, self.resources.font);
-        self.state = GameState::PreGame;
+        self.roster.clear();
+        self.score_txt = Self::create_score_txt(&self.sim, &self.roster, self.resources.font);
     }
 
-    fn inc_score(&mut self) {
-        self.score += 1;
-        self.score_txt = Self::create_score_txt(self.score, self.resources.font);
+    /// Give each player a fresh queue of typed in directions,
+    /// followed by the bots steering the rest of the snakes.
+    ///
+    fn create_controllers(&mut self) {
+        self.key_queues = (0..self.sim.config.players)
+            .map(|_| InputQueue::new())
+            .collect();
+        self.controllers = self
+            .key_queues
+            .iter()
+            .map(|queue| Box::new(queue.clone()) as Box<dyn Controller>)
+            .chain(ai::bots(&self.sim.config))
+            .collect();
     }
 
-    fn create_score_txt(score: u32, font: Font) -> Text {
-        Text::new(
-            TextFragment::new(format!(SCORE_FMT!(), score))
+    /// Create text with the score, or the scores of all players in their colors,
+    /// under the names from the `roster` of the lobby, if there was one.
+    ///
+    pub fn create_score_txt(sim: &Simulation, roster: &[Member], font: Font) -> Text {
+        let fragment = |text: String| {
+            TextFragment::new(text)
                 .scale(graphics::Scale::uniform(24.))
-                .font(font),
-        )
+                .font(font)
+        };
+        if sim.players.len() == 1 {
+            return Text::new(fragment(format!(SCORE_FMT!(), sim.players[0].score)));
+        }
+
+        let mut txt = Text::default();
+        for (idx, player) in sim.players.iter().enumerate() {
+            let name = roster.get(idx).map_or_else(
+                || player_name(&sim.config, idx),
+                |member| member.name.clone(),
+            );
+            let text = format!("{}: {}  ", name, player.score);
+            txt.add(fragment(text).color(Self::player_color(roster, idx)));
+        }
+        txt
     }
-    fn create_pregame_txt(font: Font) -> Text {
-        Text::new(
-            TextFragment::new(consts::PREGAME_TXT)
-                .scale(graphics::Scale::uniform(64.))
-                .font(font),
-        )
+
+    /// Return lines of text with the rounds won and the score of each player.
+    ///
+    pub fn round_lines(&self) -> Vec<String> {
+        self.sim
+            .players
+            .iter()
+            .zip(&self.round_wins)
+            .enumerate()
+            .map(|(idx, (player, wins))| {
+                format!(
+                    "{}: {} of {} rounds, score {}",
+                    player_name(&self.sim.config, idx),
+                    wins,
+                    ROUNDS_TO_WIN,
+                    player.score
+                )
+            })
+            .collect()
     }
 
-    /// Processes user input, capped to `consts::SECS_PER_INPUT_UPDATE`.
+    /// Ends the round of the match, crediting its winner,
+    /// and shows either the next round or the winner of the match.
     ///
-    /// The cap is there to make sure that 180 turns always makes enough
-    /// space between both parts of the snake.
+    fn end_round(&mut self) {
+        self.finish_replays();
+        let winner = self.sim.winner();
+        if let Some(winner) = winner {
+            self.round_wins[winner] += 1;
+            if self.round_wins[winner] >= ROUNDS_TO_WIN {
+                println!("{} wins the match!", player_name(&self.sim.config, winner));
+                self.scenes
+                    .push(Scene::match_over(winner, &self.sim.config));
+                return;
+            }
+        }
+        match winner {
+            Some(winner) => println!("{} wins the round!", player_name(&self.sim.config, winner)),
+            None => println!("Round is a draw!"),
+        }
+        self.scenes
+            .push(Scene::round_over(winner, &self.sim.config));
+    }
+
+    /// Runs a tick of the local game, asking the controllers of all snakes
+    /// for their inputs and recording them beforehand.
     ///
-    pub fn update_input(&mut self, time_delta: f32) {
-        self.input_timer += time_delta;
-        if self.input_timer < consts::SECS_PER_INPUT_UPDATE {
-            return;
+    fn local_tick(&mut self) -> StepEvent {
+        let inputs = controller::collect_inputs(&mut self.controllers, &self.sim);
+        for (player, _) in controller::disqualify(&self.controllers, &mut self.sim) {
+            if let Some(recording) = &mut self.recording {
+                recording.disqualify(self.sim.tick, player);
+            }
         }
+        if let Some(recording) = &mut self.recording {
+            recording.record(self.sim.tick, &inputs);
+        }
+        self.sim.tick(&inputs)
+    }
 
-        if let Some((idx, &new_dir)) =
-            it::rev(&self.inputs).find_position(|dir| !dir.is_colinear(self.snake.dir))
-        {
-            let truncated_len = self.inputs.len() - idx - 1;
-            self.inputs.truncate(truncated_len);
+    /// Runs a tick of the online game, once the inputs of all players arrived
+    /// or were predicted. Return `None` while waiting for them or when the
+    /// connection broke.
+    ///
+    fn online_tick(&mut self) -> Option<StepEvent> {
+        let client = self.online.as_mut()?;
+        for dir in self.key_queues[0].inputs(&self.sim, client.player()) {
+            client.push_input(dir);
+        }
+        let eaten = self.sim.food_eaten();
+        match client.step(&mut self.sim) {
+            Ok(event) => {
+                // Rolling back mispredicted ticks can take food away as well
+                if self.sim.food_eaten() != eaten {
+                    self.score_txt =
+                        Self::create_score_txt(&self.sim, &self.roster, self.resources.font);
+                }
+                event
+            }
+            Err(err) => {
+                eprintln!("{}", err);
+                self.end_online_game();
+                None
+            }
+        }
+    }
 
-            self.snake.dir = new_dir;
-            self.input_timer = 0.;
-        } else {
-            self.inputs.clear();
+    /// Leaves the online game, showing how the local snake did.
+    ///
+    fn end_online_game(&mut self) {
+        if let Some(client) = self.online.take() {
+            let summary = GameSummary {
+                score: self.sim.players[client.player()].score,
+                ..self.summary()
+            };
+            self.scenes.push(Scene::game_over(summary));
         }
     }
 
-    /// Updates snake (collision, movement, growth)
-    /// Upon collision with anything (self, wall, food) takes proper action.
+    /// Runs as many fixed simulation ticks as fit in `time_delta`,
+    /// locally or together with the other players of the online game.
+    /// Takes proper action upon the outcome of each tick.
     ///
-    pub fn update_snake(&mut self, time_delta: f32) {
-        if self.snake.collide(&self.food.bbox) {
-            self.snake.grow(consts::FOOD_SIZE);
-            self.inc_score();
-            while self.snake.collide(&self.food.bbox) {
-                self.food = Food::random();
+    pub fn update_sim(&mut self, time_delta: f32) {
+        for _ in 0..self.timestep.advance(time_delta) {
+            let event = match self.online.is_some() {
+                true => match self.online_tick() {
+                    Some(event) => event,
+                    None => break,
+                },
+                false => self.local_tick(),
+            };
+
+            match event {
+                StepEvent::Moved => {}
+                StepEvent::FoodEaten => {
+                    self.score_txt =
+                        Self::create_score_txt(&self.sim, &self.roster, self.resources.font);
+                }
+                StepEvent::Died if self.online.is_some() => {
+                    match self.sim.winner() {
+                        Some(winner) => println!("{} wins!", player_name(&self.sim.config, winner)),
+                        None => println!("Game over!"),
+                    }
+                    self.end_online_game();
+                    break;
+                }
+                StepEvent::Died if self.sim.players.len() > 1 && self.playback.is_none() => {
+                    self.end_round();
+                    break;
+                }
+                StepEvent::Died => {
+                    println!(
+                        "Game over! Score: {}, seed: {}",
+                        self.sim.players[0].score,
+                        self.seed()
+                    );
+                    self.finish_replays();
+                    let summary = self.summary();
+                    let qualifies = self.playback.is_none()
+                        && self.current_level().is_none()
+                        && self.sim.players.len() == 1
+                        && self.high_scores.qualifies(summary.mode, summary.score);
+                    self.scenes.push(if qualifies {
+                        Scene::enter_name(summary)
+                    } else {
+                        Scene::game_over(summary)
+                    });
+                    break;
+                }
+            }
+            if self
+                .current_level()
+                .is_some_and(|level| level.goal.is_reached(&self.sim))
+            {
+                self.complete_level();
+                break;
+            }
+            // Recorded game may have ended without dying, e.g. on a completed level
+            let playback_end = self.playback.as_ref().and_then(|replay| replay.end);
+            if playback_end.is_some_and(|end| self.sim.tick >= end.tick) {
+                self.finish_replays();
+                self.scenes.push(Scene::game_over(self.summary()));
+                break;
             }
-        } else if self.snake.self_collide() || self.snake.wall_collide() {
-            self.reset();
-        } else {
-            self.snake.do_move(time_delta * consts::SPEED);
         }
     }
+
+    fn summary(&self) -> GameSummary {
+        GameSummary {
+            score: self.sim.players[0].score,
+            length: self.sim.players[0].snake.length() / self.sim.config.snake_width,
+            secs: self.sim.elapsed_secs(),
+            seed: self.seed(),
+            mode: self.sim.config.mode,
+        }
+    }
+
+    fn finish_replays(&mut self) {
+        if let (Some(mut recording), Some(path)) = (self.recording.take(), &self.record_path) {
+            recording.finish(self.sim.tick, self.sim.players[0].score);
+            match recording.save(path) {
+                Ok(()) => println!("Replay saved to {}", path.display()),
+                Err(err) => eprintln!("Could not save replay to {}: {}", path.display(), err),
+            }
+        }
+
+        if let Some(end) = self.playback.as_ref().and_then(|replay| replay.end) {
+            println!(
+                "Replay over after {} ticks with score {}, recorded {} ticks with score {}",
+                self.sim.tick, self.sim.players[0].score, end.tick, end.score
+            );
+        }
+    }
+}
+
+/// Return what was loaded from the file under `path`, telling what went wrong if anything.
+///
+fn loaded<T, E: fmt::Display>(path: &Path, (value, err): (T, Option<E>)) -> T {
+    if let Some(err) = err {
+        eprintln!("{}: {}", path.display(), err);
+    }
+    value
 }
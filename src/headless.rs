@@ -0,0 +1,144 @@
+//! Running the game without any window
+use std::time::Duration;
+
+use ggez::GameResult;
+use snaek_core::{
+    ai, controller, rng, Controller, ExternalBot, GameConfig, HttpBot, Level, Replay, Scripted,
+    Simulation, StepEvent,
+};
+
+use crate::{
+    cli::Cli,
+    online::{self, OnlineClient},
+};
+
+/// Simulate up to `--ticks` ticks, with the snakes driven by the replay if given
+/// or by the external, HTTP and built-in bots otherwise, and print the outcome.
+/// Players without a keyboard keep going straight. Playing a level stops
+/// once its goal is reached.
+///
+pub fn run(
+    cli: &Cli,
+    config: GameConfig,
+    replay: Option<Replay>,
+    level: Option<Level>,
+) -> GameResult {
+    let seed = match &replay {
+        Some(replay) => replay.seed,
+        None => cli.seed.unwrap_or_else(rng::random_seed),
+    };
+    let mut controllers = match &replay {
+        Some(replay) => Scripted::all_from_replay(replay),
+        None => {
+            let timeout = Duration::from_millis(cli.bot_timeout);
+            let external = cli.external_bot.iter().map(|command| {
+                Box::new(ExternalBot::spawn(command, timeout)) as Box<dyn Controller>
+            });
+            let http_timeout = Duration::from_millis(cli.http_timeout);
+            let http = cli
+                .http_bot
+                .iter()
+                .map(|url| Box::new(HttpBot::new(url, http_timeout)) as Box<dyn Controller>);
+            (0..config.players)
+                .map(|_| Box::new(Scripted::default()) as Box<dyn Controller>)
+                .chain(external)
+                .chain(http)
+                .chain(ai::bots(&config).into_iter().skip(cli.remote_bot_count()))
+                .collect()
+        }
+    };
+    let mut sim = Simulation::new(config, seed);
+    let mut recording = cli.record.as_ref().map(|_| Replay::new(&sim));
+    // Recorded game may have ended without dying, e.g. on a completed level
+    let recorded_ticks = replay
+        .and_then(|replay| replay.end)
+        .map_or(u64::MAX, |end| end.tick);
+    let ticks = cli.ticks.unwrap_or(0).min(recorded_ticks);
+
+    while sim.tick < ticks {
+        let inputs = controller::collect_inputs(&mut controllers, &sim);
+        for (player, reason) in controller::disqualify(&controllers, &mut sim) {
+            println!("Snake {} disqualified: {}", player + 1, reason);
+            if let Some(recording) = &mut recording {
+                recording.disqualify(sim.tick, player);
+            }
+        }
+        if let Some(recording) = &mut recording {
+            recording.record(sim.tick, &inputs);
+        }
+
+        let died = sim.tick(&inputs) == StepEvent::Died;
+        let won = level
+            .as_ref()
+            .is_some_and(|level| level.goal.is_reached(&sim));
+        if died || won {
+            println!(
+                "{}",
+                if died {
+                    "Game over!"
+                } else {
+                    "Level complete!"
+                }
+            );
+            if let Some(recording) = &mut recording {
+                recording.finish(sim.tick, sim.players[0].score);
+            }
+            break;
+        }
+    }
+    controller::game_over(&mut controllers, &sim);
+
+    print_outcome(&sim);
+    if let (Some(recording), Some(path)) = (recording, &cli.record) {
+        recording.save(path)?;
+    }
+    Ok(())
+}
+
+/// Play up to `--ticks` ticks of the online game with a bot of the `--difficulty`
+/// steering the client's snake, and print the outcome.
+///
+pub fn run_online(cli: &Cli, mut client: OnlineClient, mut sim: Simulation) -> GameResult {
+    let player = client.player();
+    let mut bot = cli
+        .difficulty
+        .unwrap_or(sim.config.bot_difficulty)
+        .controller();
+    let ticks = cli.ticks.unwrap_or(0);
+
+    while sim.tick < ticks && !sim.is_over() {
+        for dir in bot.inputs(&sim, player) {
+            client.push_input(dir);
+        }
+        if client.wait_step(&mut sim).map_err(online::game_error)? == StepEvent::Died {
+            println!("Game over!");
+        }
+    }
+
+    print_outcome(&sim);
+    Ok(())
+}
+
+/// Print the scores of all snakes and the winner, if there is one.
+///
+fn print_outcome(sim: &Simulation) {
+    let scores: Vec<_> = sim
+        .players
+        .iter()
+        .map(|player| player.score.to_string())
+        .collect();
+    println!(
+        "Ticks: {}, score: {}, seed: {}",
+        sim.tick,
+        scores.join(" "),
+        sim.seed
+    );
+    if let Some(winner) = sim.winner() {
+        let kind = if sim.config.is_bot(winner) {
+            "bot"
+        } else {
+            "player"
+        };
+        println!("Snake {} ({}) wins!", winner + 1, kind);
+    }
+}
@@ -0,0 +1,255 @@
+//! Setting up online games together with the other players in the lobby
+//! of the server, driven from the terminal before the window opens
+use std::{
+    io::{self, BufRead},
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+use ggez::{GameError, GameResult};
+use snaek_core::{
+    lobby::{DiscoveredGame, LobbyState, Member},
+    lockstep::DISCONNECT_TIMEOUT,
+    Discovery, GameMode, LobbyClient, Simulation,
+};
+
+use crate::{
+    cli::Cli,
+    game::SNAKE_COLOR_NAMES,
+    online::{self, OnlineClient},
+};
+
+/// How long to listen for the games advertised on the local network
+const BROWSE_TIME: Duration = Duration::from_secs(2);
+/// How long to sleep between the polls of the lobby
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+const HELP: &str = "Commands: name NAME, color COLOR, ready, unready, quit\n\
+                    Host only: mode MODE, level N|none, start";
+
+/// Join the lobby of the server given with `--connect`, or of a game found
+/// on the local network, and wait there until the host starts the game.
+/// Return the game with the names and colors of its players, `None` if the
+/// player quit the lobby.
+///
+pub fn join(cli: &Cli) -> GameResult<Option<(OnlineClient, Simulation, Vec<Member>)>> {
+    let addr = match cli.server_addr() {
+        Some(addr) => addr,
+        None => pick_game()?,
+    };
+    let mut client = LobbyClient::join(&addr, DISCONNECT_TIMEOUT).map_err(online::game_error)?;
+    if let Some(name) = &cli.name {
+        client.set_name(name);
+    }
+    if let Some(color) = cli.color.as_deref().and_then(color_index) {
+        client.set_color(color);
+    }
+    println!("Joined the lobby on {}\n{}", addr, HELP);
+
+    let commands = read_commands();
+    let mut shown: Option<(usize, LobbyState)> = None;
+    let game = loop {
+        if let Some(game) = client.poll().map_err(online::game_error)? {
+            break game;
+        }
+        let lobby = (client.player(), client.lobby().clone());
+        if shown.as_ref() != Some(&lobby) {
+            print_lobby(&client);
+            shown = Some(lobby);
+        }
+        if let Ok(line) = commands.try_recv() {
+            if !run_command(&mut client, &line) {
+                return Ok(None);
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    let roster = game.roster().to_vec();
+    let (client, sim) = match cli.lockstep {
+        true => {
+            let (client, sim) = game.into_lockstep();
+            (OnlineClient::Lockstep(client), sim)
+        }
+        false => {
+            let (client, sim) = game.into_rollback();
+            (OnlineClient::Rollback(client), sim)
+        }
+    };
+    println!(
+        "Game started, playing as snake {} of {}",
+        client.player() + 1,
+        sim.players.len()
+    );
+    Ok(Some((client, sim, roster)))
+}
+
+/// Let the player pick one of the games advertised on the local network.
+/// Return the address of its server.
+///
+fn pick_game() -> GameResult<String> {
+    let mut discovery = Discovery::listen().map_err(online::game_error)?;
+    println!("Looking for games on the local network...");
+    thread::sleep(BROWSE_TIME);
+    discovery.poll().map_err(online::game_error)?;
+
+    let games = discovery.games();
+    let game = match games {
+        [] => {
+            let err = io::Error::other("No games found on the local network");
+            return Err(GameError::from(err));
+        }
+        [game] => game,
+        games => {
+            for (idx, game) in games.iter().enumerate() {
+                println!("{}) {}", idx + 1, describe(game));
+            }
+            println!("Pick the game to join:");
+            loop {
+                let mut line = String::new();
+                if io::stdin().lock().read_line(&mut line)? == 0 {
+                    return Err(GameError::from(io::Error::other("No game picked")));
+                }
+                match line.trim().parse::<usize>() {
+                    Ok(idx) if (1..=games.len()).contains(&idx) => break &games[idx - 1],
+                    _ => println!("Expected a number from 1 to {}", games.len()),
+                }
+            }
+        }
+    };
+    println!("Joining {}", describe(game));
+    Ok(game.addr.to_string())
+}
+
+fn describe(game: &DiscoveredGame) -> String {
+    let advert = &game.advert;
+    let mut text = format!(
+        "{} on {}, {}/{} players, {}",
+        advert.name, game.addr, advert.players, advert.slots, advert.mode
+    );
+    if let Some(level) = &advert.level {
+        text.push_str(&format!(", level {}", level));
+    }
+    text
+}
+
+/// Reads the lines typed in on a thread of its own, so the lobby
+/// keeps going while waiting for them.
+///
+fn read_commands() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+fn color_index(name: &str) -> Option<u8> {
+    SNAKE_COLOR_NAMES
+        .iter()
+        .position(|&color| color == name)
+        .map(|color| color as u8)
+}
+
+fn color_name(color: u8) -> &'static str {
+    SNAKE_COLOR_NAMES[color as usize % SNAKE_COLOR_NAMES.len()]
+}
+
+fn print_lobby(client: &LobbyClient) {
+    let lobby = client.lobby();
+    let level = match lobby.level {
+        Some(level) => &lobby.levels[level],
+        None => "none",
+    };
+    println!(
+        "\nLobby: {}/{} players, mode {}, level {}",
+        lobby.members.len(),
+        lobby.slots,
+        lobby.mode,
+        level
+    );
+    for (player, member) in lobby.members.iter().enumerate() {
+        let mut line = format!(
+            "  {}. {} ({})",
+            player + 1,
+            member.name,
+            color_name(member.color)
+        );
+        if member.ready {
+            line.push_str(" ready");
+        }
+        if player == 0 {
+            line.push_str(" [host]");
+        }
+        if player == client.player() {
+            line.push_str(" <- you");
+        }
+        println!("{}", line);
+    }
+    if let Some(problem) = &lobby.problem {
+        println!("{}", problem);
+    }
+    if client.is_host() && !lobby.levels.is_empty() {
+        let levels: Vec<_> = lobby
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(idx, name)| format!("{}) {}", idx + 1, name))
+            .collect();
+        println!("Levels: {}", levels.join(", "));
+    }
+}
+
+/// Runs the command typed in by the player. Return `false` once the player quits.
+///
+fn run_command(client: &mut LobbyClient, line: &str) -> bool {
+    let line = line.trim();
+    let (command, arg) = match line.split_once(' ') {
+        Some((command, arg)) => (command, arg.trim()),
+        None => (line, ""),
+    };
+    let (mode, level) = client.settings();
+    match command {
+        "" => {}
+        "quit" => return false,
+        "name" if !arg.is_empty() => client.set_name(arg),
+        "color" => match color_index(arg) {
+            Some(color) => client.set_color(color),
+            None => println!(
+                "Unknown color '{}', expected one of: {}",
+                arg,
+                SNAKE_COLOR_NAMES.join(", ")
+            ),
+        },
+        "ready" => client.set_ready(true),
+        "unready" => client.set_ready(false),
+        "mode" | "level" | "start" if !client.is_host() => {
+            println!("Only the host can do that")
+        }
+        "mode" => match arg.parse::<GameMode>() {
+            Ok(mode) => client.choose(mode, level),
+            Err(err) => println!("{}", err),
+        },
+        "level" if arg == "none" => client.choose(mode, None),
+        "level" => {
+            let levels = client.lobby().levels.len();
+            match arg.parse::<usize>() {
+                Ok(level) if (1..=levels).contains(&level) => client.choose(mode, Some(level - 1)),
+                _ if levels == 0 => println!("The server has no levels"),
+                _ => println!("Expected none or a number from 1 to {}", levels),
+            }
+        }
+        "start" => {
+            client.start();
+            if !client.lobby().all_ready() {
+                println!("The game starts once everybody is ready");
+            }
+        }
+        _ => println!("{}", HELP),
+    }
+    true
+}
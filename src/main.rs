@@ -8,28 +8,98 @@
 //!
 //! Uses [ggez crate](https://crates.io/crates/ggez) for game engine related stuff.
 
-use game::{GameData, GAME_AUTHOR, GAME_ID, SCREEN_SIZE};
+use clap::Parser;
+use cli::Cli;
+use game::{GameData, SpectatorView, CAMPAIGN_FILE, GAME_AUTHOR, GAME_ID};
 use ggez::{
     conf,
     event::{self},
+    graphics,
 };
 use ggez::{ContextBuilder, GameResult};
-use std::path;
+use snaek_core::Spectator;
+mod cli;
 mod game;
+mod headless;
+mod lobby;
+mod online;
+mod tournament;
 
-/// Main function that sets-up the window, creates GameData and runs the main game loop.
+/// Main function that parses the command line, sets-up the window,
+/// creates GameData and runs the main game loop.
 ///
 fn main() -> GameResult {
+    let cli = Cli::parse();
+    let replay = cli.load_replay()?;
+    let level = cli.load_level()?;
+    let online = match cli.server_addr() {
+        _ if cli.lobby => match lobby::join(&cli)? {
+            Some(game) => Some(game),
+            None => return Ok(()),
+        },
+        Some(addr) => {
+            let (client, sim) = online::connect(&addr, !cli.lockstep && !cli.headless)?;
+            Some((client, sim, Vec::new()))
+        }
+        None => None,
+    };
+    let spectator = match cli.spectator_addr() {
+        Some(addr) => Some(Spectator::connect(addr).map_err(online::game_error)?),
+        None => None,
+    };
+    let config = match &online {
+        Some((_, sim, _)) => sim.config.clone(),
+        None => cli.game_config(replay.as_ref(), level.as_ref())?,
+    };
+
+    if cli.headless {
+        return match online {
+            Some((client, sim, _)) => headless::run_online(&cli, client, sim),
+            None => headless::run(&cli, config, replay, level),
+        };
+    }
+    if let Some(path) = &cli.tournament {
+        return tournament::run(&cli, path, &config);
+    }
+
     let window_setup = conf::WindowSetup::default().title(GAME_ID);
-    let window_mode = conf::WindowMode::default().dimensions(SCREEN_SIZE.x, SCREEN_SIZE.y);
-    let resource_path = path::PathBuf::from("./resources");
+    let mut window_mode =
+        conf::WindowMode::default().dimensions(config.screen_width, config.screen_height);
+    if cli.fullscreen {
+        window_mode = window_mode.fullscreen_type(conf::FullscreenType::Desktop);
+    }
 
     let (ref mut ctx, ref mut event_loop) = ContextBuilder::new(GAME_ID, GAME_AUTHOR)
         .window_setup(window_setup)
         .window_mode(window_mode)
-        .add_resource_path(resource_path)
+        .add_resource_path(cli.resources.clone())
         .build()?;
+    graphics::set_screen_coordinates(
+        ctx,
+        graphics::Rect::new(0., 0., config.screen_width, config.screen_height),
+    )?;
+
+    if let Some(spectator) = spectator {
+        let view = &mut SpectatorView::new(ctx, spectator);
+        return event::run(ctx, event_loop, view);
+    }
 
-    let game_state = &mut GameData::new(ctx);
+    let game_state = &mut GameData::new(ctx, config, cli.seed);
+    game_state.load_campaign(&cli.resources.join(CAMPAIGN_FILE));
+    if let Some(path) = cli.record {
+        game_state.record_to(path);
+    }
+    if let Some(replay) = replay {
+        game_state.play_replay(replay);
+    }
+    if let Some(level) = level {
+        game_state.play_level(level);
+    }
+    if let Some((client, sim, roster)) = online {
+        game_state.play_online(client, sim, roster);
+    }
+    if let Some(path) = cli.save {
+        game_state.save_to(ctx, path);
+    }
     event::run(ctx, event_loop, game_state)
 }
@@ -0,0 +1,87 @@
+//! Joining online games run by a dedicated server
+use std::{io, thread, time::Duration};
+
+use ggez::{GameError, GameResult};
+use snaek_core::{Direction, LockstepClient, NetError, RollbackClient, Simulation, StepEvent};
+
+/// How long to wait for the other players to join
+const LOBBY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Convert the network error into the one of the game.
+///
+pub fn game_error(err: NetError) -> GameError {
+    match err {
+        NetError::Io(err) => GameError::from(err),
+        err => GameError::from(io::Error::other(err.to_string())),
+    }
+}
+
+/// How long to sleep while a rollback client waits for the server
+const WAIT_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Client of an online game, either waiting for the inputs of the other
+/// players or predicting them and rolling back the mispredicted ticks
+///
+pub enum OnlineClient {
+    Lockstep(LockstepClient),
+    Rollback(RollbackClient),
+}
+
+impl OnlineClient {
+    pub fn player(&self) -> usize {
+        match self {
+            OnlineClient::Lockstep(client) => client.player(),
+            OnlineClient::Rollback(client) => client.player(),
+        }
+    }
+
+    pub fn push_input(&mut self, dir: Direction) {
+        match self {
+            OnlineClient::Lockstep(client) => client.push_input(dir),
+            OnlineClient::Rollback(client) => client.push_input(dir),
+        }
+    }
+
+    /// Runs the next tick of `sim` if it can be run without waiting.
+    ///
+    pub fn step(&mut self, sim: &mut Simulation) -> Result<Option<StepEvent>, NetError> {
+        match self {
+            OnlineClient::Lockstep(client) => client.step(sim),
+            OnlineClient::Rollback(client) => client.step(sim),
+        }
+    }
+
+    /// Runs the next tick of `sim`, waiting as long as needed.
+    ///
+    pub fn wait_step(&mut self, sim: &mut Simulation) -> Result<StepEvent, NetError> {
+        match self {
+            OnlineClient::Lockstep(client) => client.wait_step(sim),
+            OnlineClient::Rollback(client) => loop {
+                if let Some(event) = client.step(sim)? {
+                    return Ok(event);
+                }
+                thread::sleep(WAIT_INTERVAL);
+            },
+        }
+    }
+}
+
+/// Join the game of the server at `addr`, once all of its players are there.
+/// With `rollback` the client does not wait for the inputs of the others.
+///
+pub fn connect(addr: &str, rollback: bool) -> GameResult<(OnlineClient, Simulation)> {
+    println!("Joining {}, waiting for the other players...", addr);
+    let (client, sim) = match rollback {
+        true => RollbackClient::connect(addr, LOBBY_TIMEOUT)
+            .map(|(client, sim)| (OnlineClient::Rollback(client), sim)),
+        false => LockstepClient::connect(addr, LOBBY_TIMEOUT)
+            .map(|(client, sim)| (OnlineClient::Lockstep(client), sim)),
+    }
+    .map_err(game_error)?;
+    println!(
+        "Playing as snake {} of {}",
+        client.player() + 1,
+        sim.players.len()
+    );
+    Ok((client, sim))
+}
@@ -0,0 +1,51 @@
+//! Running a tournament between bots without any window
+use std::{path::Path, thread, time::Duration};
+
+use ggez::{GameError, GameResult};
+use snaek_core::{Broadcaster, GameConfig, Tournament, TournamentError};
+
+use crate::{cli::Cli, online};
+
+/// How long the broadcast stays up after the last game, for the spectators to get its end
+const LINGER: Duration = Duration::from_secs(1);
+
+/// Play the tournament from the file under `path` with games set up by `config`,
+/// print the standings and save the results into all `--results` files.
+///
+pub fn run(cli: &Cli, path: &Path, config: &GameConfig) -> GameResult {
+    let tournament = Tournament::load(path)
+        .map_err(|err| GameError::ResourceLoadError(format!("{}: {}", path.display(), err)))?;
+    let threads = cli
+        .threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |threads| threads.get()));
+
+    let broadcaster = match cli.broadcast_addr() {
+        Some(addr) => {
+            let delay = Duration::from_secs(cli.broadcast_delay);
+            let broadcaster = Broadcaster::bind(&addr, delay).map_err(online::game_error)?;
+            println!("Streaming the games to spectators on {}", addr);
+            Some(broadcaster)
+        }
+        None => None,
+    };
+    let replay_dir = cli.replay_dir.as_deref();
+
+    let results = match &broadcaster {
+        Some(broadcaster) => tournament.run_broadcast(config, threads, replay_dir, broadcaster),
+        None => tournament.run(config, threads, replay_dir),
+    }
+    .map_err(|err| match err {
+        TournamentError::Io(err) => GameError::from(err),
+        err => GameError::ConfigError(err.to_string()),
+    })?;
+    if broadcaster.is_some() {
+        // Spectators are behind, let them see the last games to the end
+        thread::sleep(Duration::from_secs(cli.broadcast_delay) + LINGER);
+    }
+    print!("{}", results);
+    for path in &cli.results {
+        results.save(path)?;
+        println!("Results saved to {}", path.display());
+    }
+    Ok(())
+}